@@ -28,7 +28,7 @@ struct Dependency {
 }
 
 #[unsafe(no_mangle)]
-pub fn main() {
+pub fn main() -> isize {
     let dependencies_file = include_str!("dependencies.json");
     let dependencies: Vec<Dependency> = serde_json::from_str(dependencies_file).unwrap();
 
@@ -65,7 +65,7 @@ pub fn main() {
             match input {
                 Some('q') | Some('Q') | None => {
                     print!("\n");
-                    return; // Exit the application
+                    return 0; // Exit the application
                 },
                 Some('\n') => {
                     break; // Proceed to the next dependency
@@ -74,4 +74,6 @@ pub fn main() {
             }
         }
     }
+
+    0
 }
\ No newline at end of file
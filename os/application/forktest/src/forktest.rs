@@ -0,0 +1,126 @@
+#![no_std]
+
+extern crate alloc;
+
+use alloc::vec;
+use concurrent::process::{self, ForkResult};
+use concurrent::thread;
+use core::hint::black_box;
+use core::ptr;
+use core::sync::atomic::{AtomicBool, AtomicUsize};
+use core::sync::atomic::Ordering::Relaxed;
+use runtime::mman::{self, MapFlags, Protection};
+#[allow(unused_imports)]
+use runtime::*;
+use terminal::{print, println};
+
+const PAGE_SIZE: usize = 0x1000;
+
+/// Exit status of a child, whose checks have failed
+const CHILD_FAILED: isize = 1;
+
+/// Exit status of a child in the exit status test
+const EXIT_STATUS: isize = 42;
+
+/// Number of children forked one after another in the repeat test
+const CHILDREN: usize = 16;
+
+static VALUE: AtomicUsize = AtomicUsize::new(0);
+
+/// Address of a local variable on the user stack of the helper thread in the thread test
+static OTHER_STACK: AtomicUsize = AtomicUsize::new(0);
+static RELEASE: AtomicBool = AtomicBool::new(false);
+
+#[unsafe(no_mangle)]
+pub fn main() -> isize {
+    println!("Testing fork");
+
+    let results = [
+        test_exit_status(),
+        test_copy_on_write(),
+        test_heap(),
+        test_threads(),
+        test_repeat(),
+    ];
+
+    let failed = results.iter().filter(|&&ok| !ok).count();
+    if failed > 0 {
+        println!("[{}] tests failed!", failed);
+        return 1;
+    }
+
+    println!("All tests passed.");
+    0
+}
+
+/// Fork a child executing `child` and exiting with its return value. Returns the exit status of the child.
+fn run_child(child: impl FnOnce() -> isize) -> Option<isize> {
+    match process::fork() {
+        Ok(ForkResult::Child) => process::exit(child()),
+        Ok(ForkResult::Parent(child)) => child.wait().ok(),
+        Err(_) => None,
+    }
+}
+
+fn report(name: &str, ok: bool) -> bool {
+    println!("  {:<12} {}", name, if ok { "ok" } else { "FAILED" });
+    ok
+}
+
+fn test_exit_status() -> bool {
+    report("Exit status", run_child(|| EXIT_STATUS) == Some(EXIT_STATUS))
+}
+
+fn test_copy_on_write() -> bool {
+    // The child sees the values of the parent at the time of the fork, but its writes remain private
+    VALUE.store(1, Relaxed);
+    let status = run_child(|| {
+        let ok = VALUE.load(Relaxed) == 1;
+        VALUE.store(2, Relaxed);
+        if ok && VALUE.load(Relaxed) == 2 { 0 } else { CHILD_FAILED }
+    });
+
+    report("Copy-on-write", status == Some(0) && VALUE.load(Relaxed) == 1)
+}
+
+fn test_heap() -> bool {
+    let mut buffer = vec![1u8; 4 * PAGE_SIZE];
+    let status = run_child(|| {
+        let ok = buffer.iter().all(|&byte| byte == 1);
+        buffer.fill(2);
+        if ok { 0 } else { CHILD_FAILED }
+    });
+
+    report("Heap", status == Some(0) && buffer.iter().all(|&byte| byte == 1))
+}
+
+fn test_threads() -> bool {
+    // The helper thread keeps its user stack in use, while the calling thread forks
+    let helper = thread::create(|| {
+        let local = black_box(0usize);
+        OTHER_STACK.store(ptr::from_ref(&local) as usize, Relaxed);
+        while !RELEASE.load(Relaxed) {
+            thread::sleep(10);
+        }
+        black_box(&local);
+    }).expect("Failed to create thread");
+    while OTHER_STACK.load(Relaxed) == 0 {
+        thread::switch();
+    }
+
+    // Only the calling thread exists in the child, so the stack of the helper thread must not be mapped there
+    let stack_page = OTHER_STACK.load(Relaxed) & !(PAGE_SIZE - 1);
+    let map_stack_page = || mman::mmap(stack_page, PAGE_SIZE, Protection::READ | Protection::WRITE, MapFlags::ANONYMOUS | MapFlags::FIXED, 0, 0);
+    let status = run_child(|| if map_stack_page().is_ok() { 0 } else { CHILD_FAILED });
+    let mapped_in_parent = map_stack_page().is_ok();
+
+    RELEASE.store(true, Relaxed);
+    helper.join();
+
+    report("Threads", status == Some(0) && !mapped_in_parent)
+}
+
+fn test_repeat() -> bool {
+    let ok = (0..CHILDREN).all(|i| run_child(|| i as isize) == Some(i as isize));
+    report("Repeat", ok)
+}
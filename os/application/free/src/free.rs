@@ -0,0 +1,82 @@
+#![no_std]
+
+extern crate alloc;
+
+use alloc::string::String;
+use alloc::vec::Vec;
+use runtime::env;
+use runtime::meminfo::{self, Zone, ZoneInfo, NUM_ORDERS};
+#[allow(unused_imports)]
+use runtime::*;
+use terminal::{print, println};
+
+const FRAME_SIZE_KIB: usize = 4;
+
+fn print_usage() {
+    println!("usage: free [-b]");
+    println!("  -b  show the number of free blocks per order (order n -> 2^n page frames)");
+}
+
+fn print_zone(name: &str, info: &ZoneInfo) {
+    println!("{:<8}{:>14}{:>14}{:>14}{:>14}",
+        name,
+        info.total_frames * FRAME_SIZE_KIB,
+        info.used_frames() * FRAME_SIZE_KIB,
+        info.free_frames * FRAME_SIZE_KIB,
+        info.largest_free_block * FRAME_SIZE_KIB);
+}
+
+fn print_free_blocks(name: &str, info: &ZoneInfo) {
+    print!("{:<8}", name);
+    for count in info.free_blocks {
+        print!("{:>6}", count);
+    }
+    println!("");
+}
+
+#[unsafe(no_mangle)]
+pub fn main() -> isize {
+    let args: Vec<String> = env::args().collect();
+    let show_blocks = match args.len() {
+        1 => false,
+        2 if args[1] == "-b" => true,
+        _ => {
+            print_usage();
+            return -1;
+        }
+    };
+
+    let info = match meminfo::mem_info() {
+        Ok(info) => info,
+        Err(errno) => {
+            println!("free: failed to get memory statistics ({:?})", errno);
+            return -1;
+        }
+    };
+
+    println!("{:<8}{:>14}{:>14}{:>14}{:>14}", "KiB", "total", "used", "free", "largest");
+    let mut sum = ZoneInfo::default();
+    for zone in Zone::ALL {
+        let zone_info = info.zone(zone);
+        print_zone(zone.name(), zone_info);
+
+        sum.total_frames += zone_info.total_frames;
+        sum.free_frames += zone_info.free_frames;
+        sum.largest_free_block = sum.largest_free_block.max(zone_info.largest_free_block);
+    }
+    print_zone("Total", &sum);
+
+    if show_blocks {
+        println!("");
+        print!("{:<8}", "Order");
+        for order in 0..NUM_ORDERS {
+            print!("{:>6}", order);
+        }
+        println!("");
+        for zone in Zone::ALL {
+            print_free_blocks(zone.name(), info.zone(zone));
+        }
+    }
+
+    0
+}
@@ -7,7 +7,7 @@ use runtime::*;
 use terminal::{print, println, read::read};
 
 #[unsafe(no_mangle)]
-fn main() {
+fn main() -> isize {
     let mut allocations = Vec::new();
 
     println!("heap test");
@@ -32,4 +32,6 @@ fn main() {
             }
         }
     }
+
+    0
 }
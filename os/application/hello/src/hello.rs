@@ -9,7 +9,7 @@ use terminal::{print, println};
 
 
 #[unsafe(no_mangle)]
-pub fn main() {
+pub fn main() -> isize {
     let process = process::current().unwrap();
     let thread = thread::current().unwrap();
 
@@ -32,4 +32,5 @@ pub fn main() {
         println!("Failed to create second thread");
     }
 
+    0
 }
\ No newline at end of file
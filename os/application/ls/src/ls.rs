@@ -60,7 +60,7 @@ pub fn args_to_vec() -> Vec<String> {
 }
 
 #[unsafe(no_mangle)]
-pub fn main() {
+pub fn main() -> isize {
     let args_vec = args_to_vec();
     let args_count = args_vec.len();
 
@@ -76,4 +76,6 @@ pub fn main() {
     } else {
         print_usage();
     }
+
+    0
 }
@@ -8,7 +8,7 @@ use terminal::{print, println};
 use naming::mkdir;
 
 #[unsafe(no_mangle)]
-pub fn main() {
+pub fn main() -> isize {
     let args = env::args();
     for (i, arg) in args.enumerate() {
         println!("Arg[{}]: {}", i, arg);
@@ -17,4 +17,6 @@ pub fn main() {
     let res = mkdir("/home/schoettner");
 
     println!("app: mkdir {:?}", res);
+
+    0
 }
\ No newline at end of file
@@ -8,14 +8,14 @@ use runtime::*;
 use terminal::{print, println};
 
 #[unsafe(no_mangle)]
-pub fn main() {
+pub fn main() -> isize {
     println!("naming test: start");
 
     // opening file
     let res = naming::open("/file.txt", OpenOptions::READWRITE | OpenOptions::CREATE);
     if res.is_err() {
         println!("open error = {:?}", res);
-        return;
+        return 1;
     }
     let fd = res.unwrap();
 
@@ -71,7 +71,7 @@ pub fn main() {
     let res = naming::open("/test", OpenOptions::DIRECTORY);
     if res.is_err() {
         println!("open error = {:?}", res);
-        return;
+        return 1;
     }
     let fd = res.unwrap();
     println!("open dir '/test'");
@@ -96,4 +96,6 @@ pub fn main() {
     println!("close result = {:?}", close_res);
 
     println!("naming test: end");
+
+    0
 }
@@ -0,0 +1,188 @@
+/* ╔═════════════════════════════════════════════════════════════════════════╗
+   ║ Module: parser                                                          ║
+   ╟─────────────────────────────────────────────────────────────────────────╢
+   ║ Parse a command line into a pipeline of commands. Supported operators:  ║
+   ║ '|' (pipe), '<' (stdin from file), '>' and '>>' (stdout to file, trunc. ║
+   ║ or append), '2>' and '2>>' (stderr to file). Operators do not need to   ║
+   ║ be separated by spaces, e.g. "ls>out" is valid. Environment variables   ║
+   ║ ($NAME) and the exit status of the previous pipeline ($?) are expanded  ║
+   ║ within words. Pipelines can be combined into a list with '&&' and '||'. ║
+   ╟─────────────────────────────────────────────────────────────────────────╢
+   ║ Author: agent, 18.10.2026                                               ║
+   ╚═════════════════════════════════════════════════════════════════════════╝
+*/
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use runtime::env;
+
+#[derive(Debug)]
+enum Token {
+    Word(String),
+    Pipe,
+    RedirectIn,
+    RedirectOut { append: bool },
+    RedirectErr { append: bool },
+}
+
+/// Output redirection into the file `path`
+#[derive(Debug)]
+pub struct Redirect {
+    pub path: String,
+    pub append: bool,
+}
+
+/// One stage of a pipeline
+#[derive(Debug, Default)]
+pub struct Command {
+    pub args: Vec<String>,
+    pub stdin: Option<String>,
+    pub stdout: Option<Redirect>,
+    pub stderr: Option<Redirect>,
+}
+
+impl Command {
+    pub fn is_redirected(&self) -> bool {
+        self.stdin.is_some() || self.stdout.is_some() || self.stderr.is_some()
+    }
+}
+
+/// Condition for running a pipeline of a list, depending on the exit status of the previous one
+#[derive(Debug, Clone, Copy)]
+pub enum Condition {
+    Always,    // first pipeline
+    IfSuccess, // after '&&'
+    IfFailure, // after '||'
+}
+
+/// Split `line` at '&&' and '||' into pipelines (empty, if the line is empty). \
+/// The pipelines are checked for syntax errors, but must be parsed with `parse` right before they are run.
+pub fn split_list(line: &str) -> Result<Vec<(Condition, &str)>, &'static str> {
+    let mut list = Vec::new();
+    if line.trim().is_empty() {
+        return Ok(list);
+    }
+
+    let mut condition = Condition::Always;
+    let mut rest = line;
+    loop {
+        let next = [("&&", Condition::IfSuccess), ("||", Condition::IfFailure)]
+            .into_iter()
+            .filter_map(|(operator, condition)| rest.find(operator).map(|pos| (pos, condition)))
+            .min_by_key(|(pos, _)| *pos);
+
+        let pipeline = match next {
+            Some((pos, _)) => &rest[..pos],
+            None => rest,
+        };
+        if parse(pipeline, 0)?.is_empty() {
+            return Err("missing command around '&&' or '||'");
+        }
+        list.push((condition, pipeline));
+
+        match next {
+            Some((pos, next_condition)) => {
+                condition = next_condition;
+                rest = &rest[pos + 2..];
+            }
+            None => return Ok(list),
+        }
+    }
+}
+
+/// Split `line` into words and operators, undefined variables expand to nothing
+fn tokenize(line: &str, last_status: isize) -> Vec<Token> {
+    let mut tokens = Vec::new();
+    let mut word = String::new();
+    let mut chars = line.chars().peekable();
+
+    while let Some(ch) = chars.next() {
+        match ch {
+            '|' | '<' | '>' => {
+                // "2>" redirects stderr, if the '2' is a word on its own (e.g. "ls 2>err", but not "ls file2>out")
+                let stderr = ch == '>' && word == "2";
+                if stderr {
+                    word.clear();
+                }
+                if !word.is_empty() {
+                    tokens.push(Token::Word(core::mem::take(&mut word)));
+                }
+
+                let token = match ch {
+                    '|' => Token::Pipe,
+                    '<' => Token::RedirectIn,
+                    _ => {
+                        let append = chars.next_if_eq(&'>').is_some();
+                        if stderr { Token::RedirectErr { append } } else { Token::RedirectOut { append } }
+                    }
+                };
+                tokens.push(token);
+            }
+            '$' if chars.next_if_eq(&'?').is_some() => word.push_str(&last_status.to_string()),
+            '$' => {
+                // The value is part of the word, so it is not split or parsed for operators
+                let mut name = String::new();
+                while let Some(ch) = chars.next_if(|ch| ch.is_ascii_alphanumeric() || *ch == '_') {
+                    name.push(ch);
+                }
+                if name.is_empty() {
+                    word.push('$');
+                } else if let Some(value) = env::var(&name) {
+                    word.push_str(&value);
+                }
+            }
+            ch if ch.is_whitespace() => {
+                if !word.is_empty() {
+                    tokens.push(Token::Word(core::mem::take(&mut word)));
+                }
+            }
+            ch => word.push(ch),
+        }
+    }
+
+    if !word.is_empty() {
+        tokens.push(Token::Word(word));
+    }
+    tokens
+}
+
+/// Parse `line` into the commands of a pipeline (empty, if the line is empty). \
+/// `last_status` is the exit status of the previous pipeline, used for expanding `$?`.
+pub fn parse(line: &str, last_status: isize) -> Result<Vec<Command>, &'static str> {
+    let mut commands = Vec::new();
+    let mut command = Command::default();
+    let mut tokens = tokenize(line, last_status).into_iter();
+
+    if tokens.len() == 0 {
+        return Ok(commands);
+    }
+
+    while let Some(token) = tokens.next() {
+        match token {
+            Token::Word(word) => command.args.push(word),
+            Token::Pipe => {
+                if command.args.is_empty() {
+                    return Err("missing command before '|'");
+                }
+                commands.push(core::mem::take(&mut command));
+            }
+            redirect => {
+                let path = match tokens.next() {
+                    Some(Token::Word(path)) => path,
+                    _ => return Err("missing file name after redirection"),
+                };
+                match redirect {
+                    Token::RedirectIn => command.stdin = Some(path),
+                    Token::RedirectOut { append } => command.stdout = Some(Redirect { path, append }),
+                    Token::RedirectErr { append } => command.stderr = Some(Redirect { path, append }),
+                    _ => unreachable!(),
+                }
+            }
+        }
+    }
+
+    if command.args.is_empty() {
+        return Err("missing command");
+    }
+    commands.push(command);
+    Ok(commands)
+}
@@ -2,82 +2,334 @@
 
 extern crate alloc;
 
+mod parser;
+
+use alloc::format;
 use alloc::string::String;
 use alloc::vec::Vec;
+use concurrent::process::Process;
+use concurrent::signal::{self, Signal};
 use concurrent::thread;
-use naming::{mkdir, touch, cwd, cd};
+use naming::shared_types::{OpenOptions, SeekOrigin};
+use naming::{mkdir, touch, cwd, cd, mount, umount, open, seek, close, pipe};
+use parser::{Command, Condition, Redirect};
 #[allow(unused_imports)]
 use runtime::*;
+use runtime::env;
 use terminal::read::read;
-use terminal::{print, println};
+use terminal::{print, println, STDERR, STDIN, STDOUT};
+
 
+/// Exit status of builtins and commands
+const STATUS_SUCCESS: isize = 0;
+const STATUS_FAILURE: isize = 1;
+const STATUS_NOT_FOUND: isize = 127;
 
-fn process_pwd(split: &[&str]) {
+fn process_pwd(split: &[&str]) -> isize {
     if split.len() != 1 {
         println!("usage: pwd");
-        return ;
+        return STATUS_FAILURE;
     }
     let res = cwd();
     match res {
-        Ok(path) =>  println!("{}", path),
-        Err(_) => println!("usage: pwd"),
+        Ok(path) => {
+            println!("{}", path);
+            STATUS_SUCCESS
+        }
+        Err(_) => {
+            println!("usage: pwd");
+            STATUS_FAILURE
+        }
     }
 }
 
-fn process_mkdir(split: &[&str]) {
+fn process_mkdir(split: &[&str]) -> isize {
     if split.len() != 2 {
         println!("usage: mkdir directory_name");
-        return ;
+        return STATUS_FAILURE;
     }
     let res = mkdir(split[1]);
     if res.is_err() {
         println!("usage: mkdir directory_name");
+        return STATUS_FAILURE;
     }
+    STATUS_SUCCESS
 }
 
-fn process_cd(split: &[&str]) {
+fn process_cd(split: &[&str]) -> isize {
     if split.len() != 2 {
         println!("usage: cd directory_name");
-        return ;
+        return STATUS_FAILURE;
     }
     let res = cd(split[1]);
     if res.is_err() {
         println!("usage: cd directory_name");
+        return STATUS_FAILURE;
+    }
+    STATUS_SUCCESS
+}
+
+fn process_mount(split: &[&str]) -> isize {
+    if split.len() != 4 {
+        println!("usage: mount fs_type device directory_name");
+        return STATUS_FAILURE;
+    }
+    let res = mount(split[2], split[3], split[1]);
+    if let Err(e) = res {
+        println!("mount failed: {:?}", e);
+        return STATUS_FAILURE;
+    }
+    STATUS_SUCCESS
+}
+
+fn process_umount(split: &[&str]) -> isize {
+    if split.len() != 2 {
+        println!("usage: umount directory_name");
+        return STATUS_FAILURE;
+    }
+    let res = umount(split[1]);
+    if let Err(e) = res {
+        println!("umount failed: {:?}", e);
+        return STATUS_FAILURE;
+    }
+    STATUS_SUCCESS
+}
+
+fn process_touch(split: &[&str]) -> isize {
+    if split.len() != 2 {
+        println!("usage: touch file_name");
+        return STATUS_FAILURE;
+    }
+    let res = touch(split[1]);
+    if res.is_err() {
+        println!("{:?}", res);
+        return STATUS_FAILURE;
+    }
+    STATUS_SUCCESS
+}
+
+fn process_export(split: &[&str]) -> isize {
+    if split.len() < 2 {
+        println!("usage: export name=value ...");
+        return STATUS_FAILURE;
+    }
+    let mut status = STATUS_SUCCESS;
+    for assignment in &split[1..] {
+        match assignment.split_once('=') {
+            Some((name, value)) if !name.is_empty() => env::set_var(name, value),
+            _ => {
+                println!("usage: export name=value ...");
+                status = STATUS_FAILURE;
+            }
+        }
+    }
+    status
+}
+
+fn process_env(split: &[&str]) -> isize {
+    if split.len() != 1 {
+        println!("usage: env");
+        return STATUS_FAILURE;
+    }
+    for (name, value) in env::vars() {
+        println!("{}={}", name, value);
     }
+    STATUS_SUCCESS
 }
 
-fn process_internal_command(split: &Vec<&str>) -> bool {
-    if split[0] == "pwd" {
-        process_pwd(split);
-        return true;
-    } else if split[0] == "cd" {
-        process_cd(split);
-        return true;
-    } else if split[0] == "mkdir" {
-        process_mkdir(split);
-        return true;
-    } else if split[0] == "touch" {
-        let res = touch(split[1]);
-        if res.is_err() {
-            println!("{:?}", res);
+fn process_kill(split: &[&str]) -> isize {
+    let (signal, pids) = match split.get(1) {
+        Some(&"-INT") => (Signal::SIGINT, &split[2..]),
+        Some(&"-KILL") => (Signal::SIGKILL, &split[2..]),
+        Some(&"-TERM") => (Signal::SIGTERM, &split[2..]),
+        _ => (Signal::SIGTERM, &split[1..]),
+    };
+    if pids.is_empty() {
+        println!("usage: kill [-INT|-TERM|-KILL] pid ...");
+        return STATUS_FAILURE;
+    }
+
+    let mut status = STATUS_SUCCESS;
+    for pid in pids {
+        let Ok(id) = pid.parse::<usize>() else {
+            println!("kill: invalid pid '{}'", pid);
+            status = STATUS_FAILURE;
+            continue;
+        };
+        if let Err(e) = signal::kill(id, signal) {
+            println!("kill {} failed: {:?}", id, e);
+            status = STATUS_FAILURE;
         }
-        return true;
     }
-    false
+    status
 }
 
-fn process_next_char(line: &mut String, ch: char) {
-    match ch {
-        '\n' => {
-            let split = line.split_whitespace().collect::<Vec<&str>>();
-            if !split.is_empty() {
-                if !process_internal_command(&split) {
-                    match thread::start_application(split[0], split[1..].to_vec()) {
-                        Some(app) => app.join(),
-                        None => println!("Command not found!"),
-                    }
+/// Return the function implementing the builtin `name`, if it is one
+fn internal_command(name: &str) -> Option<fn(&[&str]) -> isize> {
+    match name {
+        "pwd" => Some(process_pwd),
+        "cd" => Some(process_cd),
+        "mkdir" => Some(process_mkdir),
+        "mount" => Some(process_mount),
+        "umount" => Some(process_umount),
+        "touch" => Some(process_touch),
+        "export" => Some(process_export),
+        "env" => Some(process_env),
+        "kill" => Some(process_kill),
+        _ => None,
+    }
+}
+
+/// Open the file for an output redirection (truncate or append)
+fn open_output(redirect: &Redirect) -> Result<usize, String> {
+    let flags = if redirect.append {
+        OpenOptions::READWRITE | OpenOptions::CREATE
+    } else {
+        OpenOptions::READWRITE | OpenOptions::CREATE | OpenOptions::TRUNCATE
+    };
+
+    let fh = open(&redirect.path, flags).map_err(|e| format!("{}: {:?}", redirect.path, e))?;
+    if redirect.append {
+        if let Err(e) = seek(fh, 0, SeekOrigin::End) {
+            let _ = close(fh);
+            return Err(format!("{}: {:?}", redirect.path, e));
+        }
+    }
+    Ok(fh)
+}
+
+/// Open the redirected files of `command` and start it with `handles` as stdin, stdout and stderr. \
+/// All handles opened here are added to `opened`, they must be closed by the caller after the command has been started. \
+/// Returns an error message and the resulting exit status, if the command could not be started.
+fn start_command(command: &Command, mut handles: [usize; 3], opened: &mut Vec<usize>) -> Result<Process, (String, isize)> {
+    if let Some(path) = &command.stdin {
+        handles[0] = open(path, OpenOptions::READONLY).map_err(|e| (format!("{}: {:?}", path, e), STATUS_FAILURE))?;
+        opened.push(handles[0]);
+    }
+    if let Some(redirect) = &command.stdout {
+        handles[1] = open_output(redirect).map_err(|msg| (msg, STATUS_FAILURE))?;
+        opened.push(handles[1]);
+    }
+    if let Some(redirect) = &command.stderr {
+        handles[2] = open_output(redirect).map_err(|msg| (msg, STATUS_FAILURE))?;
+        opened.push(handles[2]);
+    }
+
+    let args = command.args[1..].iter().map(String::as_str).collect::<Vec<&str>>();
+    let vars = env::vars().map(|(name, value)| format!("{}={}", name, value)).collect::<Vec<String>>();
+    let vars = vars.iter().map(String::as_str).collect::<Vec<&str>>();
+    thread::start_application_with_handles(&command.args[0], args, vars, handles)
+        .ok_or_else(|| (format!("{}: Command not found!", command.args[0]), STATUS_NOT_FOUND))
+}
+
+/// Start all commands of a pipeline, connected by pipes, and wait until all of them have finished. \
+/// Returns the exit status of the last command.
+fn run_pipeline(commands: &[Command]) -> isize {
+    let mut apps = Vec::new();
+    let mut next_stdin = STDIN;
+    let mut status = STATUS_SUCCESS;
+
+    for (i, command) in commands.iter().enumerate() {
+        let mut handles = [next_stdin, STDOUT, STDERR];
+        let mut opened = Vec::new(); // handles of the shell, not needed after the command has been started
+        if next_stdin != STDIN {
+            opened.push(next_stdin);
+            next_stdin = STDIN;
+        }
+
+        if i + 1 < commands.len() {
+            match pipe() {
+                Ok((read_end, write_end)) => {
+                    handles[1] = write_end;
+                    opened.push(write_end);
+                    next_stdin = read_end;
+                }
+                Err(e) => {
+                    println!("pipe failed: {:?}", e);
+                    opened.iter().for_each(|fh| { let _ = close(*fh); });
+                    status = STATUS_FAILURE;
+                    break;
                 }
             }
+        }
+
+        // Only the status of the last command counts, a failed start is reported like an exit status
+        match start_command(command, handles, &mut opened) {
+            Ok(app) => apps.push((i, app)),
+            Err((msg, start_status)) => {
+                println!("{}", msg);
+                status = start_status;
+            }
+        }
+
+        // The shell must close its pipe ends, otherwise readers never see the end of the data
+        opened.iter().for_each(|fh| { let _ = close(*fh); });
+    }
+
+    for (i, app) in apps {
+        let exit_status = app.wait().unwrap_or(STATUS_FAILURE);
+        if i + 1 == commands.len() {
+            status = exit_status;
+        }
+    }
+    status
+}
+
+/// Run a pipeline and return its exit status. \
+/// Builtins run within the shell and always write to its terminal, so they must not be part of a pipeline or redirected.
+fn run_commands(commands: &[Command]) -> isize {
+    if let [command] = commands {
+        if let Some(builtin) = internal_command(&command.args[0]).filter(|_| !command.is_redirected()) {
+            let split = command.args.iter().map(String::as_str).collect::<Vec<&str>>();
+            return builtin(&split);
+        }
+    }
+
+    if let Some(command) = commands.iter().find(|command| internal_command(&command.args[0]).is_some()) {
+        println!("{}: builtins cannot be redirected or used in a pipeline", command.args[0]);
+        return STATUS_FAILURE;
+    }
+
+    run_pipeline(commands)
+}
+
+/// Run all pipelines of `line`, connected by `&&` and `||`. \
+/// `last_status` is the exit status of the previous pipeline (`$?`), it is updated after each pipeline.
+fn process_line(line: &str, last_status: &mut isize) {
+    let list = match parser::split_list(line) {
+        Ok(list) => list,
+        Err(msg) => {
+            println!("syntax error: {}", msg);
+            *last_status = STATUS_FAILURE;
+            return;
+        }
+    };
+
+    for (condition, pipeline) in list {
+        let run = match condition {
+            Condition::Always => true,
+            Condition::IfSuccess => *last_status == STATUS_SUCCESS,
+            Condition::IfFailure => *last_status != STATUS_SUCCESS,
+        };
+        if !run {
+            continue;
+        }
+
+        // Variables (and `$?`) are expanded right before the pipeline is run
+        *last_status = match parser::parse(pipeline, *last_status) {
+            Ok(commands) => run_commands(&commands),
+            Err(msg) => {
+                println!("syntax error: {}", msg);
+                STATUS_FAILURE
+            }
+        };
+    }
+}
+
+fn process_next_char(line: &mut String, last_status: &mut isize, ch: char) {
+    match ch {
+        '\n' => {
+            process_line(line, last_status);
 
             line.clear();
             print!("> ");
@@ -92,13 +344,14 @@ fn process_next_char(line: &mut String, ch: char) {
 }
 
 #[unsafe(no_mangle)]
-pub fn main() {
+pub fn main() -> isize {
     let mut line = String::new();
+    let mut last_status = STATUS_SUCCESS;
     print!("> ");
 
     loop {
         match read() {
-            Some(ch) => process_next_char(&mut line, ch),
+            Some(ch) => process_next_char(&mut line, &mut last_status, ch),
             None => (),
         }
     }
@@ -0,0 +1,233 @@
+#![no_std]
+
+extern crate alloc;
+
+use alloc::vec::Vec;
+use concurrent::sync::mpmc::{self, Receiver, Sender};
+use concurrent::sync::{Barrier, Condvar, Mutex, Once, RwLock};
+use concurrent::thread::{self, Thread};
+use core::sync::atomic::AtomicUsize;
+use core::sync::atomic::Ordering::Relaxed;
+#[allow(unused_imports)]
+use runtime::*;
+use terminal::{print, println};
+
+/// Number of threads started for each test
+const THREADS: usize = 8;
+
+/// Lock operations per thread in the mutex and rwlock tests
+const ITERATIONS: usize = 10000;
+
+/// Times each thread waits at the barrier
+const BARRIER_ROUNDS: usize = 100;
+
+/// Messages sent by each producer in the channel test (half of the threads are producers)
+const MESSAGES: usize = 1000;
+
+/// Capacity of the channel (small, so that producers block frequently)
+const CHANNEL_CAPACITY: usize = 4;
+
+/// Assigns an index to each thread of a test (threads are started with a function without parameters)
+static NEXT_INDEX: AtomicUsize = AtomicUsize::new(0);
+
+/// Number of failed checks within the threads of the current test
+static ERRORS: AtomicUsize = AtomicUsize::new(0);
+
+static COUNTER: Mutex<usize> = Mutex::new(0);
+
+static TABLE: RwLock<[usize; 2]> = RwLock::new([0, 0]); // writers keep both entries equal
+
+static STARTED: Mutex<bool> = Mutex::new(false);
+static START_SIGNAL: Condvar = Condvar::new();
+static WOKEN: AtomicUsize = AtomicUsize::new(0);
+
+static BARRIER: Barrier = Barrier::new(THREADS);
+static ARRIVED: AtomicUsize = AtomicUsize::new(0);
+static LEADERS: AtomicUsize = AtomicUsize::new(0);
+
+static ONCE: Once = Once::new();
+static ONCE_CALLS: AtomicUsize = AtomicUsize::new(0);
+
+static SENDERS: Mutex<Vec<Sender<usize>>> = Mutex::new(Vec::new());
+static RECEIVERS: Mutex<Vec<Receiver<usize>>> = Mutex::new(Vec::new());
+static RECEIVED_SUM: AtomicUsize = AtomicUsize::new(0);
+static RECEIVED_COUNT: AtomicUsize = AtomicUsize::new(0);
+
+#[unsafe(no_mangle)]
+pub fn main() -> isize {
+    println!("Testing synchronization primitives with [{}] threads", THREADS);
+
+    let results = [
+        test_mutex(),
+        test_rwlock(),
+        test_condvar(),
+        test_barrier(),
+        test_once(),
+        test_channel(),
+        test_spawn(),
+    ];
+
+    let failed = results.iter().filter(|&&ok| !ok).count();
+    if failed > 0 {
+        println!("[{}] tests failed!", failed);
+        return 1;
+    }
+
+    println!("All tests passed.");
+    0
+}
+
+/// Start `THREADS` threads executing `entry` and wait for all of them
+fn run_threads(entry: fn()) {
+    NEXT_INDEX.store(0, Relaxed);
+    ERRORS.store(0, Relaxed);
+
+    let threads = (0..THREADS)
+        .map(|_| thread::create(entry).expect("Failed to create thread"))
+        .collect::<Vec<Thread>>();
+    threads.iter().for_each(Thread::join);
+}
+
+fn report(name: &str, ok: bool) -> bool {
+    println!("  {:<8} {}", name, if ok { "ok" } else { "FAILED" });
+    ok
+}
+
+fn test_mutex() -> bool {
+    run_threads(|| {
+        for _ in 0..ITERATIONS {
+            *COUNTER.lock() += 1;
+        }
+    });
+
+    report("Mutex", *COUNTER.lock() == THREADS * ITERATIONS)
+}
+
+fn test_rwlock() -> bool {
+    // Even threads write, odd threads read and check that they never see a half finished update
+    run_threads(|| {
+        let writer = NEXT_INDEX.fetch_add(1, Relaxed).is_multiple_of(2);
+        for _ in 0..ITERATIONS {
+            if writer {
+                let mut table = TABLE.write();
+                table[0] += 1;
+                thread::switch();
+                table[1] += 1;
+            } else {
+                let table = TABLE.read();
+                if table[0] != table[1] {
+                    ERRORS.fetch_add(1, Relaxed);
+                }
+            }
+        }
+    });
+
+    let table = TABLE.read();
+    report("RwLock", ERRORS.load(Relaxed) == 0 && table[0] == THREADS / 2 * ITERATIONS && table[1] == table[0])
+}
+
+fn test_condvar() -> bool {
+    let threads = (0..THREADS)
+        .map(|_| thread::create(|| {
+            let started = START_SIGNAL.wait_while(STARTED.lock(), |started| !*started);
+            drop(started);
+            WOKEN.fetch_add(1, Relaxed);
+        }).expect("Failed to create thread"))
+        .collect::<Vec<Thread>>();
+
+    // Give the threads time to block
+    thread::sleep(100);
+    let woken_early = WOKEN.load(Relaxed);
+
+    *STARTED.lock() = true;
+    START_SIGNAL.notify_all();
+    threads.iter().for_each(Thread::join);
+
+    report("Condvar", woken_early == 0 && WOKEN.load(Relaxed) == THREADS)
+}
+
+fn test_barrier() -> bool {
+    run_threads(|| {
+        for round in 1..=BARRIER_ROUNDS {
+            ARRIVED.fetch_add(1, Relaxed);
+            if BARRIER.wait().is_leader() {
+                LEADERS.fetch_add(1, Relaxed);
+            }
+
+            // Nobody may pass the barrier, before all threads of the round have arrived
+            if ARRIVED.load(Relaxed) < round * THREADS {
+                ERRORS.fetch_add(1, Relaxed);
+            }
+        }
+    });
+
+    report("Barrier", ERRORS.load(Relaxed) == 0 && LEADERS.load(Relaxed) == BARRIER_ROUNDS)
+}
+
+fn test_once() -> bool {
+    run_threads(|| {
+        ONCE.call_once(|| {
+            // Other threads have to wait until the initialization is finished
+            thread::sleep(50);
+            ONCE_CALLS.fetch_add(1, Relaxed);
+        });
+
+        if ONCE_CALLS.load(Relaxed) != 1 {
+            ERRORS.fetch_add(1, Relaxed);
+        }
+    });
+
+    report("Once", ERRORS.load(Relaxed) == 0 && ONCE.is_completed() && ONCE_CALLS.load(Relaxed) == 1)
+}
+
+fn test_channel() -> bool {
+    let (sender, receiver) = mpmc::sync_channel(CHANNEL_CAPACITY);
+    for _ in 0..THREADS / 2 {
+        SENDERS.lock().push(sender.clone());
+        RECEIVERS.lock().push(receiver.clone());
+    }
+
+    // The channel is disconnected, when all producers have finished
+    drop(sender);
+    drop(receiver);
+
+    run_threads(|| {
+        let producer = NEXT_INDEX.fetch_add(1, Relaxed).is_multiple_of(2);
+        if producer {
+            let sender = SENDERS.lock().pop().unwrap();
+            for message in 1..=MESSAGES {
+                if sender.send(message).is_err() {
+                    ERRORS.fetch_add(1, Relaxed);
+                }
+            }
+        } else {
+            let receiver = RECEIVERS.lock().pop().unwrap();
+            for message in receiver.iter() {
+                RECEIVED_SUM.fetch_add(message, Relaxed);
+                RECEIVED_COUNT.fetch_add(1, Relaxed);
+            }
+        }
+    });
+
+    let producers = THREADS / 2;
+    report("Channel", ERRORS.load(Relaxed) == 0
+        && RECEIVED_COUNT.load(Relaxed) == producers * MESSAGES
+        && RECEIVED_SUM.load(Relaxed) == producers * MESSAGES * (MESSAGES + 1) / 2)
+}
+
+fn test_spawn() -> bool {
+    let handles = (0..THREADS)
+        .map(|index| {
+            let values = (0..=index * MESSAGES).collect::<Vec<usize>>();
+            thread::spawn(move || values.iter().sum::<usize>()).expect("Failed to spawn thread")
+        })
+        .collect::<Vec<_>>();
+
+    // Join all threads, even if a result is wrong
+    let correct = handles.into_iter().enumerate().map(|(index, handle)| {
+        let n = index * MESSAGES;
+        handle.join() == Some(n * (n + 1) / 2)
+    }).filter(|&ok| ok).count();
+
+    report("Spawn", correct == THREADS)
+}
@@ -155,6 +155,89 @@ clear_bss:
     mov esi, ebx
     call start
 
+; Startup code for the application processors (see 'smp.rs')
+; This code is copied to AP_TRAMPOLINE_ADDR (below 1 MiB) and executed in real mode by each application processor,
+; after it has received a startup IPI. It switches directly to long mode, using the page tables and control registers
+; of the bootstrap processor, which are written into 'ap_trampoline_data' (together with a stack and the core id)
+; and calls the Rust function, given in 'ap_trampoline_data.entry'.
+AP_TRAMPOLINE_ADDR equ 0x8000
+%define AP_ADDR(label) (AP_TRAMPOLINE_ADDR + (label - ap_trampoline))
+
+[BITS 16]
+
+align 16
+global ap_trampoline
+ap_trampoline:
+    cli
+    xor ax, ax
+    mov ds, ax
+    mov es, ax
+    mov ss, ax
+
+    ; Load temporary GDT with 64-bit code and data segment
+    o32 lgdt [AP_ADDR(ap_gdt_descriptor)]
+
+    ; Enable PAE (and everything else enabled on the bootstrap processor) and load the page tables
+    mov eax, [AP_ADDR(ap_trampoline_data.cr4)]
+    mov cr4, eax
+    mov eax, [AP_ADDR(ap_trampoline_data.cr3)]
+    mov cr3, eax
+
+    ; Enable long mode in EFER
+    mov ecx, 0xc0000080
+    mov eax, [AP_ADDR(ap_trampoline_data.efer)]
+    mov edx, [AP_ADDR(ap_trampoline_data.efer) + 4]
+    wrmsr
+
+    ; Enable protected mode and paging at once, which activates long mode
+    mov eax, [AP_ADDR(ap_trampoline_data.cr0)]
+    mov cr0, eax
+
+    ; Load 64-bit code segment
+    jmp dword 0x08:AP_ADDR(ap_trampoline_64)
+
+[BITS 64]
+
+ap_trampoline_64:
+    mov ax, 0x10
+    mov ds, ax
+    mov es, ax
+    mov ss, ax
+
+    ; Call rust function with core id on the stack allocated for this core
+    mov rsp, [AP_ADDR(ap_trampoline_data.stack)]
+    mov rdi, [AP_ADDR(ap_trampoline_data.core_id)]
+    mov rax, [AP_ADDR(ap_trampoline_data.entry)]
+    call rax
+
+ap_halt:
+    hlt
+    jmp ap_halt
+
+align 8
+ap_gdt:
+    dq 0x0000000000000000 ; Null descriptor
+    dq 0x00af9a000000ffff ; 64-bit code segment
+    dq 0x00cf92000000ffff ; Data segment
+ap_gdt_descriptor:
+    dw ap_gdt_descriptor - ap_gdt - 1
+    dd AP_ADDR(ap_gdt)
+
+; Written by 'smp.rs' for each application processor (layout must match 'TrampolineData')
+align 8
+global ap_trampoline_data
+ap_trampoline_data:
+.cr0: dq 0
+.cr3: dq 0
+.cr4: dq 0
+.efer: dq 0
+.stack: dq 0
+.entry: dq 0
+.core_id: dq 0
+
+global ap_trampoline_end
+ap_trampoline_end:
+
 [SECTION .bss]
 
 global init_stack:data (init_stack.end - init_stack)
@@ -12,7 +12,9 @@ use crate::device::pit::Timer;
 use crate::device::ps2::Keyboard;
 use crate::device::serial::SerialPort;
 use crate::device::{cxl, qemu_cfg};
+use crate::consts::DOUBLE_FAULT_STACK_PAGES;
 use crate::interrupt::interrupt_dispatcher;
+use crate::interrupt::interrupt_dispatcher::DOUBLE_FAULT_IST_INDEX;
 use crate::memory::frames;
 use crate::memory::nvmem::Nfit;
 use crate::memory::pages;
@@ -20,18 +22,19 @@ use crate::memory::pages::page_table_index;
 use crate::memory::vma::VmaType;
 use crate::memory::{MemorySpace, PAGE_SIZE, nvmem};
 use crate::network::rtl8139;
+use crate::process::image::ExecutableImage;
 use crate::process::thread::Thread;
+use crate::smp::AP_TRAMPOLINE_ADDR;
 use crate::syscall::syscall_dispatcher;
 use crate::{
-    acpi_tables, allocator, apic, built_info, gdt, init_acpi_tables, init_apic, init_cpu_info,
-    init_initrd, init_pci, init_serial_port, init_terminal, initrd, keyboard, logger, memory,
-    network, process_manager, scheduler, serial_port, terminal, timer, tss,
+    acpi_tables, allocator, apic, built_info, cpu, gdt, init_acpi_tables, init_apic, init_core_local_storage,
+    init_cpu_info, init_initrd, init_pci, init_serial_port, init_terminal, keyboard, logger, memory,
+    network, process_manager, scheduler, serial_port, smp, terminal, timer, tss,
 };
 use crate::{efi_services_available, naming, storage};
 use alloc::format;
 use alloc::string::ToString;
 use alloc::sync::Arc;
-use alloc::vec::Vec;
 use chrono::DateTime;
 use core::ffi::c_void;
 use core::mem::size_of;
@@ -56,7 +59,7 @@ use x86_64::PrivilegeLevel::Ring0;
 use x86_64::instructions::interrupts;
 use x86_64::instructions::segmentation::{CS, DS, ES, FS, GS, SS, Segment};
 use x86_64::instructions::tables::load_tss;
-use x86_64::registers::control::{Cr0, Cr0Flags, Cr3, Cr4, Cr4Flags};
+use x86_64::registers::control::{Cr0, Cr0Flags, Cr3, Cr4, Cr4Flags, Efer, EferFlags};
 use x86_64::registers::segmentation::SegmentSelector;
 use x86_64::structures::gdt::Descriptor;
 use x86_64::structures::paging::frame::PhysFrameRange;
@@ -76,6 +79,9 @@ const INIT_HEAP_PAGES: usize = 0x400; // number of heap pages for booting the OS
 ///   and `multiboot2_addr` is the address of multiboot2 info records
 #[unsafe(no_mangle)]
 pub extern "C" fn start(multiboot2_magic: u32, multiboot2_addr: *const BootInformationHeader) {
+    // Initialize core local storage of the bootstrap processor (core 0), needed to access all per core structures
+    init_core_local_storage(0);
+
     // Initialize logger
     log::set_logger(logger())
         .map(|()| log::set_max_level(LevelFilter::Debug))
@@ -92,16 +98,29 @@ pub extern "C" fn start(multiboot2_magic: u32, multiboot2_addr: *const BootInfor
     // Search memory map, provided by bootloader or EFI, for usable memory and initialize physical memory management with free memory regions
     let multiboot = multiboot2_search_memory_map(multiboot2_addr);
 
-    // Setup the GDT (Global Descriptor Table)
-    // Has to be done after EFI boot services have been exited, since they rely on their own GDT
-    info!("Initializing GDT");
-    init_gdt();
-
     // The bootloader marks the kernel image region as available, so we need to reserve it manually
     unsafe {
         memory::frames::reserve(kernel_image_region());
     }
 
+    // Reserve the page frame for the startup code of the application processors (see 'smp.rs')
+    unsafe {
+        let trampoline_frame = PhysFrame::containing_address(PhysAddr::new(AP_TRAMPOLINE_ADDR));
+        memory::frames::reserve(PhysFrameRange { start: trampoline_frame, end: trampoline_frame + 1 });
+    }
+
+    // Set up the free lists of the page frame allocator, now that all regions are known and the reserved frames are cut out
+    info!("Initializing page frame allocator");
+    unsafe {
+        memory::frames::init();
+    }
+
+    // Setup the GDT (Global Descriptor Table)
+    // Has to be done after EFI boot services have been exited, since they rely on their own GDT
+    // and after the kernel image has been reserved (the stack of the double fault handler is allocated)
+    info!("Initializing GDT");
+    init_gdt();
+
     // and initialize kernel heap, after which formatted strings may be used in logs and panics.
     info!("Initializing kernel heap");
     let heap_region = memory::frames::alloc(INIT_HEAP_PAGES);
@@ -118,6 +137,13 @@ pub extern "C" fn start(multiboot2_magic: u32, multiboot2_addr: *const BootInfor
     // Initialize CPU information
     init_cpu_info();
 
+    // Enable no-execute pages, before any page is mapped with `NO_EXECUTE` (applications are mapped W^X).
+    // The application processors take over EFER from this core (see 'smp.rs').
+    if !cpu().supports_no_execute() {
+        panic!("CPU does not support no-execute pages!");
+    }
+    unsafe { Efer::update(|flags| flags.insert(EferFlags::NO_EXECUTE_ENABLE)) };
+
     // Create kernel process (and initialize virtual memory management)
     info!("Create kernel process and initialize paging");
     let kernel_process = process_manager().write().create_process();
@@ -358,6 +384,11 @@ pub extern "C" fn start(multiboot2_magic: u32, multiboot2_addr: *const BootInfor
         .expect("Initrd not found!");
     init_initrd(initrd_tag);
 
+    // Make initial ramdisk available in the naming service
+    naming::api::mkdir("/initrd")
+        .and_then(|_| naming::api::mount("", "/initrd", "initrd"))
+        .expect("Failed to mount initrd!");
+
     // Create and register the cleanup thread in the scheduler
     // (If the last thread of a process terminates, it cannot delete its own address space)
     scheduler().ready(Thread::new_kernel_thread(
@@ -370,16 +401,16 @@ pub extern "C" fn start(multiboot2_magic: u32, multiboot2_addr: *const BootInfor
         "cleanup",
     ));
 
+    // Start APIC timer (threads are preempted, once the scheduler has been started on a core)
+    // and the application processors, which start their schedulers immediately
+    info!("Starting application processors");
+    apic().start_timer(10);
+    smp::start_application_processors();
+
     // Create and register the 'shell' thread (from app image in ramdisk) in the scheduler
-    scheduler().ready(Thread::load_application(
-        initrd()
-            .entries()
-            .find(|entry| entry.filename().as_str().unwrap() == "shell")
-            .expect("Shell application not available!")
-            .data(),
-        "shell",
-        &Vec::new(),
-    ));
+    let shell = naming::api::read_file("/initrd/shell").expect("Shell application not available!");
+    let shell = ExecutableImage::load(shell).expect("Shell application is not executable!");
+    scheduler().ready(Thread::load_application(shell, "shell", &[b"shell".as_slice()], &[]));
 
     // Disable terminal logging (remove terminal output stream)
     logger().remove(terminal().as_ref());
@@ -402,17 +433,32 @@ pub extern "C" fn start(multiboot2_magic: u32, multiboot2_addr: *const BootInfor
     // Dump information about all processes (including VMAs)
     process_manager().read().dump();
 
-    // Start APIC timer & scheduler
+    // Start scheduler
     info!("Starting scheduler");
-    apic().start_timer(10);
+    scheduler().start();
+}
 
+/// First Rust function called on an application processor by the startup code in `boot.asm` (see `smp.rs`) ///   `core_id` is the id assigned to the core
+#[unsafe(no_mangle)]
+pub extern "C" fn start_application_processor(core_id: usize) {
+    init_core_local_storage(core_id);
+    init_gdt();
+    interrupt_dispatcher::load_idt();
+    syscall_dispatcher::init();
+    apic().init_application_processor();
+
+    info!("Core [{core_id}] is online");
+    smp::set_online();
     scheduler().start();
 }
 
-/// Set up the GDT
+/// Set up the GDT of the calling core (and the stack for the double fault handler in its TSS)
 fn init_gdt() {
     let mut gdt = gdt().lock();
-    let tss = tss().lock();
+    let mut tss = tss().lock();
+
+    let double_fault_stack = frames::alloc(DOUBLE_FAULT_STACK_PAGES);
+    tss.interrupt_stack_table[DOUBLE_FAULT_IST_INDEX as usize] = VirtAddr::new(double_fault_stack.end.start_address().as_u64());
 
     gdt.append(Descriptor::kernel_code_segment());
     gdt.append(Descriptor::kernel_data_segment());
@@ -12,4 +12,13 @@ pub const USER_SPACE_ARG_START: usize = USER_SPACE_ENV_START;
 pub const MAX_USER_STACK_SIZE: usize = 0x40000000;  // 1 GiB
 pub const MAIN_USER_STACK_START: usize = USER_SPACE_ENV_START + 0x40000000;  // 1 GiB
 pub const KERNEL_STACK_PAGES: usize = 64;
+// Unmapped pages below each kernel and user stack, so that a stack overflow causes a page fault
+pub const STACK_GUARD_PAGES: usize = 1;
+// Smallest user stack, which may be requested when creating a thread (without the TLS block)
+pub const MIN_USER_STACK_SIZE: usize = 0x4000;  // 16 KiB
+// Stack for the double fault handler (one per core, see `interrupt_dispatcher`)
+pub const DOUBLE_FAULT_STACK_PAGES: usize = 4;
 pub const STACK_ENTRY_SIZE: usize = 8;  
+
+// Maximum number of cores used (further application processors listed in the MADT are not started)
+pub const MAX_CORES: usize = 16;
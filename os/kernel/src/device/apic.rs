@@ -6,23 +6,32 @@ use crate::{acpi_tables, allocator, interrupt_dispatcher, process_manager, sched
 use acpi::InterruptModel;
 use acpi::madt::Madt;
 use acpi::platform::interrupt::{InterruptSourceOverride, NmiSource, Polarity, TriggerMode};
+use acpi::platform::ProcessorState;
 use alloc::boxed::Box;
 use alloc::format;
 use alloc::vec::Vec;
+use core::sync::atomic::AtomicUsize;
+use core::sync::atomic::Ordering::Relaxed;
 use log::{info, warn};
 use raw_cpuid::CpuId;
 use spin::Mutex;
 use x2apic::ioapic::{IoApic, IrqFlags, IrqMode, RedirectionTableEntry};
-use x2apic::lapic::{LocalApic, LocalApicBuilder, TimerDivide, TimerMode};
+use x2apic::lapic::{IpiAllShorthand, LocalApic, LocalApicBuilder, TimerDivide, TimerMode};
+use x86_64::instructions::interrupts;
 use x86_64::structures::paging::frame::PhysFrameRange;
 use x86_64::structures::paging::PageTableFlags;
 
+/// The registers of the local APIC are mapped at the same address on all cores, but each core accesses its own local APIC.
+/// Thus, `local_apic` is shared by all cores and always locked with interrupts disabled
+/// (otherwise `end_of_interrupt()` could deadlock, if an interrupt occurs while the own core holds the lock).
 pub struct Apic {
     local_apic: Mutex<LocalApic>,
     io_apics: Vec<(Mutex<IoApic>, u32)>, // (0: IO APIC instance, 1: Base Global System Interrupt)
     irq_overrides: Vec<InterruptSourceOverride>,
     nmi_sources: Vec<NmiSource>,
+    application_processors: Vec<u32>, // local APIC ids of the application processors, which can be started
     timer_ticks_per_ms: usize,
+    timer_interval_ms: AtomicUsize,
 }
 
 unsafe impl Send for Apic {}
@@ -81,6 +90,12 @@ impl Apic {
             cpu_info.boot_processor.processor_uid
         );
 
+        // Remember the application processors, which may be started later on (see 'smp.rs')
+        let application_processors = cpu_info.application_processors.iter()
+            .filter(|processor| processor.state != ProcessorState::Disabled)
+            .map(|processor| processor.local_apic_id)
+            .collect::<Vec<u32>>();
+
         // Vectors to store IRQ overrides and Non-maskable interrupts
         let mut irq_overrides = Vec::<InterruptSourceOverride>::new();
         let mut nmi_sources = Vec::<NmiSource>::new();
@@ -263,7 +278,9 @@ impl Apic {
             io_apics,
             irq_overrides,
             nmi_sources,
+            application_processors,
             timer_ticks_per_ms,
+            timer_interval_ms: AtomicUsize::new(0),
         }
     }
 
@@ -354,35 +371,64 @@ impl Apic {
     }
 
     pub fn end_of_interrupt(&self) {
-        let mut local_apic = self.local_apic.try_lock();
-        while local_apic.is_none() {
-            // It its extremely unlikely, that the local APIC is locked during an interrupt,
-            // but if it happens, the whole system would hang, trying to send an EOI.
-            unsafe {
-                self.local_apic.force_unlock();
-            }
-            local_apic = self.local_apic.try_lock();
-        }
-
+        // The local APIC is only locked with interrupts disabled, so it may only be held by another core (for a short time)
         unsafe {
-            local_apic.unwrap().end_of_interrupt();
+            self.local_apic.lock().end_of_interrupt();
         }
     }
 
+    /// Start the timer of the calling core (the bootstrap processor), interrupting every `interval_ms` milliseconds
     pub fn start_timer(&self, interval_ms: usize) {
-        let mut local_apic = self.local_apic.lock();
+        self.timer_interval_ms.store(interval_ms, Relaxed);
+        interrupts::without_interrupts(|| self.enable_timer(&mut self.local_apic.lock(), interval_ms));
 
+        interrupt_dispatcher().assign(
+            InterruptVector::ApicTimer,
+            Box::new(ApicTimerInterruptHandler::default()),
+        );
+    }
+
+    /// Return the local APIC ids of the application processors listed in the MADT (except disabled ones)
+    pub fn application_processors(&self) -> &[u32] {
+        &self.application_processors
+    }
+
+    /// Enable the local APIC of the calling application processor and start its timer
+    /// with the same interval as on the bootstrap processor (see `start_timer()`)
+    pub fn init_application_processor(&self) {
+        interrupts::without_interrupts(|| {
+            let mut local_apic = self.local_apic.lock();
+            unsafe { local_apic.enable(); }
+            self.enable_timer(&mut local_apic, self.timer_interval_ms.load(Relaxed));
+        });
+    }
+
+    /// Send an INIT IPI to the core with the local APIC id `apic_id` (first step for starting an application processor)
+    pub fn send_init_ipi(&self, apic_id: u32) {
+        interrupts::without_interrupts(|| unsafe { self.local_apic.lock().send_init_ipi(apic_id) });
+    }
+
+    /// Send a startup IPI to the core with the local APIC id `apic_id`, which starts executing in real mode
+    /// at physical address `vector * 0x1000`
+    pub fn send_startup_ipi(&self, apic_id: u32, vector: u8) {
+        interrupts::without_interrupts(|| unsafe { self.local_apic.lock().send_sipi(vector, apic_id) });
+    }
+
+    /// Send an IPI with the given `vector` to all cores, except the calling one
+    pub fn send_ipi_to_others(&self, vector: InterruptVector) {
+        interrupts::without_interrupts(|| unsafe {
+            self.local_apic.lock().send_ipi_all(vector as u8, IpiAllShorthand::AllExcludingSelf)
+        });
+    }
+
+    /// Let the timer of the calling core interrupt every `interval_ms` milliseconds
+    fn enable_timer(&self, local_apic: &mut LocalApic, interval_ms: usize) {
         unsafe {
             local_apic.set_timer_divide(TimerDivide::Div1);
             local_apic.set_timer_mode(TimerMode::Periodic);
             local_apic.set_timer_initial((self.timer_ticks_per_ms * interval_ms) as u32);
             local_apic.enable_timer();
         }
-
-        interrupt_dispatcher().assign(
-            InterruptVector::ApicTimer,
-            Box::new(ApicTimerInterruptHandler::default()),
-        );
     }
 
     fn calibrate_timer(local_apic: &mut LocalApic) -> usize {
@@ -13,6 +13,7 @@ pub struct Cpu {
     physical_address_bits: u8,
     linear_address_bits: u8,
     supports_1gib_pages: bool,
+    supports_no_execute: bool,
 }
 
 impl Cpu {
@@ -20,6 +21,7 @@ impl Cpu {
         let physical_bits;
         let virtual_bits;
         let mut has_1gib_pages: bool = false;
+        let mut has_no_execute: bool = false;
         
         let cpuid = CpuId::new();
 
@@ -39,15 +41,19 @@ impl Cpu {
                 if features.has_1gib_pages() {
                     has_1gib_pages = true;
                 }
+                if features.has_execute_disable() {
+                    has_no_execute = true;
+                }
             }    
         }
 
-        info!("Cpu: Physical address bits {physical_bits}, Linear address bits {virtual_bits}, supports_1gib_pages = {has_1gib_pages}");
+        info!("Cpu: Physical address bits {physical_bits}, Linear address bits {virtual_bits}, supports_1gib_pages = {has_1gib_pages}, supports_no_execute = {has_no_execute}");
     
         Cpu {
             physical_address_bits: physical_bits,
             linear_address_bits: virtual_bits,
             supports_1gib_pages: has_1gib_pages,
+            supports_no_execute: has_no_execute,
         }
     }
 
@@ -63,5 +69,10 @@ impl Cpu {
         self.supports_1gib_pages
     }
 
+    /// Check if pages can be marked as not executable (`PageTableFlags::NO_EXECUTE`)
+    pub fn supports_no_execute(&self) -> bool {
+        self.supports_no_execute
+    }
+
 
 }
@@ -170,12 +170,15 @@ impl InputStream for LFBTerminal {
             let read_byte;
 
             loop {
-                let mut decoder = self.decoder.lock();
+                // Wait for the next scancode without holding the decoder lock
+                // (the wait ends early, if the reading thread has been killed or Ctrl-C has been pressed)
                 let scancode = keyboard.read_byte();
                 if scancode == -1 {
-                    panic!("Keyboard stream closed!");
+                    return -1;
                 }
 
+                let mut decoder = self.decoder.lock();
+
                 if let Ok(Some(event)) = decoder.add_byte(scancode as u8) {
                     if let Some(key) = decoder.process_keyevent(event) {
                         match key {
@@ -1,7 +1,9 @@
 use alloc::boxed::Box;
 use alloc::sync::Arc;
+use core::sync::atomic::{AtomicBool, Ordering};
 use crate::interrupt::interrupt_dispatcher::InterruptVector;
 use crate::interrupt::interrupt_handler::InterruptHandler;
+use crate::process::signal;
 use stream::InputStream;
 use log::info;
 use nolock::queues::{DequeueError, mpmc};
@@ -10,10 +12,15 @@ use ps2::{Controller, KeyboardType};
 use ps2::error::{ControllerError, KeyboardError};
 use spin::Mutex;
 use spin::once::Once;
-use crate::{apic, interrupt_dispatcher};
+use crate::{apic, interrupt_dispatcher, scheduler};
 
 const KEYBOARD_BUFFER_CAPACITY: usize = 128;
 
+/// Scancodes (set 1) needed to detect Ctrl-C in the interrupt handler (right Ctrl is prefixed by 0xe0)
+const SCANCODE_CTRL_PRESSED: u8 = 0x1d;
+const SCANCODE_CTRL_RELEASED: u8 = 0x9d;
+const SCANCODE_C_PRESSED: u8 = 0x2e;
+
 pub struct PS2 {
     controller: Arc<Mutex<Controller>>,
     keyboard: Once<Arc<Keyboard>>,
@@ -22,6 +29,7 @@ pub struct PS2 {
 pub struct Keyboard {
     controller: Arc<Mutex<Controller>>,
     buffer: (mpmc::bounded::scq::Receiver<u8>, mpmc::bounded::scq::Sender<u8>),
+    ctrl_pressed: AtomicBool,
 }
 
 struct KeyboardInterruptHandler {
@@ -30,7 +38,7 @@ struct KeyboardInterruptHandler {
 
 impl Keyboard {
     fn new(controller: Arc<Mutex<Controller>>, buffer_cap: usize) -> Self {
-        Self { controller, buffer: mpmc::bounded::scq::queue(buffer_cap) }
+        Self { controller, buffer: mpmc::bounded::scq::queue(buffer_cap), ctrl_pressed: AtomicBool::new(false) }
     }
 
     pub fn plugin(keyboard: Arc<Keyboard>) {
@@ -40,11 +48,15 @@ impl Keyboard {
 }
 
 impl InputStream for Keyboard {
+    /// Wait for the next scancode. \
+    /// Returns -1, if the buffer has been closed or if the calling thread has to stop waiting, because it has been killed
+    /// or Ctrl-C has been pressed (so that it reaches its next safe point, see `syscall_dispatcher::return_to_user`).
     fn read_byte(&self) -> i16 {
         loop {
             match self.buffer.0.try_dequeue() {
                 Ok(code) => return code as i16,
                 Err(DequeueError::Closed) => return -1,
+                Err(_) if signal::interrupt_pending() || scheduler().current_thread().is_killed() => return -1,
                 Err(_) => {}
             }
         }
@@ -61,6 +73,17 @@ impl InterruptHandler for KeyboardInterruptHandler {
     fn trigger(&self) {
         if let Some(mut controller) = self.keyboard.controller.try_lock() {
             if let Ok(data) = controller.read_data() {
+                match data {
+                    SCANCODE_CTRL_PRESSED => self.keyboard.ctrl_pressed.store(true, Ordering::Relaxed),
+                    SCANCODE_CTRL_RELEASED => self.keyboard.ctrl_pressed.store(false, Ordering::Relaxed),
+                    SCANCODE_C_PRESSED if self.keyboard.ctrl_pressed.load(Ordering::Relaxed) => {
+                        // Ctrl-C is not added to the input buffer, SIGINT is sent on return to user mode
+                        signal::raise_interrupt();
+                        return;
+                    }
+                    _ => {}
+                }
+
                 while self.keyboard.buffer.1.try_enqueue(data).is_err() {
                     if self.keyboard.buffer.0.try_dequeue().is_err() {
                         panic!("Keyboard: Failed to store received byte in buffer!");
@@ -180,7 +180,7 @@ impl TransmitDescriptor {
 
 impl ReceiveBuffer {
     pub fn new() -> Self {
-        let receive_memory = frames::alloc(BUFFER_PAGES);
+        let receive_memory = frames::alloc_dma32(BUFFER_PAGES);
         let receive_buffer = unsafe { Vec::from_raw_parts(receive_memory.start.start_address().as_u64() as *mut u8, BUFFER_SIZE, BUFFER_SIZE) };
 
         Self { index: 0, data: receive_buffer }
@@ -234,7 +234,7 @@ impl<'a> phy::TxToken for Rtl8139TxToken<'a> {
         }
 
         // Allocate physical memory for the packet (DMA only works with physical addresses)
-        let phys_buffer = frames::alloc(1);
+        let phys_buffer = frames::alloc_dma32(1);
         let phys_start_addr = phys_buffer.start.start_address();
         let pages = PageRange {
             start: Page::from_start_address(VirtAddr::new(phys_start_addr.as_u64())).unwrap(),
@@ -376,7 +376,7 @@ impl Rtl8139 {
         let kernel_process = process_manager().read().kernel_process().unwrap();
         let recv_buffers = mpmc::bounded::scq::queue(RECV_QUEUE_CAP);
         for _ in 0..RECV_QUEUE_CAP {
-            let phys_frame = frames::alloc(1);
+            let phys_frame = frames::alloc_dma32(1);
             let pages = PageRange {
                 start: Page::from_start_address(VirtAddr::new(phys_frame.start.start_address().as_u64())).unwrap(),
                 end: Page::from_start_address(VirtAddr::new(phys_frame.end.start_address().as_u64())).unwrap()
@@ -1,17 +1,22 @@
 use crate::interrupt::interrupt_handler::InterruptHandler;
+use crate::memory::user_access;
 use crate::memory::vma::VmaType;
+use crate::syscall::syscall_dispatcher::return_to_user;
 use alloc::boxed::Box;
 use alloc::vec::Vec;
 use core::ops::Deref;
 use core::ptr;
+use log::error;
 use spin::Mutex;
+use syscall::return_vals::Errno;
+use syscall::signal::Signal;
 use x86_64::registers::control::Cr2;
+use x86_64::instructions::interrupts;
 use x86_64::set_general_handler;
-use x86_64::structures::idt::InterruptStackFrame;
-use x86_64::structures::paging::{Page, PageTableFlags};
-use x86_64::structures::paging::page::PageRange;
+use x86_64::structures::idt::{InterruptStackFrame, PageFaultErrorCode};
+use x86_64::structures::paging::PageTableFlags;
+use x86_64::{PrivilegeLevel, VirtAddr};
 use crate::{apic, idt, interrupt_dispatcher, scheduler};
-use crate::memory::MemorySpace;
 
 #[repr(u8)]
 #[derive(PartialEq, PartialOrd, Copy, Clone, Debug)]
@@ -65,6 +70,10 @@ pub enum InterruptVector {
     // interrupts for cxl messages
     ARB = 0x30,
 
+    // Inter-processor interrupts
+    ThreadKilled = 0xf6,
+    TlbShootdown = 0xf7,
+
     // Local APIC interrupts (247 - 254)
     Cmci = 0xf8,
     ApicTimer = 0xf9,
@@ -166,6 +175,12 @@ impl TryFrom<u8> for InterruptVector {
                 Ok(InterruptVector::SecondaryAta)
             }
             value if value == InterruptVector::ARB as u8 => Ok(InterruptVector::ARB),
+            value if value == InterruptVector::ThreadKilled as u8 => {
+                Ok(InterruptVector::ThreadKilled)
+            }
+            value if value == InterruptVector::TlbShootdown as u8 => {
+                Ok(InterruptVector::TlbShootdown)
+            }
             value if value == InterruptVector::Cmci as u8 => Ok(InterruptVector::Cmci),
             value if value == InterruptVector::ApicTimer as u8 => Ok(InterruptVector::ApicTimer),
             value if value == InterruptVector::Thermal as u8 => Ok(InterruptVector::Thermal),
@@ -190,12 +205,26 @@ pub struct InterruptDispatcher {
 unsafe impl Send for InterruptDispatcher {}
 unsafe impl Sync for InterruptDispatcher {}
 
+/// Entry of the interrupt stack table (in the TSS of each core), whose stack is used by the double fault handler
+pub const DOUBLE_FAULT_IST_INDEX: u16 = 0;
+
 pub fn setup_idt() {
     let mut idt = idt().lock();
 
     set_general_handler!(&mut idt, handle_exception, 0..31);
     set_general_handler!(&mut idt, handle_interrupt, 32..255);
-    set_general_handler!(&mut idt, handle_page_fault, 14);
+    // The page fault handler is not a general handler, since it may modify the interrupted instruction pointer
+    idt.page_fault.set_handler_fn(handle_page_fault);
+    // A double fault is typically caused by a kernel stack overflow, so the handler needs its own stack
+    unsafe { idt.double_fault.set_handler_fn(handle_double_fault).set_stack_index(DOUBLE_FAULT_IST_INDEX); }
+
+    drop(idt);
+    load_idt();
+}
+
+/// Load the IDT on the calling core (the IDT is shared by all cores, see `setup_idt()`)
+pub fn load_idt() {
+    let idt = idt().lock();
 
     unsafe {
         // We need to obtain a static reference to the IDT for the following operation.
@@ -207,48 +236,124 @@ pub fn setup_idt() {
 }
 
 fn handle_exception(frame: InterruptStackFrame, index: u8, error: Option<u64>) {
-    panic!("CPU Exception: [{} - {:?}]\nError code: [{:?}]\n{:?}", index, InterruptVector::try_from(index).unwrap(), error, frame);
+    let vector = InterruptVector::try_from(index);
+    if let Ok(vector) = vector && is_user_mode(&frame)
+        && !matches!(vector, InterruptVector::NonMaskableInterrupt | InterruptVector::DoubleFault | InterruptVector::MachineCheck) {
+        kill_faulting_process(vector, &frame, error, None);
+    }
+
+    panic!("CPU Exception: [{} - {:?}]\nError code: [{:?}]\n{:?}", index, vector.unwrap(), error, frame);
 }
 
-fn handle_page_fault(frame: InterruptStackFrame, _index: u8, error: Option<u64>) {
+extern "x86-interrupt" fn handle_page_fault(mut frame: InterruptStackFrame, error_code: PageFaultErrorCode) {
     let fault_addr = Cr2::read().expect("Invalid address in CR2 during page fault");
     let thread = scheduler().current_thread();
 
     if !thread.is_kernel_thread() {
-        // Check if page fault occurred inside a user stack
-        let fault_handled = thread.process().virtual_address_space
-            .iter_vmas()
-            .filter(|vma| vma.typ == VmaType::UserStack)
-            .find(|stack| stack.start() <= fault_addr && fault_addr < stack.end())
-            .map(|stack| {
-                // If we found a user stack, we can map the page
-                let fault_page = Page::containing_address(fault_addr);
-                thread.process().virtual_address_space.map_partial_vma(&stack, PageRange { start: fault_page, end: fault_page + 1, }, MemorySpace::User, PageTableFlags::PRESENT | PageTableFlags::WRITABLE | PageTableFlags::USER_ACCESSIBLE);
-
-                ()
-            })
-            // Check if page fault occurred inside the allocated, but not yet mapped heap.
-            .or_else(|| {
-                thread.process().virtual_address_space
+        // Check if a copy-on-write page has been written (after fork, also by the kernel during a system call)
+        if error_code.contains(PageFaultErrorCode::PROTECTION_VIOLATION | PageFaultErrorCode::CAUSED_BY_WRITE)
+            && thread.process().virtual_address_space.resolve_copy_on_write(fault_addr) {
+            return;
+        }
+
+        // Pages are only mapped on demand, if they are not present yet (otherwise the access rights have been violated)
+        if !error_code.contains(PageFaultErrorCode::PROTECTION_VIOLATION) {
+            // Check if page fault occurred inside a user stack, the allocated, but not yet mapped heap or anonymous memory
+            // or the not yet loaded code of the application, which may be accessed this way (see `Mprotect`).
+            // The lookup is repeated, if another thread has removed or changed the VMA meanwhile (see `Munmap` and `Mprotect`).
+            loop {
+                let vma = thread.process().virtual_address_space
                     .iter_vmas()
-                    .filter(|vma| vma.typ == VmaType::Heap)
-                    .find(|heap| heap.start() <= fault_addr && fault_addr < heap.end())
-                    .map(|heap| {
-                        thread.process().grow_heap(&heap, fault_addr);
-                        ()
-                    })
-            });
-
-        if fault_handled.is_some() {
-            return; // Page fault was handled by mapping the user stack page
+                    .filter(|vma| matches!(vma.typ, VmaType::UserStack | VmaType::Heap | VmaType::Anonymous | VmaType::Code))
+                    .find(|vma| vma.start() <= fault_addr && fault_addr < vma.end())
+                    .filter(|vma| vma.flags.contains(PageTableFlags::PRESENT)
+                        && (vma.flags.contains(PageTableFlags::WRITABLE) || !error_code.contains(PageFaultErrorCode::CAUSED_BY_WRITE)));
+                let Some(vma) = vma else {
+                    break;
+                };
+
+                match thread.process().map_on_demand(&vma, fault_addr) {
+                    Ok(()) => return, // Page fault was handled by mapping the page
+                    Err(Errno::EFAULT) => continue, // The VMA has been removed or changed meanwhile
+                    Err(_) => break,
+                }
+            }
         }
     }
 
-    panic!("Page Fault!\nError code: [{:?}]\nAddress: [0x{:0>16x}]\n{:?}", error, fault_addr, frame);
+    // A system call has accessed inaccessible user memory -> let the copy fail with `EFAULT` instead of panicking
+    if let Some(fixup) = user_access::fault_fixup(frame.instruction_pointer) {
+        unsafe { frame.as_mut().update(|frame| frame.instruction_pointer = fixup) };
+        return;
+    }
+
+    if is_user_mode(&frame) {
+        kill_faulting_process(InterruptVector::PageFault, &frame, Some(error_code.bits()), Some(fault_addr));
+    }
+
+    panic!("Page Fault!\nError code: [{:?}]\nAddress: [0x{:0>16x}]\n{:?}", error_code, fault_addr, frame);
+}
+
+/// Handle a double fault, which occurs on a kernel stack overflow: the page fault in the guard pages of the stack
+/// cannot be delivered on the same stack. Runs on the double fault stack of the core (see `DOUBLE_FAULT_IST_INDEX`).
+extern "x86-interrupt" fn handle_double_fault(frame: InterruptStackFrame, _error_code: u64) -> ! {
+    if let Some(thread) = scheduler().try_current_thread() && thread.kernel_stack_overflowed(frame.stack_pointer) {
+        panic!("Kernel stack overflow in thread [{}] of process [{}]!\n{:?}", thread.id(), thread.process().id(), frame);
+    }
+
+    panic!("Double Fault!\n{:?}", frame);
+}
+
+/// Check if the exception described by `frame` has been caused by code running in ring 3
+fn is_user_mode(frame: &InterruptStackFrame) -> bool {
+    frame.code_segment.rpl() == PrivilegeLevel::Ring3
 }
 
-fn handle_interrupt(_frame: InterruptStackFrame, index: u8, _error: Option<u64>) {
+/// Terminate the current process, which has caused the exception `vector` in user mode, instead of panicking.
+/// The signal (only used for the exit status) depends on the exception, signal handlers are not called. \
+/// A short diagnostic is printed on the terminal (also reporting stack overflows into guard pages),
+/// the details (registers and VMAs) are logged.
+fn kill_faulting_process(vector: InterruptVector, frame: &InterruptStackFrame, error: Option<u64>, fault_addr: Option<VirtAddr>) -> ! {
+    let signal = match vector {
+        InterruptVector::InvalidOpcode => Signal::SIGILL,
+        InterruptVector::Debug | InterruptVector::Breakpoint => Signal::SIGTRAP,
+        InterruptVector::DivisionByZero | InterruptVector::X87FloatingPointException
+            | InterruptVector::SimdFloatingPointException => Signal::SIGFPE,
+        _ => Signal::SIGSEGV,
+    };
+
+    let thread = scheduler().current_thread();
+    let process = thread.process();
+    let in_guard_pages = |addr: VirtAddr| process.virtual_address_space.iter_vmas()
+        .any(|vma| vma.typ == VmaType::Guard && vma.start() <= addr && addr < vma.end());
+    match fault_addr {
+        Some(fault_addr) if in_guard_pages(fault_addr) => println!("Process [{}]: stack overflow in thread [{}] at address 0x{:x} (rip: 0x{:x}) -> killed by {:?}",
+            process.id(), thread.id(), fault_addr, frame.instruction_pointer, signal),
+        Some(fault_addr) => println!("Process [{}]: {:?} at address 0x{:x} (rip: 0x{:x}, error code: {:?}) -> killed by {:?}",
+            process.id(), vector, fault_addr, frame.instruction_pointer, PageFaultErrorCode::from_bits_truncate(error.unwrap_or(0)), signal),
+        None => println!("Process [{}]: {:?} (rip: 0x{:x}, error code: {:?}) -> killed by {:?}",
+            process.id(), vector, frame.instruction_pointer, error, signal),
+    }
+    error!("Process [{}], thread [{}]: {:?} in user mode (address: {:?}, error code: {:?})\n{:?}",
+        process.id(), thread.id(), vector, fault_addr, error, frame);
+    process.dump();
+
+    drop(thread);
+    process.exit(signal.exit_status());
+    drop(process); // Manually decrease reference count, because exit() will never return
+    scheduler().exit();
+}
+
+fn handle_interrupt(frame: InterruptStackFrame, index: u8, _error: Option<u64>) {
     interrupt_dispatcher().dispatch(index);
+
+    // Returning to user mode is a safe point like the end of a system call (see `return_to_user`).
+    // The interrupt has already been acknowledged, so interrupts can be enabled again.
+    if is_user_mode(&frame) {
+        interrupts::enable();
+        return_to_user();
+        interrupts::disable();
+    }
 }
 
 impl InterruptDispatcher {
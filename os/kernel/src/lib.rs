@@ -18,6 +18,7 @@
 #![allow(internal_features)]
 #![no_std]
 
+use crate::consts::MAX_CORES;
 use crate::device::apic::Apic;
 use crate::device::cpu::Cpu;
 use crate::device::lfb_terminal::{CursorThread, LFBTerminal};
@@ -36,16 +37,20 @@ use crate::memory::heap::KernelAllocator;
 use crate::process::process_manager::ProcessManager;
 use crate::process::scheduler::Scheduler;
 use crate::process::thread::Thread;
+use crate::smp::core_id;
 use crate::syscall::syscall_dispatcher::CoreLocalStorage;
 use ::log::{Level, Log, Record, error};
 use acpi::AcpiTables;
 use alloc::sync::Arc;
 use core::fmt::Arguments;
+use core::ops::Deref;
 use core::panic::PanicInfo;
+use core::ptr;
 use multiboot2::ModuleTag;
 use spin::{Mutex, Once, RwLock};
 use tar_no_std::TarArchiveRef;
-use x86_64::PhysAddr;
+use x86_64::{PhysAddr, VirtAddr};
+use x86_64::registers::model_specific::KernelGsBase;
 use x86_64::structures::gdt::GlobalDescriptorTable;
 use x86_64::structures::idt::InterruptDescriptorTable;
 use x86_64::structures::paging::PhysFrame;
@@ -64,7 +69,9 @@ pub mod memory;
 pub mod naming;
 pub mod network;
 pub mod process;
+pub mod smp;
 pub mod storage;
+pub mod sync;
 pub mod syscall;
 
 pub mod built_info {
@@ -122,19 +129,22 @@ pub fn efi_services_available() -> bool {
 
 /// Global Descriptor Table.
 /// Needed to set up basic segmentation (flat model) and the TSS.
-static GDT: Mutex<GlobalDescriptorTable> = Mutex::new(GlobalDescriptorTable::new());
+/// Each core has its own GDT, because it contains the descriptor of the TSS of the core.
+static GDT: [Mutex<GlobalDescriptorTable>; MAX_CORES] = [const { Mutex::new(GlobalDescriptorTable::new()) }; MAX_CORES];
 
+/// Returns the GDT of the calling core.
 pub fn gdt() -> &'static Mutex<GlobalDescriptorTable> {
-    &GDT
+    &GDT[core_id()]
 }
 
 /// Task State Segment.
 /// Needed to set up kernel/user mode switching.
-/// Once multicore is implemented, we need one TSS per core.
-static TSS: Mutex<TaskStateSegment> = Mutex::new(TaskStateSegment::new());
+/// Each core has its own TSS, since rsp0 depends on the thread running on the core.
+static TSS: [Mutex<TaskStateSegment>; MAX_CORES] = [const { Mutex::new(TaskStateSegment::new()) }; MAX_CORES];
 
+/// Returns the TSS of the calling core.
 pub fn tss() -> &'static Mutex<TaskStateSegment> {
-    &TSS
+    &TSS[core_id()]
 }
 
 /// Interrupt Descriptor Table.
@@ -146,14 +156,21 @@ pub fn idt() -> &'static Mutex<InterruptDescriptorTable> {
 }
 
 /// Core Local Storage.
-/// Contains information that is needed by the syscall handler.
-/// It is never accessed directly, but via the swapgs instruction.
-/// 'boot.rs' sets up the gs base register with a pointer to this struct.
-/// Once multicore is implemented, we need one of these per core.
-static CORE_LOCAL_STORAGE: Mutex<CoreLocalStorage> = Mutex::new(CoreLocalStorage::new());
+/// Contains information that is needed by the syscall handler and the id of the core.
+/// It is never accessed directly, but via the swapgs instruction (or the kernel gs base register, see 'smp::core_id()').
+/// Each core has its own core local storage.
+/// 'boot.rs' sets up the kernel gs base register of each core by calling 'init_core_local_storage()', before anything else.
+static CORE_LOCAL_STORAGE: [Mutex<CoreLocalStorage>; MAX_CORES] = [const { Mutex::new(CoreLocalStorage::new()) }; MAX_CORES];
+
+pub fn init_core_local_storage(core_id: usize) {
+    let mut core_local_storage = CORE_LOCAL_STORAGE[core_id].lock();
+    core_local_storage.set_core_id(core_id);
+    KernelGsBase::write(VirtAddr::new(ptr::from_ref(core_local_storage.deref()) as u64));
+}
 
+/// Returns the core local storage of the calling core.
 pub fn core_local_storage() -> &'static Mutex<CoreLocalStorage> {
-    &CORE_LOCAL_STORAGE
+    &CORE_LOCAL_STORAGE[core_id()]
 }
 
 /// ACPI Tables.
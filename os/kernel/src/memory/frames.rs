@@ -3,18 +3,37 @@
    ╟─────────────────────────────────────────────────────────────────────────╢
    ║ Page frame allocator.                                                   ║
    ║   - alloc              allooc a range of frames                         ║
+   ║   - try_alloc          alloc a range of frames, None if out of memory   ║
+   ║   - alloc_aligned      alloc a range of frames with a given alignment   ║
+   ║   - alloc_dma32        alloc a range of frames below 4 GiB              ║
    ║   - allocator_locked   check if allocator is locked                     ║
-   ║   - dump               get a dump of the current free list              ║
+   ║   - dump               get a dump of the current free lists             ║
+   ║   - mem_info           get the statistics of all zones                  ║
    ║   - free               free a range of frames                           ║
+   ║   - share              add a reference to a frame (copy-on-write)       ║
+   ║   - is_shared          check if a frame has more than one reference     ║
+   ║   - release            drop a reference, free the frame if it was last  ║
    ║   - insert             insert free frame region detected during boot    ║
+   ║   - init               set up the free lists after all regions are known║
    ║   - phys_limit         get the highest phys. addr. managed by the alloc.║
    ║   - reserve            permanently reserve a range of frames            ║
    ║   - frame_from_u64     convert a u64 address to a PhysFrame             ║
+   ║                                                                         ║
+   ║ Free frames are managed by a buddy allocator: Each free block consists  ║
+   ║ of 2^order frames and is aligned to its size. There is a free list per  ║
+   ║ order and zone (below/above 4 GiB), stored in the free frames itself.   ║
+   ║ Allocations are rounded up to the next order, the unused tail is freed  ║
+   ║ again. Freed blocks are merged with their buddy, if it is free as well. ║
+   ║ The order of each free block is also stored in a byte per frame (0 = no ║
+   ║ free block starts at this frame), so buddies and double frees are found ║
+   ║ without walking the free lists. This array is taken from the largest    ║
+   ║ region in 'init', before which inserted regions are only remembered.    ║
    ╟─────────────────────────────────────────────────────────────────────────╢
    ║ Author: Fabian Ruhland, Univ. Duesseldorf, 24.5.2025                    ║
    ╚═════════════════════════════════════════════════════════════════════════╝
 */
 use crate::memory::PAGE_SIZE;
+use alloc::collections::BTreeMap;
 use alloc::format;
 use alloc::string::String;
 use core::cell::Cell;
@@ -23,14 +42,27 @@ use core::ptr;
 use log::info;
 use spin::Mutex;
 use spin::once::Once;
+use syscall::meminfo::{MemInfo, ZoneInfo, Zone, NUM_ORDERS, NUM_ZONES};
 use x86_64::PhysAddr;
 use x86_64::structures::paging::frame::PhysFrameRange;
 use x86_64::structures::paging::{PhysFrame, Size4KiB};
 
-static PAGE_FRAME_ALLOCATOR: Mutex<PageFrameListAllocator> =
-    Mutex::new(PageFrameListAllocator::new());
+/// Largest order of a free block (2^20 frames = 4 GiB, so no block crosses the 4 GiB boundary)
+const MAX_ORDER: usize = NUM_ORDERS - 1;
+
+/// First frame number of the zone `Normal`
+const DMA32_LIMIT: u64 = 0x1_0000_0000 / PAGE_SIZE as u64;
+
+/// Maximum number of regions, which can be inserted before `init` (adjacent regions are merged)
+const MAX_REGIONS: usize = 128;
+
+static PAGE_FRAME_ALLOCATOR: Mutex<BuddyAllocator> = Mutex::new(BuddyAllocator::new());
 static PHYS_LIMIT: Once<Mutex<Cell<PhysFrame>>> = Once::new();
 
+/// Reference counts of frames shared by several address spaces (copy-on-write after fork). \
+/// Frames not contained in this map are owned by a single address space.
+static SHARED_FRAMES: Mutex<BTreeMap<PhysFrame, usize>> = Mutex::new(BTreeMap::new());
+
 /// Check if the page frame allocator is currently locked.
 pub fn allocator_locked() -> bool {
     PAGE_FRAME_ALLOCATOR.is_locked()
@@ -69,28 +101,82 @@ pub unsafe fn insert(mut region: PhysFrameRange) {
         current_limit.swap(&Cell::new(region.end));
     }
 
+    PAGE_FRAME_ALLOCATOR.lock().add_region(frame_number(region.start), frame_number(region.end));
+}
+
+/// Set up the free lists with all inserted regions (except reserved frames).
+/// Must be called once after the last call of `insert` and before the first allocation.
+pub unsafe fn init() {
     unsafe {
-        free(region);
+        PAGE_FRAME_ALLOCATOR.lock().init(frame_number(phys_limit()));
     }
 }
 
-/// Allocate `frame_count` contiguous page frames.
+/// Allocate `frame_count` contiguous page frames (preferably above 4 GiB).
 pub fn alloc(frame_count: usize) -> PhysFrameRange {
-    PAGE_FRAME_ALLOCATOR.lock().alloc_block(frame_count)
+    try_alloc(frame_count).expect("PageFrameAllocator: Out of memory!")
+}
+
+/// Allocate `frame_count` contiguous page frames (preferably above 4 GiB).
+/// Returns `None`, if there is no large enough free block (e.g. for allocations on behalf of applications).
+pub fn try_alloc(frame_count: usize) -> Option<PhysFrameRange> {
+    PAGE_FRAME_ALLOCATOR.lock().alloc_block(frame_count, PAGE_SIZE, &[Zone::Normal, Zone::Dma32])
+}
+
+/// Allocate `frame_count` contiguous page frames, starting at a multiple of `align` bytes
+/// (power of two, at least `PAGE_SIZE`, e.g. 2 MiB for huge pages).
+pub fn alloc_aligned(frame_count: usize, align: usize) -> PhysFrameRange {
+    PAGE_FRAME_ALLOCATOR.lock().alloc_block(frame_count, align, &[Zone::Normal, Zone::Dma32])
+        .expect("PageFrameAllocator: Out of memory!")
+}
+
+/// Allocate `frame_count` contiguous page frames below 4 GiB (for devices using 32 bit DMA addresses).
+pub fn alloc_dma32(frame_count: usize) -> PhysFrameRange {
+    PAGE_FRAME_ALLOCATOR.lock().alloc_block(frame_count, PAGE_SIZE, &[Zone::Dma32])
+        .expect("PageFrameAllocator: Out of memory!")
 }
 
 /// Free a contiguous range of page `frames`.
-/// Unsafe because invalid parameters may break the list allocator.
+/// Unsafe because invalid parameters may break the buddy allocator.
 pub unsafe fn free(frames: PhysFrameRange) {
     unsafe {
-        PAGE_FRAME_ALLOCATOR.lock().free_block(frames);
+        PAGE_FRAME_ALLOCATOR.lock().free_range(frame_number(frames.start), frame_number(frames.end));
+    }
+}
+
+/// Add a reference to `frame`, which is now mapped by one more address space.
+pub fn share(frame: PhysFrame) {
+    *SHARED_FRAMES.lock().entry(frame).or_insert(1) += 1;
+}
+
+/// Check if `frame` is mapped by more than one address space.
+pub fn is_shared(frame: PhysFrame) -> bool {
+    SHARED_FRAMES.lock().contains_key(&frame)
+}
+
+/// Drop a reference to `frame`. The frame is freed, if it has not been shared (anymore).
+/// Unsafe because the frame must not be used by the caller afterwards.
+pub unsafe fn release(frame: PhysFrame) {
+    {
+        let mut shared_frames = SHARED_FRAMES.lock();
+        if let Some(count) = shared_frames.get_mut(&frame) {
+            *count -= 1;
+            if *count == 1 {
+                shared_frames.remove(&frame);
+            }
+            return;
+        }
+    }
+
+    unsafe {
+        free(PhysFrameRange { start: frame, end: frame + 1 });
     }
 }
 
 /// Permanently reserve a range of page `frames`.
 pub unsafe fn reserve(frames: PhysFrameRange) {
     unsafe {
-        PAGE_FRAME_ALLOCATOR.lock().reserve_block(frames);
+        PAGE_FRAME_ALLOCATOR.lock().reserve_range(frame_number(frames.start), frame_number(frames.end));
     }
 }
 
@@ -99,64 +185,190 @@ pub fn phys_limit() -> PhysFrame {
     return PHYS_LIMIT.get().unwrap().lock().get();
 }
 
-/// Get a dump of the current free list.
+/// Get the statistics (total, free and largest free block) of all zones.
+pub fn mem_info() -> MemInfo {
+    PAGE_FRAME_ALLOCATOR.lock().mem_info()
+}
+
+/// Get a dump of the current free lists.
 pub fn dump() -> String {
     format!("{:?}", PAGE_FRAME_ALLOCATOR.lock())
 }
 
-/// Entry in the free list.
-/// Represents a block of available physical memory.
-struct PageFrameNode {
-    frame_count: usize,
-    next: Option<&'static mut PageFrameNode>,
+fn frame_number(frame: PhysFrame) -> u64 {
+    frame.start_address().as_u64() / PAGE_SIZE as u64
+}
+
+fn frame_at(number: u64) -> PhysFrame {
+    PhysFrame::from_start_address(PhysAddr::new(number * PAGE_SIZE as u64)).unwrap()
+}
+
+/// Zone of the frame with the given `number`.
+fn zone_of(number: u64) -> Zone {
+    if number < DMA32_LIMIT { Zone::Dma32 } else { Zone::Normal }
+}
+
+/// Smallest order, whose blocks contain at least `frame_count` frames.
+fn order_for(frame_count: usize) -> usize {
+    frame_count.next_power_of_two().trailing_zeros() as usize
+}
+
+/// Entry in a free list, written into the first frame of a free block.
+struct FreeBlock {
+    prev: *mut FreeBlock,
+    next: *mut FreeBlock,
+}
+
+/// Free lists and statistics of one zone.
+struct FrameZone {
+    free_lists: [*mut FreeBlock; NUM_ORDERS],
+    free_blocks: [usize; NUM_ORDERS],
+    total_frames: usize,
+    free_frames: usize,
+    first_frame: u64,
+    frame_count: u64,
+    block_orders: *mut u8, // order + 1 of the free block starting at each frame of the zone (0 = none)
 }
 
-impl PageFrameNode {
-    const fn new(frame_count: usize) -> Self {
+impl FrameZone {
+    const fn new() -> Self {
         Self {
-            frame_count,
-            next: None,
+            free_lists: [ptr::null_mut(); NUM_ORDERS],
+            free_blocks: [0; NUM_ORDERS],
+            total_frames: 0,
+            free_frames: 0,
+            first_frame: 0,
+            frame_count: 0,
+            block_orders: ptr::null_mut(),
+        }
+    }
+
+    /// Check if a free block of `order` starts at frame `start`.
+    fn is_free(&self, order: usize, start: u64) -> bool {
+        let index = start.wrapping_sub(self.first_frame);
+        index < self.frame_count && unsafe { *self.block_orders.add(index as usize) } == order as u8 + 1
+    }
+
+    /// Store the `order` of the free block starting at frame `start` (`None` if no block starts there).
+    fn set_order(&mut self, start: u64, order: Option<usize>) {
+        let index = start.wrapping_sub(self.first_frame);
+        if index >= self.frame_count {
+            panic!("PageFrameAllocator: Frame [{:#x}] is not managed by its zone!", start * PAGE_SIZE as u64);
+        }
+
+        unsafe {
+            *self.block_orders.add(index as usize) = order.map_or(0, |order| order as u8 + 1);
+        }
+    }
+
+    /// Add the block starting at frame `start` to the free list of `order`.
+    unsafe fn push(&mut self, order: usize, start: u64) {
+        self.set_order(start, Some(order));
+
+        let block = (start * PAGE_SIZE as u64) as *mut FreeBlock;
+        let next = self.free_lists[order];
+        unsafe {
+            block.write(FreeBlock { prev: ptr::null_mut(), next });
+            if !next.is_null() {
+                (*next).prev = block;
+            }
         }
+
+        self.free_lists[order] = block;
+        self.free_blocks[order] += 1;
+        self.free_frames += 1 << order;
+    }
+
+    /// Remove the first block from the free list of `order` and return its start frame.
+    fn pop(&mut self, order: usize) -> Option<u64> {
+        let block = self.free_lists[order];
+        if block.is_null() {
+            return None;
+        }
+
+        let start = block as u64 / PAGE_SIZE as u64;
+        self.remove(order, start);
+        Some(start)
+    }
+
+    /// Remove the block starting at frame `start` from the free list of `order`.
+    /// Returns false, if the block is not free.
+    fn remove(&mut self, order: usize, start: u64) -> bool {
+        if !self.is_free(order, start) {
+            return false;
+        }
+
+        let block = (start * PAGE_SIZE as u64) as *mut FreeBlock;
+        unsafe {
+            let FreeBlock { prev, next } = block.read();
+            if prev.is_null() {
+                self.free_lists[order] = next;
+            } else {
+                (*prev).next = next;
+            }
+            if !next.is_null() {
+                (*next).prev = prev;
+            }
+        }
+
+        self.set_order(start, None);
+        self.free_blocks[order] -= 1;
+        self.free_frames -= 1 << order;
+        true
+    }
+
+    /// Search the free block containing `frame` and return its order and start frame.
+    fn find_containing(&self, frame: u64) -> Option<(usize, u64)> {
+        (0..NUM_ORDERS)
+            .map(|order| (order, frame & !((1 << order) - 1)))
+            .find(|(order, start)| self.is_free(*order, *start))
     }
 
-    fn start(&self) -> PhysFrame {
-        PhysFrame::from_start_address(PhysAddr::new(ptr::from_ref(self) as u64)).unwrap()
+    fn largest_free_block(&self) -> usize {
+        match (0..NUM_ORDERS).rev().find(|order| self.free_blocks[*order] > 0) {
+            Some(order) => 1 << order,
+            None => 0,
+        }
     }
 
-    fn end(&self) -> PhysFrame {
-        self.start() + self.frame_count as u64
+    fn info(&self) -> ZoneInfo {
+        ZoneInfo {
+            total_frames: self.total_frames,
+            free_frames: self.free_frames,
+            largest_free_block: self.largest_free_block(),
+            free_blocks: self.free_blocks,
+        }
     }
 }
 
-/// Manages blocks of available physical memory as a linked list
-/// Since each page frame is exactly 4 KiB large, allocations are always a multiple of 4096.
-struct PageFrameListAllocator {
-    head: PageFrameNode,
+/// Manages the available physical memory as blocks of 2^order page frames in two zones
+/// (below and above 4 GiB). Each block is aligned to its size, so its buddy (the other half
+/// of the block of the next order) is found by flipping the bit `order` of its frame number.
+struct BuddyAllocator {
+    zones: [FrameZone; NUM_ZONES],
+    regions: [(u64, u64); MAX_REGIONS], // regions inserted before `init`
+    region_count: usize,
+    initialized: bool,
 }
 
-impl Debug for PageFrameListAllocator {
-    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
-        let mut available: usize = 0;
+// The free lists are only accessed with the allocator locked and point into identity mapped frames
+unsafe impl Send for BuddyAllocator {}
 
-        let mut current = &self.head;
-        while let Some(block) = &current.next {
+impl Debug for BuddyAllocator {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        for zone in Zone::ALL {
+            let info = self.zones[zone as usize].info();
             writeln!(
                 f,
-                "Block: [0x{:x} - 0x{:x}], Frame count: [{}]",
-                block.start().start_address().as_u64(),
-                block.end().start_address().as_u64(),
-                block.end() - block.start()
+                "Zone {}: Total: [{} KiB], Free: [{} KiB], Largest free block: [{} KiB]",
+                zone.name(),
+                info.total_frames * PAGE_SIZE / 1024,
+                info.free_frames * PAGE_SIZE / 1024,
+                info.largest_free_block * PAGE_SIZE / 1024
             )?;
-            available += block.frame_count;
-
-            current = current.next.as_ref().unwrap();
+            writeln!(f, "  Free blocks per order: {:?}", info.free_blocks)?;
         }
 
-        writeln!(
-            f,
-            "Available memory: [{} KiB]",
-            available * PAGE_SIZE / 1024
-        )?;
         write!(
             f,
             "Physical limit: [0x{:0>16x}]",
@@ -171,222 +383,230 @@ impl Debug for PageFrameListAllocator {
     }
 }
 
-impl PageFrameListAllocator {
+impl BuddyAllocator {
     pub const fn new() -> Self {
         Self {
-            head: PageFrameNode::new(0),
+            zones: [FrameZone::new(), FrameZone::new()],
+            regions: [(0, 0); MAX_REGIONS],
+            region_count: 0,
+            initialized: false,
         }
     }
 
-    /// Insert a new range of `frames`, sorted ascending by its memory address.
-    unsafe fn insert(&mut self, frames: PhysFrameRange) {
-        let mut new_block = PageFrameNode::new((frames.end - frames.start) as usize);
-        let new_block_ptr = frames.start.start_address().as_u64() as *mut PageFrameNode;
+    fn zone(&mut self, frame: u64) -> &mut FrameZone {
+        &mut self.zones[zone_of(frame) as usize]
+    }
 
-        // Check if list is empty
-        if self.head.next.is_none() {
-            unsafe {
-                new_block.next = self.head.next.take();
-                new_block_ptr.write(new_block);
-                self.head.next = Some(&mut *new_block_ptr);
-            }
+    /// Remember the frames `start..end` until the allocator is initialized.
+    fn add_region(&mut self, start: u64, end: u64) {
+        assert!(!self.initialized, "PageFrameAllocator: Cannot insert regions after initialization!");
 
+        // Memory maps often split contiguous memory into several entries
+        let regions = &mut self.regions[..self.region_count];
+        if let Some(region) = regions.iter_mut().find(|region| region.1 == start || region.0 == end) {
+            *region = (region.0.min(start), region.1.max(end));
             return;
         }
 
-        // List is not empty -> Search for correct position and insert new block
-        let mut current = &mut self.head;
-        while let Some(ref mut block) = current.next {
-            if block.start().start_address() > frames.start.start_address() {
-                unsafe {
-                    new_block.next = current.next.take();
-                    new_block_ptr.write(new_block);
-                    current.next = Some(&mut *new_block_ptr);
+        assert!(self.region_count < MAX_REGIONS, "PageFrameAllocator: Too many memory regions!");
+        self.regions[self.region_count] = (start, end);
+        self.region_count += 1;
+    }
+
+    /// Cut the frames `start..end` out of the regions inserted before `init`.
+    fn cut_regions(&mut self, start: u64, end: u64) {
+        let mut i = 0;
+        while i < self.region_count {
+            let (region_start, region_end) = self.regions[i];
+            if region_start < end && region_end > start {
+                match (region_start < start, region_end > end) {
+                    (true, true) => {
+                        assert!(self.region_count < MAX_REGIONS, "PageFrameAllocator: Too many memory regions!");
+                        self.regions[i] = (region_start, start);
+                        self.regions[self.region_count] = (end, region_end);
+                        self.region_count += 1;
+                    }
+                    (true, false) => self.regions[i] = (region_start, start),
+                    (false, true) => self.regions[i] = (end, region_end),
+                    (false, false) => {
+                        self.region_count -= 1;
+                        self.regions[i] = self.regions[self.region_count];
+                        continue;
+                    }
                 }
+            }
+            i += 1;
+        }
+    }
 
-                return;
+    /// Take the block orders (a byte per frame below `limit`) from the largest region
+    /// and insert all regions into the free lists.
+    unsafe fn init(&mut self, limit: u64) {
+        assert!(!self.initialized, "PageFrameAllocator: Already initialized!");
+
+        let frames = (limit as usize).div_ceil(PAGE_SIZE) as u64;
+        let region = self.regions[..self.region_count]
+            .iter_mut()
+            .max_by_key(|region| region.1 - region.0)
+            .filter(|region| region.1 - region.0 >= frames)
+            .expect("PageFrameAllocator: No region large enough for the block orders!");
+        region.1 -= frames;
+
+        let block_orders = (region.1 * PAGE_SIZE as u64) as *mut u8;
+        unsafe {
+            block_orders.write_bytes(0, limit as usize);
+        }
+
+        let split = limit.min(DMA32_LIMIT);
+        self.zones[Zone::Dma32 as usize].frame_count = split;
+        self.zones[Zone::Dma32 as usize].block_orders = block_orders;
+        self.zones[Zone::Normal as usize].first_frame = DMA32_LIMIT;
+        self.zones[Zone::Normal as usize].frame_count = limit - split;
+        self.zones[Zone::Normal as usize].block_orders = unsafe { block_orders.add(split as usize) };
+        self.initialized = true;
+
+        for i in 0..self.region_count {
+            let (start, end) = self.regions[i];
+            unsafe {
+                self.insert_region(start, end);
             }
+        }
+        self.region_count = 0;
+    }
 
-            current = current.next.as_mut().unwrap()
+    fn mem_info(&self) -> MemInfo {
+        MemInfo {
+            zones: [self.zones[0].info(), self.zones[1].info()],
         }
+    }
+
+    /// Insert the frames `start..end`, which have not been managed by the allocator before.
+    unsafe fn insert_region(&mut self, start: u64, end: u64) {
+        let split = end.min(DMA32_LIMIT).max(start);
+        self.zones[Zone::Dma32 as usize].total_frames += (split - start) as usize;
+        self.zones[Zone::Normal as usize].total_frames += (end - split) as usize;
 
-        // Insert new block at the list's end
         unsafe {
-            new_block.next = None;
-            new_block_ptr.write(new_block);
-            current.next = Some(&mut *new_block_ptr);
+            self.free_range(start, end);
         }
     }
 
-    /// Search a block with `frame_count` contiguous page frames.
-    fn find_free_block(&mut self, frame_count: usize) -> Option<&'static mut PageFrameNode> {
-        let mut current = &mut self.head;
-        while let Some(ref mut block) = current.next {
-            if block.frame_count >= frame_count {
-                let next = block.next.take();
-                let ret = Some(current.next.take().unwrap());
-                current.next = next;
+    /// Allocate a block with `frame_count` contiguous page frames, aligned to `align` bytes,
+    /// from the first of `zones` with a large enough free block (`None`, if there is none).
+    fn alloc_block(&mut self, frame_count: usize, align: usize, zones: &[Zone]) -> Option<PhysFrameRange> {
+        assert!(self.initialized, "PageFrameAllocator: Not initialized!");
+        assert!(align.is_power_of_two() && align >= PAGE_SIZE, "alloc_block: Invalid alignment [{align}]!");
+
+        // Blocks are aligned to their size, so a large enough order also guarantees the alignment
+        let order = order_for(frame_count.max(1)).max(order_for(align / PAGE_SIZE));
+        if order > MAX_ORDER {
+            info!("alloc_block: Allocation of {frame_count} frames exceeds the largest block!");
+            return None;
+        }
 
-                return ret;
-            } else {
-                // Block to small -> Continue with next block
-                current = current.next.as_mut().unwrap();
+        for zone in zones {
+            let zone = &mut self.zones[*zone as usize];
+            if let Some(found) = (order..NUM_ORDERS).find(|o| zone.free_blocks[*o] > 0) {
+                let start = zone.pop(found).unwrap();
+
+                // Split the block until it has the requested order (the upper halves remain free)
+                for lower in (order..found).rev() {
+                    unsafe {
+                        zone.push(lower, start + (1 << lower));
+                    }
+                }
+
+                // Give back the frames not needed
+                let end = start + frame_count as u64;
+                unsafe {
+                    self.free_range(end, start + (1 << order));
+                }
+
+                return Some(PhysFrameRange { start: frame_at(start), end: frame_at(end) });
             }
         }
+
+        info!(
+            "alloc_block: No free block found for {frame_count} frames!",
+        );
         None
     }
 
-    /// Allocate a block with `frame_count` contiguous page frames.
-    fn alloc_block(&mut self, frame_count: usize) -> PhysFrameRange {
-        //      info!("frames: alloc_block:{} frames!", frame_count);
-        match self.find_free_block(frame_count) {
-            Some(block) => {
-                let remaining = PhysFrameRange {
-                    start: block.start() + frame_count as u64,
-                    end: block.end(),
-                };
-                // info!("   found free block: [0x{:x} - 0x{:x}], Frame count: [{}]", block.start().start_address().as_u64(), block.end().start_address().as_u64(), block.frame_count);
-                if (remaining.end - remaining.start) > 0 {
-                    //   info!("   remaining frames: [{}]", remaining.end - remaining.start);
-                    unsafe {
-                        self.insert(remaining);
-                    }
-                }
-                //info!("   remaining block: [0x{:x} - 0x{:x}], Frame count: [{}]", remaining.start.start_address().as_u64(), remaining.end.start_address().as_u64(), remaining.end - remaining.start);
-
-                let ret_block = PhysFrameRange {
-                    start: block.start(),
-                    end: remaining.start,
-                };
-                //info!("   returning block: [0x{:x} - 0x{:x}], Frame count: [{}]", ret_block.start.start_address().as_u64(), ret_block.end.start_address().as_u64(), ret_block.end - ret_block.start);
-                ret_block
-                //                return PhysFrameRange { start: block.start(), end: remaining.start };
+    /// Free the frames `start..end`, which are split into the largest possible blocks.
+    unsafe fn free_range(&mut self, mut start: u64, end: u64) {
+        while start < end {
+            let mut order = (start.trailing_zeros() as usize).min(MAX_ORDER);
+            while start + (1 << order) > end {
+                order -= 1;
             }
-            None => {
-                info!(
-                    "alloc_block: No free block found for {frame_count} frames!",
-                );
-                panic!("PageFrameAllocator: Out of memory!")
+
+            unsafe {
+                self.free_block(start, order);
             }
+            start += 1 << order;
         }
     }
 
-    /// Free a region of `frames` consisting of at least one page frame.
-    /// The block is inserted ascending by address and fused with its neighbours, if possible.
-    unsafe fn free_block(&mut self, frames: PhysFrameRange) {
-        let mut current = &mut self.head;
-        let new_block_ptr: *mut PageFrameNode;
+    /// Free the block of `order` starting at frame `start`.
+    /// The block is merged with its buddy as long as the buddy is free as well.
+    unsafe fn free_block(&mut self, mut start: u64, mut order: usize) {
+        let zone = self.zone(start);
 
-        // Run through list and check if fusion is possible
-        while let Some(ref mut block) = current.next {
-
-            // Check if the block to free overlaps with the current block
-            if !(frames.end <= block.start() || frames.start >= block.end()) {
+        // The block must not be part of a free block (same or larger)
+        for containing_order in order..NUM_ORDERS {
+            let containing = start & !((1 << containing_order) - 1);
+            if zone.is_free(containing_order, containing) {
                 panic!(
                     "free_block: Double-free or overlapping free detected!\n\
             Trying to free: [{:#x} - {:#x})\n\
             Overlaps with:  [{:#x} - {:#x})",
-                    frames.start.start_address().as_u64(),
-                    frames.end.start_address().as_u64(),
-                    block.start().start_address().as_u64(),
-                    block.end().start_address().as_u64()
+                    start * PAGE_SIZE as u64,
+                    (start + (1 << order)) * PAGE_SIZE as u64,
+                    containing * PAGE_SIZE as u64,
+                    (containing + (1 << containing_order)) * PAGE_SIZE as u64
                 );
             }
+        }
 
-            if frames.end == block.start() {
-                // The freed memory block extends 'block' from the bottom
-                let mut new_block =
-                    PageFrameNode::new(block.frame_count + (frames.end - frames.start) as usize);
-
-                unsafe {
-                    new_block_ptr = frames.start.start_address().as_u64() as *mut PageFrameNode;
-                    new_block.next = block.next.take();
-                    new_block_ptr.write(new_block);
-
-                    current.next = Some(&mut *new_block_ptr);
-                }
-
-                return;
-            } else if block.end() == frames.start {
-                // The freed memory block extends 'block' from the top
-                block.frame_count += (frames.end - frames.start) as usize;
-
-                // The extended 'block' may now extend its successor from the bottom
-                let end = block.end();
-                if let Some(ref mut next) = block.next {
-                    if end == next.start() {
-                        block.frame_count += next.frame_count;
-                        block.next = next.next.take();
-                    }
-                }
-
-                return;
-            } else if block.end() > frames.start {
-                // The freed memory block does not extend any existing block and needs a new entry in the list
-                break;
-            }
-
-            current = current.next.as_mut().unwrap();
+        while order < MAX_ORDER && zone.remove(order, start ^ (1 << order)) {
+            start &= !(1 << order);
+            order += 1;
         }
 
         unsafe {
-            self.insert(frames);
+            zone.push(order, start);
         }
     }
 
-    /// Permanently reserve a frame region given by `reserved`.
-    unsafe fn reserve_block(&mut self, reserved: PhysFrameRange) {
-        let mut current = &mut self.head;
-
-        // Run through list and search for free blocks, containing the reserved block
-        while let Some(ref mut block) = current.next {
-            if block.start() > reserved.end {
-                // Block lies completely above reserved region and since the list is sorted, we can abort
-                break;
-            } else if block.start() < reserved.start && block.end() >= reserved.start {
-                // Block starts below the reserved region
-                if block.end() <= reserved.end {
-                    // Block starts below and ends inside the reserved region
-                    let overlapping = block.end() - reserved.start;
-                    block.frame_count -= overlapping as usize;
-                } else {
-                    // Block starts below and ends above the reserved region
-                    let below_size = reserved.start - block.start();
-                    let above_size = block.end() - reserved.end;
-                    block.frame_count = below_size as usize;
-
-                    let mut above_block = PageFrameNode::new(above_size as usize);
-                    let above_block_ptr =
-                        reserved.end.start_address().as_u64() as *mut PageFrameNode;
+    /// Permanently reserve the frames `start..end`.
+    /// Free blocks overlapping the region are removed and their remaining parts are freed again.
+    unsafe fn reserve_range(&mut self, start: u64, end: u64) {
+        if !self.initialized {
+            self.cut_regions(start, end);
+            return;
+        }
 
-                    unsafe {
-                        above_block.next = block.next.take();
-                        block.next = Some(&mut *above_block_ptr);
-                        above_block_ptr.write(above_block);
-                    }
-                }
-            } else if block.start() <= reserved.end && block.end() >= reserved.start {
-                // Block starts within the reserved region
-                if block.end() <= reserved.end {
-                    // Block start within and ends within the reserved region
-                    current.next = block.next.take();
-                } else {
-                    // Block starts within and ends above the reserved region
-                    let overlapping = reserved.end - block.start();
-                    let mut new_block =
-                        PageFrameNode::new(block.frame_count - overlapping as usize);
-                    let new_block_ptr = (block.start() + overlapping).start_address().as_u64()
-                        as *mut PageFrameNode;
+        let mut frame = start;
+        while frame < end.min(frame_number(phys_limit())) {
+            let zone = self.zone(frame);
+            let Some((order, block_start)) = zone.find_containing(frame) else {
+                frame += 1;
+                continue;
+            };
 
-                    unsafe {
-                        new_block.next = block.next.take();
-                        new_block_ptr.write(new_block);
-                        current.next = Some(&mut *new_block_ptr);
-                    }
+            let block_end = block_start + (1 << order);
+            zone.remove(order, block_start);
+            zone.total_frames -= (block_end.min(end) - block_start.max(start)) as usize;
+
+            unsafe {
+                if block_start < start {
+                    self.free_range(block_start, start);
+                }
+                if block_end > end {
+                    self.free_range(end, block_end);
                 }
             }
-
-            current = current.next.as_mut().unwrap();
+            frame = block_end;
         }
     }
 }
@@ -6,8 +6,12 @@
    ║   - map_physical  map a range of frames to the given page range in the  ║ 
    ║                   in the given memory space                             ║
    ║   - set_flags     set flags of page table entries for a range of pages  ║
+   ║   - protect       change access rights for a range of mapped pages      ║
    ║   - translate     translate a virtual address to a physical address     ║
    ║   - unmap         unmap a range of pages                                ║
+   ║   - share_pages   share mapped frames with another address space        ║
+   ║                   (copy-on-write)                                       ║
+   ║   - resolve_copy_on_write  copy a shared frame on a write access        ║
    ║   - page_from_u64 convert a u64 address to a Page                       ║
    ╟─────────────────────────────────────────────────────────────────────────╢
    ║ Author: Fabian Ruhland, Univ. Duesseldorf, 24.5.2025                    ║
@@ -18,7 +22,9 @@
 use core::cmp::min;
 use core::ptr;
 use spin::RwLock;
+use x86_64::instructions::tlb;
 use x86_64::structures::paging::{PageTable, PageTableFlags, PageTableIndex, PhysFrame};
+use x86_64::structures::paging::page_table::PageTableEntry;
 use x86_64::{PhysAddr, VirtAddr};
 use x86_64::registers::control::{Cr3, Cr3Flags};
 use x86_64::structures::paging::frame::PhysFrameRange;
@@ -26,6 +32,7 @@ use x86_64::structures::paging::page::{PageRange,Page};
 use x86_64::structures::paging::Size4KiB;
 
 use crate::memory::{MemorySpace, PAGE_SIZE, frames};
+use crate::smp;
 
 /// Helper function to convert a u64 address to a PhysFrame.
 pub fn page_from_u64(addr: u64) -> Result<Page<Size4KiB>, x86_64::structures::paging::page::AddressNotAligned> {
@@ -33,6 +40,10 @@ pub fn page_from_u64(addr: u64) -> Result<Page<Size4KiB>, x86_64::structures::pa
 }
 
 
+/// Page table flag (available for the OS) marking pages, which are shared read-only after fork
+/// and copied on the first write access (see `Paging::resolve_copy_on_write`)
+pub const COPY_ON_WRITE: PageTableFlags = PageTableFlags::BIT_9;
+
 /// Address space for a process
 pub struct Paging {
     root_table: RwLock<*mut PageTable>, // Root page table (pml4)
@@ -60,7 +71,8 @@ impl Paging {
 
     /// Create a new root page table for address space `self` with the given `depth`
     pub(super) fn new(depth: usize) -> Self {
-        let table_addr = frames::alloc(1).start;
+        // The startup code of the application processors loads cr3 in 32 bit mode (see 'smp.rs')
+        let table_addr = frames::alloc_dma32(1).start;
         let root_table = table_addr.start_address().as_u64() as *mut PageTable;
         unsafe { root_table.as_mut().unwrap().zero(); }
 
@@ -147,21 +159,100 @@ impl Paging {
 
     /// Unmap a range of `pages` from the address space. 
     /// `free_physical` indicates if the physical frames should be freed.
+    /// The TLBs of all cores are flushed (see `smp::tlb_shootdown`).
     pub(super) fn unmap(&self, pages: PageRange, free_physical: bool) {
         let depth = self.depth;
-        let root_table_guard = self.root_table.read();
+        let root_table_guard = self.root_table.write();
         let root_table = unsafe { root_table_guard.as_mut().unwrap() };
 
         Paging::unmap_in_table(root_table, pages, depth, free_physical);
+        drop(root_table_guard);
+        smp::tlb_shootdown();
     }
 
     /// Set `flags` of page table entries for the give range of `pages`` 
+    /// The TLBs of all cores are flushed (see `smp::tlb_shootdown`).
     pub(super) fn set_flags(&self, pages: PageRange, flags: PageTableFlags) {
         let depth = self.depth;
         let root_table_guard = self.root_table.write();
         let root_table = unsafe { root_table_guard.as_mut().unwrap() };
 
         Paging::set_flags_in_table(root_table, pages, flags, depth);
+        drop(root_table_guard);
+        smp::tlb_shootdown();
+    }
+
+    /// Change the access rights of all mapped pages in `pages` to `flags` (unmapped pages are skipped). \
+    /// Pages, whose frame is shared with another address space, are not made writable,
+    /// but marked `COPY_ON_WRITE` instead. The TLBs of all cores are flushed (see `smp::tlb_shootdown`).
+    pub(super) fn protect(&self, pages: PageRange, flags: PageTableFlags) {
+        let depth = self.depth;
+        let root_table_guard = self.root_table.write();
+        let root_table = unsafe { root_table_guard.as_mut().unwrap() };
+
+        Paging::protect_in_table(root_table, pages, flags, depth);
+        drop(root_table_guard);
+        smp::tlb_shootdown();
+    }
+
+    /// Share all frames mapped for `pages` in `self` with the address space `target`, mapping them at the same pages. \
+    /// Writable pages become read-only in both address spaces and are marked `COPY_ON_WRITE`.
+    /// The TLBs of all cores are flushed, because `self` may be the active address space (also on other cores).
+    pub(super) fn share_pages(&self, target: &Paging, pages: PageRange) {
+        let depth = self.depth;
+        let root_table_guard = self.root_table.write();
+        let root_table = unsafe { root_table_guard.as_mut().unwrap() };
+        let target_root_table_guard = target.root_table.write();
+        let target_root_table = unsafe { target_root_table_guard.as_mut().unwrap() };
+
+        Paging::share_in_table(root_table, target_root_table, pages, depth);
+        drop(target_root_table_guard);
+        drop(root_table_guard);
+        smp::tlb_shootdown();
+    }
+
+    /// Resolve a write access to `page`, if it is a copy-on-write page. The page is made writable again,
+    /// after copying the frame, if it is still shared with another address space. \
+    /// Returns `false`, if `page` is not a copy-on-write page (the page fault is caused by something else).
+    pub(super) fn resolve_copy_on_write(&self, page: Page) -> bool {
+        let depth = self.depth;
+        let root_table_guard = self.root_table.write();
+        let root_table = unsafe { root_table_guard.as_mut().unwrap() };
+
+        let Some(entry) = Paging::entry_in_table(root_table, page.start_address(), depth) else {
+            return false;
+        };
+        let mut flags = entry.flags();
+        if !flags.contains(COPY_ON_WRITE) {
+            if flags.contains(PageTableFlags::WRITABLE | PageTableFlags::USER_ACCESSIBLE) {
+                // Already resolved by a thread of the process on another core -> the TLB of this core is outdated
+                tlb::flush(page.start_address());
+                return true;
+            }
+            return false;
+        }
+        flags.remove(COPY_ON_WRITE);
+        flags.insert(PageTableFlags::WRITABLE);
+
+        let frame = PhysFrame::from_start_address(entry.addr()).unwrap();
+        if frames::is_shared(frame) {
+            let copy = frames::alloc(1).start;
+            unsafe {
+                let source = frame.start_address().as_u64() as *const u8;
+                let target = copy.start_address().as_u64() as *mut u8;
+                target.copy_from_nonoverlapping(source, PAGE_SIZE);
+            }
+            entry.set_frame(copy, flags);
+            unsafe { frames::release(frame); }
+        } else {
+            // All other address spaces have copied the page or have been dropped already
+            entry.set_flags(flags);
+        }
+
+        // Other cores may run threads of this address space with the read-only mapping in their TLB
+        drop(root_table_guard);
+        smp::tlb_shootdown();
+        true
     }
 
     /// Internal recursive function to copy page tables from `source` to `target`
@@ -202,8 +293,10 @@ impl Paging {
             for entry in table.iter_mut().skip(start_index) {
                 let next_level_table;
                 if entry.is_unused() { // Entry is empty -> Allocate new page frame
+                    // Access rights are checked on the last level only (the table may also contain pages with other rights)
                     let phys_frame = frames::alloc(1).start;
-                    entry.set_frame(phys_frame, flags);
+                    let table_flags = PageTableFlags::PRESENT | PageTableFlags::WRITABLE | (flags & PageTableFlags::USER_ACCESSIBLE);
+                    entry.set_frame(phys_frame, table_flags);
 
                     next_level_table = unsafe { (entry.addr().as_u64() as *mut PageTable).as_mut().unwrap() };
                     next_level_table.zero();
@@ -246,7 +339,13 @@ impl Paging {
 
         if level > 1 { // Calculate next level page table until level == 1
             for entry in table.iter_mut().skip(start_index) {
-                if entry.is_unused() {
+                if entry.is_unused() { // Skip all pages covered by the empty entry
+                    let skipped_pages = Paging::pages_in_entry(pages, level);
+                    pages = PageRange { start: pages.start + skipped_pages, end: pages.end };
+                    total_freed_pages += skipped_pages as usize;
+                    if pages.start >= pages.end {
+                        break;
+                    }
                     continue;
                 }
 
@@ -276,8 +375,9 @@ impl Paging {
 
                 if !entry.is_unused() {
                     if free_physical {
+                        // Frames shared with other address spaces are only freed by the last one
                         let frame = PhysFrame::from_start_address(entry.addr()).unwrap();
-                        unsafe { frames::free(PhysFrameRange { start: frame, end: frame + 1 }); }
+                        unsafe { frames::release(frame); }
                     }
 
                     entry.set_unused();
@@ -317,7 +417,13 @@ impl Paging {
 
         if level > 1 { // Calculate next level page table until level == 1
             for entry in table.iter_mut().skip(start_index) {
-                if entry.is_unused() { // Skip empty entries
+                if entry.is_unused() { // Skip all pages covered by the empty entry
+                    let skipped_pages = Paging::pages_in_entry(pages, level);
+                    pages = PageRange { start: pages.start + skipped_pages, end: pages.end };
+                    total_edited_pages += skipped_pages as usize;
+                    if pages.start >= pages.end {
+                        break;
+                    }
                     continue;
                 }
 
@@ -349,6 +455,57 @@ impl Paging {
         total_edited_pages
     }
 
+    /// Internal recursive function to change the access rights of the mapped pages in `pages` (see `protect`).
+    fn protect_in_table(table: &mut PageTable, mut pages: PageRange, flags: PageTableFlags, level: usize) -> usize {
+        let mut total_edited_pages: usize = 0;
+        let start_index = usize::from(page_table_index(pages.start.start_address(), level));
+
+        if level > 1 { // Calculate next level page table until level == 1
+            for entry in table.iter_mut().skip(start_index) {
+                let edited_pages = if entry.is_unused() { // Skip all pages covered by the empty entry
+                    Paging::pages_in_entry(pages, level) as usize
+                } else {
+                    let next_level_table = unsafe { (entry.addr().as_u64() as *mut PageTable).as_mut().unwrap() };
+                    Paging::protect_in_table(next_level_table, pages, flags, level - 1)
+                };
+                pages = PageRange { start: pages.start + edited_pages as u64, end: pages.end };
+                total_edited_pages += edited_pages;
+
+                if pages.start >= pages.end {
+                    break;
+                }
+            }
+        } else { // Reached level 1 page table
+            let edit_count = min((pages.end - pages.start) as usize, 512 - start_index);
+
+            for entry in table.iter_mut().skip(start_index).take(edit_count) {
+                if entry.is_unused() { // Mapped later with the flags of the virtual memory area
+                    continue;
+                }
+
+                let mut entry_flags = flags;
+                let frame = PhysFrame::from_start_address(entry.addr()).unwrap();
+                if flags.contains(PageTableFlags::WRITABLE) && (entry.flags().contains(COPY_ON_WRITE) || frames::is_shared(frame)) {
+                    entry_flags.remove(PageTableFlags::WRITABLE);
+                    entry_flags.insert(COPY_ON_WRITE);
+                }
+                entry.set_flags(entry_flags);
+            }
+
+            return edit_count;
+        }
+
+        total_edited_pages
+    }
+
+    /// Return the number of pages from `pages.start` to the end of the area covered by
+    /// the page table entry on `level` containing `pages.start` (at most the number of pages in `pages`).
+    fn pages_in_entry(pages: PageRange, level: usize) -> u64 {
+        let entry_pages = 1u64 << ((level - 1) * 9);
+        let offset = pages.start.start_address().as_u64() / PAGE_SIZE as u64 % entry_pages;
+        min(entry_pages - offset, pages.end - pages.start)
+    }
+
     /// Internal recursive function returning physical address for the given virtual address `addr` or None.
     fn translate_in_table(table: &mut PageTable, addr: VirtAddr, level: usize) -> Option<PhysAddr> {
         let aligned_addr = addr.align_down(PAGE_SIZE as u64);
@@ -366,6 +523,72 @@ impl Paging {
         }
     }
 
+    /// Internal recursive function to share the frames mapped for `pages` in `source` with `target` (see `share_pages`). \
+    /// Unmapped parts of `pages` are skipped, missing page tables in `target` are allocated.
+    fn share_in_table(source: &mut PageTable, target: &mut PageTable, pages: PageRange, level: usize) {
+        let entry_size = (PAGE_SIZE as u64) << ((level - 1) * 9); // bytes covered by one entry on this level
+        let end = pages.end.start_address().as_u64();
+        let mut addr = pages.start.start_address().as_u64();
+        let start_index = usize::from(page_table_index(pages.start.start_address(), level));
+
+        for index in start_index..512 {
+            if addr >= end {
+                break;
+            }
+            let entry_end = min(addr - (addr % entry_size) + entry_size, end);
+            let source_entry = &mut source[index];
+
+            if !source_entry.is_unused() {
+                if level > 1 {
+                    let target_entry = &mut target[index];
+                    if target_entry.is_unused() {
+                        // Access rights are checked on the last level only
+                        let phys_frame = frames::alloc(1).start;
+                        target_entry.set_frame(phys_frame, PageTableFlags::PRESENT | PageTableFlags::WRITABLE | PageTableFlags::USER_ACCESSIBLE);
+                        unsafe { (target_entry.addr().as_u64() as *mut PageTable).as_mut().unwrap().zero(); }
+                    }
+
+                    let next_level_source = unsafe { (source_entry.addr().as_u64() as *mut PageTable).as_mut().unwrap() };
+                    let next_level_target = unsafe { (target_entry.addr().as_u64() as *mut PageTable).as_mut().unwrap() };
+                    let sub_pages = PageRange {
+                        start: Page::containing_address(VirtAddr::new(addr)),
+                        end: Page::containing_address(VirtAddr::new(entry_end)),
+                    };
+                    Paging::share_in_table(next_level_source, next_level_target, sub_pages, level - 1);
+                } else {
+                    let mut flags = source_entry.flags();
+                    if flags.contains(PageTableFlags::WRITABLE) {
+                        flags.remove(PageTableFlags::WRITABLE);
+                        flags.insert(COPY_ON_WRITE);
+                        source_entry.set_flags(flags);
+                    }
+
+                    let frame = PhysFrame::from_start_address(source_entry.addr()).unwrap();
+                    frames::share(frame);
+                    target[index].set_frame(frame, flags);
+                }
+            }
+
+            addr = entry_end;
+        }
+    }
+
+    /// Internal recursive function returning the last level page table entry for the virtual address `addr`, if it is mapped.
+    fn entry_in_table(table: &mut PageTable, addr: VirtAddr, level: usize) -> Option<&mut PageTableEntry> {
+        let index = usize::from(page_table_index(addr, level));
+        let entry = &mut table[index];
+        if entry.is_unused() {
+            return None;
+        }
+
+        if level > 1 {
+            let next_level_table = unsafe { (entry.addr().as_u64() as *mut PageTable).as_mut().unwrap() };
+            Paging::entry_in_table(next_level_table, addr, level - 1)
+        } else {
+            Some(entry)
+        }
+    }
+
     /// Create 1:1 mapping entries in the given page `table` for `pages` with the given `flags` for the kernel space.
     fn identity_map_kernel(table: &mut PageTable, pages: PageRange, flags: PageTableFlags) -> usize {
         let start_index = usize::from(page_table_index(pages.start.start_address(), 1));
@@ -385,6 +608,7 @@ impl Paging {
     }
 
     /// Create mapping entries in the given page `table` for `pages` with the given `flags` using freshly allocated physical frames.
+    /// Entries in use are kept (e.g. mapped by another thread of the process, which has faulted on the same page).
     fn map_user(table: &mut PageTable, pages: PageRange, flags: PageTableFlags) -> usize {
         let start_index = usize::from(page_table_index(pages.start.start_address(), 1));
         let alloc_count = min((pages.end - pages.start) as usize, 512 - start_index);
@@ -394,8 +618,10 @@ impl Paging {
                 break;
             }
 
-            let phys_frame = frames::alloc(1).start;
-            entry.set_frame(phys_frame, flags);
+            if entry.is_unused() {
+                let phys_frame = frames::alloc(1).start;
+                entry.set_frame(phys_frame, flags);
+            }
         }
 
         alloc_count
@@ -4,6 +4,10 @@
    ║ Memory for a stack (user or kernel). The stack will be accessed within  ║
    ║ the kernel through a Vec and thus a Allocator is required.              ║
    ║                                                                         ║
+   ║ Each stack has 'STACK_GUARD_PAGES' below its first page, which are not  ║
+   ║ accessible (see 'VirtualAddressSpace::alloc_guard_vma'). A stack        ║
+   ║ overflow causes a page fault instead of overwriting other memory.       ║
+   ║                                                                         ║
    ║ Public functions:                                                       ║
    ║   - alloc_kernel_stack      alloc frames for a kernel stack             ║
    ║   - alloc_user_stack        alloc page range for a user stack           ║
@@ -12,7 +16,7 @@
    ╚═════════════════════════════════════════════════════════════════════════╝
 */
 
-use crate::consts::KERNEL_STACK_PAGES;
+use crate::consts::{KERNEL_STACK_PAGES, STACK_GUARD_PAGES};
 use crate::memory::{PAGE_SIZE, frames};
 use alloc::vec::Vec;
 use core::alloc::{AllocError, Allocator, Layout};
@@ -21,12 +25,15 @@ use core::sync::atomic::AtomicUsize;
 use core::sync::atomic::Ordering;
 use log::info;
 use x86_64::structures::paging::Page;
+use x86_64::structures::paging::page::PageRange;
 use x86_64::structures::paging::frame::PhysFrameRange;
 use x86_64::VirtAddr;
 
-/// Allocate frames for a kernel stack for a thread with the given `pid` and `tid`.
+/// Allocate frames for a kernel stack for a thread with the given `pid` and `tid`. \
+/// The frames of the guard pages are allocated as well (directly below the stack), since kernel stacks are identity mapped.
 pub fn alloc_kernel_stack(pid: usize, tid: usize) -> Vec<u64, StackAllocator> {
-    let frames: PhysFrameRange = frames::alloc(KERNEL_STACK_PAGES);
+    let mut frames: PhysFrameRange = frames::alloc(STACK_GUARD_PAGES + KERNEL_STACK_PAGES);
+    frames.start += STACK_GUARD_PAGES as u64;
     let mut kernel_stack = unsafe {
         Vec::from_raw_parts_in(
             frames.start.start_address().as_u64() as *mut u64,
@@ -46,7 +53,8 @@ pub fn alloc_kernel_stack(pid: usize, tid: usize) -> Vec<u64, StackAllocator> {
 }
 
 /// Allocate page range for a user stack for a thread with the given `pid` and `tid`. \
-/// The first page begins at `start_addr` and the size of the stack is `size_in_bytes`.
+/// The first page begins at `start_addr` and the size of the stack is `size_in_bytes`
+/// (the guard pages lie below `start_addr`).
 pub fn alloc_user_stack(pid: usize, tid: usize, start_addr: usize, size_in_bytes: usize) -> Vec<u64, StackAllocator> {
     // Create Vec for user stack (backed by stack allocator)
     unsafe {
@@ -97,6 +105,12 @@ impl StackAllocator {
         Page::from_start_address(VirtAddr::new(end_addr as u64)).unwrap()
     }
 
+    /// Return the guard pages directly below the first page of the stack
+    pub fn get_guard_pages(&self) -> PageRange {
+        let start_page = self.get_start_page();
+        PageRange { start: start_page - STACK_GUARD_PAGES as u64, end: start_page }
+    }
+
     pub fn get_num_pages(&self) -> u64 {
         let start_addr = self.start_addr.load(Ordering::SeqCst);
         let end_addr = self.end_addr.load(Ordering::SeqCst);
@@ -0,0 +1,232 @@
+/* ╔═════════════════════════════════════════════════════════════════════════╗
+   ║ Module: user_access                                                     ║
+   ╟─────────────────────────────────────────────────────────────────────────╢
+   ║ Safe access to user memory from system calls.                           ║
+   ║   - copy_from_user     copy bytes from the calling process              ║
+   ║   - copy_to_user       copy bytes to the calling process                ║
+   ║   - read_from_user     read a value from the calling process            ║
+   ║   - write_to_user      write a value to the calling process             ║
+   ║   - read_user_str      copy a null-terminated string                    ║
+   ║   - UserSlice          buffer given as pointer and length               ║
+   ║   - fault_fixup        used by the page fault handler                   ║
+   ║                                                                         ║
+   ║ Pointers passed by an application are never dereferenced directly.      ║
+   ║ Each range is checked against the areas (VMAs) of the calling process   ║
+   ║ and copied by `copy_bytes`. If the copy faults anyway (e.g. another     ║
+   ║ thread has unmapped the memory in the meantime), the page fault handler ║
+   ║ continues after the copy instruction and `Err(EFAULT)` is returned.     ║
+   ╟─────────────────────────────────────────────────────────────────────────╢
+   ║ Author: agent, 18.10.2026                                               ║
+   ╚═════════════════════════════════════════════════════════════════════════╝
+*/
+use alloc::string::String;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::arch::naked_asm;
+use core::mem::{size_of, MaybeUninit};
+use core::{ptr, slice};
+use x86_64::VirtAddr;
+use x86_64::structures::paging::PageTableFlags;
+use syscall::return_vals::Errno;
+
+use crate::consts::USER_SPACE_START;
+use crate::memory::PAGE_SIZE;
+use crate::process_manager;
+
+/// Max. length of a null-terminated string copied by `read_user_str` (e.g. a path)
+pub const MAX_USER_STR_LEN: usize = 4096;
+
+// labels in `copy_bytes`
+unsafe extern "C" {
+    static user_copy_start: u8; // instruction copying the bytes (may fault)
+    static user_copy_end: u8; // continuation after the copy instruction
+}
+
+/// Access to user memory, required by a system call
+#[derive(Clone, Copy, PartialEq)]
+enum Access {
+    Read,
+    Write,
+}
+
+/// A buffer in the user space of the calling process, given to a system call as pointer and length. \
+/// The range is checked when the slice is created, but the memory is only accessed by copying.
+#[derive(Clone, Copy)]
+pub struct UserSlice {
+    address: usize,
+    len: usize,
+}
+
+impl UserSlice {
+    /// Create a slice for `len` bytes at `ptr`, which must be readable by the calling process (otherwise `Err(EFAULT)`)
+    pub fn readable(ptr: *const u8, len: usize) -> Result<Self, Errno> {
+        check_range(ptr as usize, len, Access::Read)?;
+        Ok(Self { address: ptr as usize, len })
+    }
+
+    /// Create a slice for `len` bytes at `ptr`, which must be writable by the calling process (otherwise `Err(EFAULT)`)
+    pub fn writable(ptr: *mut u8, len: usize) -> Result<Self, Errno> {
+        check_range(ptr as usize, len, Access::Write)?;
+        Ok(Self { address: ptr as usize, len })
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Split the slice into consecutive slices of at most `size` bytes (e.g. for copying through a fixed-size buffer)
+    pub fn chunks(&self, size: usize) -> impl Iterator<Item = UserSlice> {
+        let slice = *self;
+        (0..slice.len).step_by(size).map(move |offset| UserSlice { address: slice.address + offset, len: (slice.len - offset).min(size) })
+    }
+
+    /// Copy the content of the slice to the beginning of `buffer` (`Err(EINVAL)`, if the buffer is too small)
+    pub fn read(&self, buffer: &mut [u8]) -> Result<(), Errno> {
+        if buffer.len() < self.len {
+            return Err(Errno::EINVAL);
+        }
+        copy_from_user(&mut buffer[..self.len], self.address as *const u8)
+    }
+
+    /// Copy the content of the slice into a new vector
+    pub fn read_to_vec(&self) -> Result<Vec<u8>, Errno> {
+        let mut buffer = vec![0; self.len];
+        copy_from_user(&mut buffer, self.address as *const u8)?;
+        Ok(buffer)
+    }
+
+    /// Copy the content of the slice into a new string (`Err(EBADSTR)`, if it is not valid UTF-8)
+    pub fn read_to_string(&self) -> Result<String, Errno> {
+        String::from_utf8(self.read_to_vec()?).map_err(|_| Errno::EBADSTR)
+    }
+
+    /// Copy `data` to the beginning of the slice (`Err(EINVAL)`, if the slice is too small)
+    pub fn write(&self, data: &[u8]) -> Result<(), Errno> {
+        if data.len() > self.len {
+            return Err(Errno::EINVAL);
+        }
+        copy_to_user(self.address as *mut u8, data)
+    }
+}
+
+/// Copy `dst.len()` bytes from `src` in the user space of the calling process to `dst`
+pub fn copy_from_user(dst: &mut [u8], src: *const u8) -> Result<(), Errno> {
+    let src = src as usize; // user pointers are just addresses, until they have been checked
+    check_range(src, dst.len(), Access::Read)?;
+    match unsafe { copy_bytes(dst.as_mut_ptr(), src as *const u8, dst.len()) } {
+        0 => Ok(()),
+        _ => Err(Errno::EFAULT),
+    }
+}
+
+/// Copy `src` to `dst` in the user space of the calling process
+pub fn copy_to_user(dst: *mut u8, src: &[u8]) -> Result<(), Errno> {
+    let dst = dst as usize; // user pointers are just addresses, until they have been checked
+    check_range(dst, src.len(), Access::Write)?;
+    match unsafe { copy_bytes(dst as *mut u8, src.as_ptr(), src.len()) } {
+        0 => Ok(()),
+        _ => Err(Errno::EFAULT),
+    }
+}
+
+/// Read a value of type `T` from `src` in the user space of the calling process (need not be aligned).
+///
+/// # Safety
+/// Every bit pattern must be a valid value of `T` (e.g. integers or arrays of integers),
+/// since the content of user memory is arbitrary.
+pub unsafe fn read_from_user<T: Copy>(src: *const T) -> Result<T, Errno> {
+    let mut value = MaybeUninit::<T>::uninit();
+    let bytes = unsafe { slice::from_raw_parts_mut(value.as_mut_ptr().cast::<u8>(), size_of::<T>()) };
+    copy_from_user(bytes, src.cast::<u8>())?;
+    Ok(unsafe { value.assume_init() })
+}
+
+/// Write `value` to `dst` in the user space of the calling process (need not be aligned)
+pub fn write_to_user<T>(dst: *mut T, value: &T) -> Result<(), Errno> {
+    let bytes = unsafe { slice::from_raw_parts(ptr::from_ref(value).cast::<u8>(), size_of::<T>()) };
+    copy_to_user(dst.cast::<u8>(), bytes)
+}
+
+/// Copy the null-terminated string at `ptr` in the user space of the calling process. \
+/// Returns `Err(EFAULT)` if the string is not accessible and `Err(EBADSTR)`,
+/// if it is not valid UTF-8 or longer than `MAX_USER_STR_LEN`.
+pub fn read_user_str(ptr: *const u8) -> Result<String, Errno> {
+    let mut bytes = Vec::new();
+    let mut address = ptr as usize;
+    while bytes.len() <= MAX_USER_STR_LEN {
+        // Copy page by page, since the string may end in front of an inaccessible page
+        let start = bytes.len();
+        bytes.resize(start + PAGE_SIZE - address % PAGE_SIZE, 0);
+        copy_from_user(&mut bytes[start..], address as *const u8)?;
+
+        if let Some(len) = bytes[start..].iter().position(|&byte| byte == 0) {
+            bytes.truncate(start + len);
+            if bytes.len() > MAX_USER_STR_LEN {
+                break;
+            }
+            return String::from_utf8(bytes).map_err(|_| Errno::EBADSTR);
+        }
+        address += bytes.len() - start;
+    }
+
+    Err(Errno::EBADSTR)
+}
+
+/// Return the address, where the interrupted code continues after an unresolved page fault at `instruction_pointer`,
+/// if the fault occurred while copying from or to user memory (see `copy_bytes`).
+pub fn fault_fixup(instruction_pointer: VirtAddr) -> Option<VirtAddr> {
+    let (start, end) = unsafe { (ptr::from_ref(&user_copy_start), ptr::from_ref(&user_copy_end)) };
+    (instruction_pointer == VirtAddr::from_ptr(start)).then(|| VirtAddr::from_ptr(end))
+}
+
+/// Check if the `len` bytes at `address` lie completely in areas of the calling process,
+/// which the process may access as requested (otherwise `Err(EFAULT)`). \
+/// Pages of these areas, which are not mapped yet, are mapped by the page fault handler during the copy.
+fn check_range(address: usize, len: usize, access: Access) -> Result<(), Errno> {
+    if len == 0 {
+        return Ok(()); // empty slices may have any (dangling) address
+    }
+    let end = address.checked_add(len).ok_or(Errno::EFAULT)?;
+    if address < USER_SPACE_START || VirtAddr::try_new(end as u64).is_err() {
+        return Err(Errno::EFAULT);
+    }
+
+    let mut required = PageTableFlags::PRESENT | PageTableFlags::USER_ACCESSIBLE;
+    if access == Access::Write {
+        required |= PageTableFlags::WRITABLE;
+    }
+
+    let process = process_manager().read().current_process();
+    let vmas = process.virtual_address_space.iter_vmas().collect::<Vec<_>>();
+    let mut addr = VirtAddr::new(address as u64);
+    let end = VirtAddr::new(end as u64);
+    while addr < end {
+        let vma = vmas.iter()
+            .find(|vma| vma.start() <= addr && addr < vma.end())
+            .filter(|vma| vma.flags.contains(required))
+            .ok_or(Errno::EFAULT)?;
+        addr = vma.end();
+    }
+    Ok(())
+}
+
+/// Copy `len` bytes from `src` to `dst` and return the number of bytes, which have not been copied. \
+/// This is 0, unless a page fault could not be resolved. In this case, the page fault handler continues
+/// at `user_copy_end` (see `fault_fixup`), with the remaining byte count still in `rcx`.
+#[unsafe(naked)]
+unsafe extern "C" fn copy_bytes(dst: *mut u8, src: *const u8, len: usize) -> usize {
+    naked_asm!(
+    "mov rcx, rdx",
+    ".global user_copy_start",
+    "user_copy_start:",
+    "rep movsb",
+    ".global user_copy_end",
+    "user_copy_end:",
+    "mov rax, rcx",
+    "ret"
+    );
+}
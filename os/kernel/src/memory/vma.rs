@@ -12,6 +12,7 @@ use x86_64::VirtAddr;
 use x86_64::structures::paging::page::PageRange;
 use x86_64::structures::paging::{Page, PageTableFlags};
 use crate::memory::{MemorySpace,PAGE_SIZE};
+use syscall::return_vals::Errno;
 
 
 #[derive(Copy, Clone, PartialEq, Debug)]
@@ -23,6 +24,8 @@ pub enum VmaType {
     UserStack,
     KernelStack,
     Anonymous,
+    File, // private copy of a file (see `Mmap`)
+    Guard, // inaccessible pages below a kernel or user stack (see `VirtualAddressSpace::alloc_guard_vma`)
 }
 
 pub const TAG_SIZE: usize = 8; // Define a constant for tag size in bytes
@@ -33,6 +36,7 @@ pub struct VirtualMemoryArea {
     pub range: PageRange,
     pub typ: VmaType,
     pub tag: [u8; TAG_SIZE], // 6-byte tag name (for debugging)
+    pub flags: PageTableFlags, // page table flags for the pages of the area (e.g. changed by `Mprotect`)
 }
 
 impl VirtualMemoryArea {
@@ -63,6 +67,7 @@ impl VirtualMemoryArea {
             range,
             typ,
             tag,
+            flags: Self::default_flags(space),
         }
     }
 
@@ -88,6 +93,7 @@ impl VirtualMemoryArea {
             range,
             typ,
             tag,
+            flags: Self::default_flags(space),
         }
     }
 
@@ -114,9 +120,31 @@ impl VirtualMemoryArea {
             range,
             typ,
             tag,
+            flags: Self::default_flags(space),
         }
     }
 
+    /// Default page table flags for an area in `space` (readable and writable, but not executable in user space)
+    pub const fn default_flags(space: MemorySpace) -> PageTableFlags {
+        match space {
+            MemorySpace::User => PageTableFlags::PRESENT
+                .union(PageTableFlags::WRITABLE)
+                .union(PageTableFlags::USER_ACCESSIBLE)
+                .union(PageTableFlags::NO_EXECUTE),
+            MemorySpace::Kernel => PageTableFlags::PRESENT.union(PageTableFlags::WRITABLE),
+        }
+    }
+
+    /// Return a copy of this area with the given `flags` (see `check_and_enforce_consistency`)
+    pub fn with_flags(&self, flags: PageTableFlags) -> Result<Self, Errno> {
+        Ok(Self { flags: self.check_and_enforce_consistency(flags)?, ..*self })
+    }
+
+    /// Return a copy of this area, covering only `range` (used for splitting an area)
+    pub fn with_range(&self, range: PageRange) -> Self {
+        Self { range, ..*self }
+    }
+
     pub fn start(&self) -> VirtAddr {
         self.range.start.start_address()
     }
@@ -137,17 +165,33 @@ impl VirtualMemoryArea {
         self.range.end > other.range.start && self.range.start < other.range.end
     }
 
-    /// Helper function to check if flags are consistent with the vma
-    pub fn check_and_enforce_consistency(&self, mut flags: PageTableFlags) -> PageTableFlags {
+    /// Check if `other` directly follows this area and both can be merged into one area. \
+    /// Only heap and anonymous areas are merged (e.g. stacks are identified by their range).
+    pub fn can_merge_with(&self, other: &VirtualMemoryArea) -> bool {
+        self.range.end == other.range.start
+            && matches!(self.typ, VmaType::Heap | VmaType::Anonymous)
+            && self.typ == other.typ
+            && self.space == other.space
+            && self.flags == other.flags
+            && self.tag == other.tag
+    }
+
+    /// Helper function to check if flags are consistent with the vma. \
+    /// User pages must not be writable and executable at the same time (W^X) -> `Err(EACCES)`.
+    /// This is not enforced in kernel space, because all physical memory (including the kernel code) is mapped there writable.
+    pub fn check_and_enforce_consistency(&self, mut flags: PageTableFlags) -> Result<PageTableFlags, Errno> {
         match self.space {
             MemorySpace::User => {
+                if flags.contains(PageTableFlags::WRITABLE) && !flags.contains(PageTableFlags::NO_EXECUTE) {
+                    return Err(Errno::EACCES);
+                }
                 flags |= PageTableFlags::USER_ACCESSIBLE;
             }
             MemorySpace::Kernel => {
                 flags &= !PageTableFlags::USER_ACCESSIBLE;
             }
         }
-        flags
+        Ok(flags)
     }
 }
 
@@ -158,13 +202,14 @@ impl fmt::Debug for VirtualMemoryArea {
 
         write!(
             f,
-            "   VMA {:?}, [0x{:x}; 0x{:x}], #pages: {}, tag: {:?}",
+            "   VMA {:?}, [0x{:x}; 0x{:x}], #pages: {}, tag: {:?}, flags: {:?}",
             self.typ,
             self.range.start.start_address().as_u64(),
             self.range.end.start_address().as_u64(),
             (self.range.end.start_address().as_u64() - self.range.start.start_address().as_u64())
                 / PAGE_SIZE as u64,
-            tag_str
+            tag_str,
+            self.flags
         )
     }
 }
@@ -7,6 +7,11 @@
    ║                                                                         ║
    ║ Public functions:                                                       ║
    ║   - alloc_vma                 allocate a page range in an address space ║
+   ║   - alloc_vma_with_flags      same, but with given page table flags     ║
+   ║   - alloc_guard_vma           allocate inaccessible pages below a stack ║
+   ║   - unmap_pages               remove a page range (splitting vmas)      ║
+   ║   - protect                   change access rights of a page range      ║
+   ║                               (splitting and merging vmas)              ║
    ║   - alloc_pfr_for_vma         allocate pf range for full vma            ║
    ║   - alloc_pfr_for_partial_vma alloc pf range for a subrange of a vma    ║
    ║   - map_pfr_for_vma           map pf range for full vma                 ║
@@ -15,11 +20,15 @@
    ║                               allocating frames as needed               ║
    ║                                                                         ║
    ║   - clone_address_space       used for process creation                 ║
+   ║   - clone_user_space          copy user vmas (copy-on-write), for fork  ║
+   ║   - resolve_copy_on_write     copy a shared page on a write access      ║
    ║   - create_kernel_address_space   used for process creation             ║
    ║   - iter_vmas                 Iterate over all VMAs                     ║
    ║   - dump                      dump all VMAs of an address space         ║
    ║   - page_table_address        get root page table address               ║
    ║   - set_flags                 set page table flags                      ║
+   ║   - translate                 get physical address for virtual address  ║
+   ║   - is_user_code              check if an address is executable by user ║
    ╟─────────────────────────────────────────────────────────────────────────╢
    ║ Author: Fabian Ruhland and Michael Schoettner                           ║
    ║         Univ. Duesseldorf, 26.05.2025                                   ║
@@ -42,7 +51,7 @@
 ///
 use alloc::sync::Arc;
 use alloc::vec::Vec;
-use core::ops::Deref;
+use core::cmp::{max, min};
 use log::info;
 use spin::RwLock;
 
@@ -50,7 +59,7 @@ use x86_64::PhysAddr;
 use x86_64::VirtAddr;
 use x86_64::structures::paging::frame::PhysFrameRange;
 use x86_64::structures::paging::page::PageRange;
-use x86_64::structures::paging::{Page, PageTableFlags};
+use x86_64::structures::paging::{Page, PageTableFlags, PhysFrame};
 
 use crate::cpu;
 use crate::memory::frames;
@@ -58,6 +67,7 @@ use crate::memory::frames::phys_limit;
 use crate::memory::pages::Paging;
 use crate::memory::{MemorySpace, PAGE_SIZE};
 use crate::memory::vma::{VirtualMemoryArea, VmaType};
+use syscall::return_vals::Errno;
 
 /// Clone address space. Used during process creation.
 pub fn clone_address_space(other: &VirtualAddressSpace) -> Arc<Paging> {
@@ -158,17 +168,122 @@ impl VirtualAddressSpace {
         vma_space: MemorySpace,
         vma_type: VmaType,
         vma_tag: &str,
+    ) -> Option<Arc<VirtualMemoryArea>> {
+        let flags = VirtualMemoryArea::default_flags(vma_space);
+        self.alloc_vma_with_flags(start_page, num_pages, vma_space, vma_type, vma_tag, flags)
+    }
+
+    /// Same as [`VirtualAddressSpace::alloc_vma`], but the pages of the new [`VirtualMemoryArea`]
+    /// will be mapped with the page table `flags` (instead of the default flags for `vma_space`). \
+    /// Returns `None` also, if `flags` are not allowed (see `VirtualMemoryArea::check_and_enforce_consistency`).
+    pub fn alloc_vma_with_flags(
+        &self,
+        start_page: Option<Page>,
+        num_pages: u64,
+        vma_space: MemorySpace,
+        vma_type: VmaType,
+        vma_tag: &str,
+        flags: PageTableFlags,
     ) -> Option<Arc<VirtualMemoryArea>> {
         match start_page {
-            Some(start_page) => self.alloc_at(start_page, num_pages, vma_space, vma_type, vma_tag),
-            None => self.alloc(num_pages, vma_space, vma_type, vma_tag),
+            Some(start_page) => self.alloc_at(start_page, num_pages, vma_space, vma_type, vma_tag, flags),
+            None => self.alloc(num_pages, vma_space, vma_type, vma_tag, flags),
         }
     }
 
+    /// Allocate a guard area for `pages` below a stack in `vma_space`, which cannot be accessed and not be removed
+    /// by the application (see `unmap_pages`). A stack overflow into the area causes a page fault. \
+    /// Kernel stacks are identity mapped, so their guard pages are unmapped in the page tables (only in this
+    /// address space, in which the stack is used). The frames still belong to the stack and are freed with the area.
+    pub fn alloc_guard_vma(&self, pages: PageRange, vma_space: MemorySpace, vma_tag: &str) -> Option<Arc<VirtualMemoryArea>> {
+        let flags = VirtualMemoryArea::default_flags(vma_space) & !(PageTableFlags::PRESENT | PageTableFlags::WRITABLE);
+        let vma = self.alloc_vma_with_flags(Some(pages.start), pages.len(), vma_space, VmaType::Guard, vma_tag, flags)?;
+        if vma_space == MemorySpace::Kernel {
+            self.page_tables.unmap(pages, false);
+        }
+        Some(vma)
+    }
+
+    /// Remove all user [`VirtualMemoryArea`]s in the range `pages` and free the mapped frames. \
+    /// Areas only partially covered by `pages` are split. Unused pages in `pages` are ignored.
+    /// Returns `Err(EINVAL)`, if `pages` contains memory, which may not be unmapped by an application.
+    pub fn unmap_pages(&self, pages: PageRange) -> Result<(), Errno> {
+        let mut vmas = self.virtual_memory_areas.write();
+        if vmas.iter().any(|vma| overlaps_with_range(vma, pages) && !is_user_mapping(vma)) {
+            return Err(Errno::EINVAL);
+        }
+
+        let mut remaining = Vec::with_capacity(vmas.len() + 1);
+        let mut unmapped = Vec::new();
+        for vma in vmas.drain(..) {
+            if !overlaps_with_range(&vma, pages) {
+                remaining.push(vma);
+                continue;
+            }
+
+            let (before, inside, after) = split_vma(&vma, pages);
+            remaining.extend(before.into_iter().chain(after).map(Arc::new));
+            unmapped.push(inside.range());
+        }
+        *vmas = remaining;
+
+        // Release the lock before the TLB shootdown (other cores might need it for handling page faults)
+        drop(vmas);
+        for range in unmapped {
+            self.page_tables.unmap(range, true);
+        }
+        Ok(())
+    }
+
+    /// Change the page table flags of all pages in the range `pages` to `flags`,
+    /// splitting areas partially covered by `pages` and merging adjacent areas with the same flags afterwards. \
+    /// Returns `Err(ENOMEM)`, if `pages` is not completely covered by areas,
+    /// `Err(EINVAL)`, if `pages` contains memory, whose access rights may not be changed by an application and
+    /// `Err(EACCES)`, if `flags` are not allowed (see `VirtualMemoryArea::check_and_enforce_consistency`).
+    pub fn protect(&self, pages: PageRange, flags: PageTableFlags) -> Result<(), Errno> {
+        let mut vmas = self.virtual_memory_areas.write();
+        vmas.sort_by(|a, b| a.range.start.cmp(&b.range.start));
+
+        // Check that there are no holes in the range and that `flags` are allowed
+        let mut covered = pages.start;
+        for vma in vmas.iter().filter(|vma| overlaps_with_range(vma, pages)) {
+            if !is_user_mapping(vma) {
+                return Err(Errno::EINVAL);
+            }
+            vma.check_and_enforce_consistency(flags)?;
+            if vma.range.start > covered {
+                return Err(Errno::ENOMEM);
+            }
+            covered = vma.range.end;
+        }
+        if covered < pages.end {
+            return Err(Errno::ENOMEM);
+        }
+
+        let mut result = Vec::with_capacity(vmas.len() + 2);
+        for vma in vmas.drain(..) {
+            if !overlaps_with_range(&vma, pages) {
+                result.push(vma);
+                continue;
+            }
+
+            let (before, inside, after) = split_vma(&vma, pages);
+            result.extend(before.map(Arc::new));
+            result.push(Arc::new(inside.with_flags(flags).expect("Flags have been checked above")));
+            result.extend(after.map(Arc::new));
+        }
+        *vmas = merge_vmas(result);
+
+        // Release the lock before the TLB shootdown (other cores might need it for handling page faults)
+        drop(vmas);
+        self.page_tables.protect(pages, flags | PageTableFlags::USER_ACCESSIBLE);
+        Ok(())
+    }
+
     /// Tries to allocate a frame range for the full `vma`. \
     /// Returns the allocated [`PhysFrameRange`] if successful, otherwise `None`.
     pub fn alloc_pf_for_vma(&self, vma: &VirtualMemoryArea) -> Option<PhysFrameRange> {
-        Some(frames::alloc(vma.range.len() as usize))
+        frames::try_alloc(vma.range.len() as usize)
     }
 
     /// Tries to allocate a frame range for the given `page_range` which must be within the given `vma`. \
@@ -181,26 +296,27 @@ impl VirtualAddressSpace {
         if page_range.start < vma.range.start || page_range.end > vma.range.end {
             return None;
         }
-        Some(frames::alloc(page_range.len() as usize))
+        frames::try_alloc(page_range.len() as usize)
     }
 
     /// Map `frame_range` for the full page range of the given `vma`. \
-    /// The mapping will use the given `flags` for the page table entries.
+    /// The mapping will use the given `flags` for the page table entries. \
+    /// Returns `Err(EINVAL)`, if the number of frames and pages differ, and `Err(EACCES)` for W+X user pages.
     pub fn map_pfr_for_vma(
         &self,
         vma: &VirtualMemoryArea,
         frame_range: PhysFrameRange,
         mut flags: PageTableFlags,
-    ) -> Result<(), i64> {
+    ) -> Result<(), Errno> {
         // Check if the number of frames is identical with the number of pages of the vma
         let num_frames = frame_range.end - frame_range.start;
         let num_pages = vma.range.end - vma.range.start;
         if num_frames != num_pages {
-            return Err(-1);
+            return Err(Errno::EINVAL);
         }
 
         // Check if the flags are consistent with the vma
-        flags = vma.check_and_enforce_consistency(flags);
+        flags = vma.check_and_enforce_consistency(flags)?;
         flags |= PageTableFlags::PRESENT;
 
         // Do the mapping
@@ -210,28 +326,36 @@ impl VirtualAddressSpace {
     }
 
     /// Map `frame_range` for the given page range which must be witin the given `vma`. \
-    /// The mapping will use the given already allocated frames and the `flags` for the page table entries.
+    /// The mapping will use the given already allocated frames and the `flags` for the page table entries. \
+    /// Returns `Err(EINVAL)` for an invalid range, `Err(EACCES)` for W+X user pages
+    /// and `Err(EFAULT)`, if `vma` has been removed meanwhile.
     pub fn map_pfr_for_partial_vma(
         &self,
         vma: &VirtualMemoryArea,
         frame_range: PhysFrameRange,
         page_range: PageRange,
         mut flags: PageTableFlags,
-    ) -> Result<(), i64> {
+    ) -> Result<(), Errno> {
         // Check if the number of frames of the `frame_range` is identical with the number of pages of `page_range`
         let num_frames = frame_range.end - frame_range.start;
-        let num_pages = vma.range.end - vma.range.start;
+        let num_pages = page_range.end - page_range.start;
         if num_frames != num_pages {
-            return Err(-1);
+            return Err(Errno::EINVAL);
         }
 
         // Check if the flags are consistent with the vma
-        flags = vma.check_and_enforce_consistency(flags);
+        flags = vma.check_and_enforce_consistency(flags)?;
         flags |= PageTableFlags::PRESENT;
 
         // Check if `page_range` is within the VMA range
         if page_range.start < vma.range.start || page_range.end > vma.range.end {
-            return Err(-1);
+            return Err(Errno::EINVAL);
+        }
+
+        // Check if the VMA still exists (it may have been removed by another thread, see `map_partial_vma`)
+        let areas = self.virtual_memory_areas.read();
+        if !areas.iter().any(|area| **area == *vma) {
+            return Err(Errno::EFAULT);
         }
 
         // Do the mapping
@@ -252,6 +376,7 @@ impl VirtualAddressSpace {
         vma_space: MemorySpace,
         vma_type: VmaType,
         vma_tag_str: &str,
+        flags: PageTableFlags,
     ) -> Option<Arc<VirtualMemoryArea>> {
         let start_addr = first_page.start_address();
 
@@ -273,7 +398,7 @@ impl VirtualAddressSpace {
             start: first_page,
             end: first_page + num_pages,
         };
-        let new_vma = Arc::new(VirtualMemoryArea::new_with_tag(vma_space, vma_range, vma_type, vma_tag_str));
+        let new_vma = Arc::new(VirtualMemoryArea::new_with_tag(vma_space, vma_range, vma_type, vma_tag_str).with_flags(flags).ok()?);
 
         // Check for overlap with existing VMAs
         let mut vmas = self.virtual_memory_areas.write();
@@ -300,6 +425,7 @@ impl VirtualAddressSpace {
         vma_space: MemorySpace,
         vma_type: VmaType,
         vma_tag: &str,
+        flags: PageTableFlags,
     ) -> Option<Arc<VirtualMemoryArea>> {
         let mut vmas = self.virtual_memory_areas.write();
         vmas.sort_by(|a, b| a.range.start.cmp(&b.range.start));
@@ -318,7 +444,7 @@ impl VirtualAddressSpace {
                 if gap_size >= requested_region_size {
                     let candidate_page = Page::containing_address(gap_start);
                     drop(vmas); // release lock before recursive call
-                    return self.alloc_at(candidate_page, num_pages, vma_space, vma_type, vma_tag);
+                    return self.alloc_at(candidate_page, num_pages, vma_space, vma_type, vma_tag, flags);
                 }
             }
 
@@ -335,7 +461,7 @@ impl VirtualAddressSpace {
 
         if available >= requested_region_size {
             let candidate_page = Page::containing_address(last_addr);
-            return self.alloc_at(candidate_page, num_pages, vma_space, vma_type, vma_tag);
+            return self.alloc_at(candidate_page, num_pages, vma_space, vma_type, vma_tag, flags);
         }
 
         None // No space found
@@ -347,22 +473,60 @@ impl VirtualAddressSpace {
         VmaIterator::new(vmas)
     }
 
-    /// Map the sub `page_range` of the given `vma` by allocating frames as needed. 
+    /// Map the sub `page_range` of the given `vma` by allocating frames as needed. \
+    /// Returns `Err(EFAULT)`, if `vma` does not exist (anymore), e.g. because another thread has unmapped it
+    /// or changed its access rights after the caller has looked it up.
     pub fn map_partial_vma(
         &self,
         vma: &VirtualMemoryArea,
         page_range: PageRange,
         space: MemorySpace,
         flags: PageTableFlags,
-    ) {
+    ) -> Result<(), Errno> {
+        // The areas stay locked while mapping, so that `vma` cannot be removed meanwhile
         let areas = self.virtual_memory_areas.read();
-        areas
-            .iter()
-            .find(|area| (**area).deref() == vma)
-            .expect("tried to map a non-existent VMA!");
+        if !areas.iter().any(|area| **area == *vma) {
+            return Err(Errno::EFAULT);
+        }
         assert!(page_range.start.start_address() >= vma.start());
         assert!(page_range.end.start_address() <= vma.end());
         self.page_tables.map(page_range, space, flags);
+        Ok(())
+    }
+
+    /// Copy all user VMAs (code, heap, stacks) of `parent` into `self`, which must not contain user VMAs yet. \
+    /// Of the user stacks, only the one containing `user_stack` (of the forking thread) is copied with its guard pages.
+    /// The mapped frames are shared copy-on-write: pages are read-only in both address spaces,
+    /// until one of them writes to a page (see `resolve_copy_on_write`). Used for forking a process.
+    pub fn clone_user_space(&self, parent: &VirtualAddressSpace, user_stack: VirtAddr) {
+        let parent_vmas = parent.virtual_memory_areas.read().clone();
+        let mut vmas = self.virtual_memory_areas.write();
+
+        // The user stacks of the other threads (and the guard pages below them) are not used in the child
+        let other_stacks: Vec<Page> = parent_vmas.iter()
+            .filter(|vma| vma.typ == VmaType::UserStack && !(vma.start() <= user_stack && user_stack < vma.end()))
+            .map(|vma| vma.range.start)
+            .collect();
+        let is_other_stack = |vma: &VirtualMemoryArea| match vma.typ {
+            VmaType::UserStack => other_stacks.contains(&vma.range.start),
+            VmaType::Guard => other_stacks.contains(&vma.range.end),
+            _ => false,
+        };
+
+        // Kernel stacks (and their guard pages) belong to the threads of `parent`
+        let user_vmas = parent_vmas.iter()
+            .filter(|vma| vma.space == MemorySpace::User && !matches!(vma.typ, VmaType::KernelStack | VmaType::DeviceMemory))
+            .filter(|vma| !is_other_stack(vma));
+        for vma in user_vmas {
+            parent.page_tables.share_pages(&self.page_tables, vma.range);
+            vmas.push(Arc::new(**vma));
+        }
+    }
+
+    /// Resolve a write access to the copy-on-write page containing `addr` by copying it, if necessary. \
+    /// Returns `false`, if `addr` does not belong to a copy-on-write page.
+    pub fn resolve_copy_on_write(&self, addr: VirtAddr) -> bool {
+        self.page_tables.resolve_copy_on_write(Page::containing_address(addr))
     }
 
     /// Set page table `flags` for the give page range `pages`  
@@ -370,6 +534,23 @@ impl VirtualAddressSpace {
         self.page_tables.set_flags(pages, flags);
     }
 
+    /// Get physical address to which the virtual address `addr` is mapped, if any
+    pub fn translate(&self, addr: VirtAddr) -> Option<PhysAddr> {
+        self.page_tables.translate(addr)
+    }
+
+    /// Check if `addr` lies in a user area, which may be executed (e.g. functions passed in system calls)
+    pub fn is_user_code(&self, addr: VirtAddr) -> bool {
+        self.virtual_memory_areas.read().iter().any(|vma| {
+            vma.space == MemorySpace::User
+                && vma.typ != VmaType::Guard
+                && vma.flags.contains(PageTableFlags::PRESENT | PageTableFlags::USER_ACCESSIBLE)
+                && !vma.flags.contains(PageTableFlags::NO_EXECUTE)
+                && vma.start() <= addr
+                && addr < vma.end()
+        })
+    }
+
     /// Get physical address of root page table
     pub fn page_table_address(&self) -> PhysAddr {
         self.page_tables.page_table_address()
@@ -385,9 +566,51 @@ impl VirtualAddressSpace {
     }
 }
 
+/// Check if `vma` overlaps with the page range `pages`
+fn overlaps_with_range(vma: &VirtualMemoryArea, pages: PageRange) -> bool {
+    vma.range.end > pages.start && vma.range.start < pages.end
+}
+
+/// Check if `vma` is user memory, which may be unmapped or protected by the application itself
+fn is_user_mapping(vma: &VirtualMemoryArea) -> bool {
+    vma.space == MemorySpace::User && !matches!(vma.typ, VmaType::KernelStack | VmaType::DeviceMemory | VmaType::Guard)
+}
+
+/// Split `vma` into the parts before, inside and after the page range `pages` (which must overlap with `vma`)
+fn split_vma(vma: &VirtualMemoryArea, pages: PageRange) -> (Option<VirtualMemoryArea>, VirtualMemoryArea, Option<VirtualMemoryArea>) {
+    let start = max(vma.range.start, pages.start);
+    let end = min(vma.range.end, pages.end);
+
+    let before = (vma.range.start < start).then(|| vma.with_range(PageRange { start: vma.range.start, end: start }));
+    let inside = vma.with_range(PageRange { start, end });
+    let after = (end < vma.range.end).then(|| vma.with_range(PageRange { start: end, end: vma.range.end }));
+    (before, inside, after)
+}
+
+/// Merge adjacent areas of the sorted list `vmas`, if possible (see `VirtualMemoryArea::can_merge_with`)
+fn merge_vmas(vmas: Vec<Arc<VirtualMemoryArea>>) -> Vec<Arc<VirtualMemoryArea>> {
+    let mut merged: Vec<Arc<VirtualMemoryArea>> = Vec::with_capacity(vmas.len());
+    for vma in vmas {
+        match merged.last_mut() {
+            Some(last) if last.can_merge_with(&vma) => {
+                let joined = last.with_range(PageRange { start: last.range.start, end: vma.range.end });
+                *last = Arc::new(joined);
+            }
+            _ => merged.push(vma),
+        }
+    }
+    merged
+}
+
 impl Drop for VirtualAddressSpace {
     fn drop(&mut self) {
         for vma in self.virtual_memory_areas.read().iter() {
+            if vma.typ == VmaType::Guard && vma.space == MemorySpace::Kernel {
+                // The guard pages of a kernel stack are not mapped, but their (identity mapped) frames belong to the stack
+                let start = PhysFrame::containing_address(PhysAddr::new(vma.start().as_u64()));
+                unsafe { frames::free(PhysFrameRange { start, end: start + vma.range().len() }); }
+                continue;
+            }
             self.page_tables.unmap(vma.range(), true);
         }
     }
@@ -5,33 +5,47 @@
    ║   - init   init ns, called once                                         ║
    ║   - open   open a named object                                          ║
    ║   - read   read bytes from an open object                               ║
+   ║   - read_at  read bytes from an open file at an offset                  ║
    ║   - write  write bytes into an open object                              ║
    ║   - seek   set file pointer (for files)                                 ║
    ║   - mkdi : create a directory                                           ║
    ║   - touch  create a file                                                ║
+   ║   - mount  mount a file system on a directory                           ║
+   ║   - umount unmount a file system                                        ║
+   ║   - read_file  read a whole file (e.g. an executable)                   ║
+   ║   - pipe   create a pipe                                                ║
    ╟─────────────────────────────────────────────────────────────────────────╢
    ║ Author: Michael Schoettner, Univ. Duesseldorf, 23.2.2025                ║
    ╚═════════════════════════════════════════════════════════════════════════╝
 */
 
+use alloc::format;
 use alloc::string::{String, ToString};
 use alloc::sync::Arc;
+use alloc::vec;
 use alloc::vec::Vec;
-use log::info;
+use log::{info, warn};
 use spin::{Mutex, Once};
 
 use super::traits::FileSystem;
+use super::fat32;
 use super::lookup;
+use super::mount;
 use super::open_objects;
 use super::stat::Mode;
+use super::tarfs;
 use super::tmpfs;
 
+use crate::{initrd, storage};
 use naming::shared_types::{OpenOptions, RawDirent, SeekOrigin};
 use syscall::return_vals::Errno;
 
 // root of naming service
 pub(super) static ROOT: Once<Arc<dyn FileSystem>> = Once::new();
 
+// size used to grow the buffer in 'read_file', if the file size is unknown
+const READ_FILE_CHUNK_SIZE: usize = 4096;
+
 // current working directory
 static CWD: Mutex<String> = Mutex::new(String::new());
 
@@ -42,16 +56,38 @@ pub fn init() {
         let tmpfs = tmpfs::TmpFs::new();
         Arc::new(tmpfs)
     });
-    open_objects::open_object_table_init();
     let mut cwd = CWD.lock();
     *cwd = "/".to_string();
+    drop(cwd);
+
+    mount_fat32_volumes();
     info!("naming service initialized");
     //    test::running_tests();
 }
 
-/// Open/create a named object referenced by `path` using the given `flags`. \
+/// Mount every block device containing a FAT32 file system on `/<device name>` (e.g. `/ata0p0`).
+fn mount_fat32_volumes() {
+    for name in storage::block_device_names() {
+        let device = match storage::block_device(&name) {
+            Some(device) => device,
+            None => continue,
+        };
+
+        // Devices without a FAT32 file system are silently skipped
+        if let Ok(fs) = fat32::Fat32::new(device) {
+            let path = format!("/{name}");
+            match mkdir(&path).and_then(|_| mount::mount(&path, Arc::new(fs))) {
+                Ok(_) => info!("FAT32 file system on [{name}] mounted at {path}"),
+                Err(e) => warn!("Failed to mount FAT32 file system on [{name}]: {e:?}"),
+            }
+        }
+    }
+}
+
+/// Open/create a named object referenced by `path` (relative paths start at the current working directory) using the given `flags`. \
 /// Returns `Ok(object_handle)` or `Err`.
 pub fn open(path: &String, flags: OpenOptions) -> Result<usize, Errno> {
+    let path = &absolute_path(path);
     open_objects::open(path, flags).or_else(|e| {
         if flags.contains(OpenOptions::CREATE) {
             touch(path).and_then(|_| open_objects::open(path, flags))
@@ -73,6 +109,12 @@ pub fn read(object_handle: usize, buffer: &mut [u8]) -> Result<usize, Errno> {
     open_objects::read(object_handle, buffer)
 }
 
+/// Read from the file referenced by `object_handle` at `offset` into the given `buffer` (the file pointer is not moved). \
+/// Returns `Ok(number of bytes read)` or `Err`.
+pub fn read_at(object_handle: usize, buffer: &mut [u8], offset: usize) -> Result<usize, Errno> {
+    open_objects::read_at(object_handle, buffer, offset)
+}
+
 /// Move the object pointer for the named object referenced by `object_handle` to the specified `offset` from the `origin`. \
 /// Returns `Ok(nr of bytes seeked)` or `Err`.
 pub fn seek(object_handle: usize, offset: usize, origin: SeekOrigin) -> Result<usize, Errno> {
@@ -85,6 +127,12 @@ pub fn close(object_handle: usize) -> Result<usize, Errno> {
     open_objects::close(object_handle)
 }
 
+/// Create a pipe. \
+/// Returns `Ok((read handle, write handle))` or `Err(errno)`
+pub fn pipe() -> Result<(usize, usize), Errno> {
+    open_objects::pipe()
+}
+
 /// Create a directory for the given `path`. \
 /// Returns `Ok(0)` or `Err(errno)`
 pub fn mkdir(path: &str) -> Result<usize, Errno> {
@@ -229,3 +277,65 @@ pub fn cd(path: &String) -> Result<usize, Errno> {
         }
     }
 }
+
+/// Create a file system of type `fs_type` (`tmpfs`, `fat32` or `initrd`) and mount it on the directory `target`
+/// (relative paths start at the current working directory). \
+/// `source` is the name of the block device (e.g. `ata0p0`) and ignored for `tmpfs` and `initrd`. \
+/// Returns `Ok(0)` or `Err(errno)`
+pub fn mount(source: &str, target: &str, fs_type: &str) -> Result<usize, Errno> {
+    let fs: Arc<dyn FileSystem> = match fs_type {
+        "tmpfs" => Arc::new(tmpfs::TmpFs::new()),
+        "initrd" => Arc::new(tarfs::TarFs::new(initrd())),
+        "fat32" => {
+            let device = storage::block_device(source).ok_or(Errno::ENODEV)?;
+            Arc::new(fat32::Fat32::new(device)?)
+        }
+        _ => return Err(Errno::EINVAL),
+    };
+
+    mount::mount(&absolute_path(target), fs)
+}
+
+/// Unmount the file system mounted on the directory `target` (relative paths start at the current working directory). \
+/// Returns `Ok(0)` or `Err(errno)`
+pub fn umount(target: &str) -> Result<usize, Errno> {
+    mount::umount(&absolute_path(target))
+}
+
+/// Read the whole file referenced by `path` (relative paths start at the current working directory). \
+/// Returns `Ok(file content)` or `Err(errno)`
+pub fn read_file(path: &str) -> Result<Vec<u8>, Errno> {
+    let object = lookup::lookup_named_object(&absolute_path(path))?;
+    let file = object.as_file()?;
+
+    // The size is only a hint, we read until the file system reports the end of the file
+    let mut content = vec![0; file.stat().map_or(0, |stat| stat.size)];
+    let mut pos = 0;
+    loop {
+        if pos == content.len() {
+            content.resize(pos + READ_FILE_CHUNK_SIZE, 0);
+        }
+
+        match file.read(&mut content[pos..], pos, OpenOptions::READONLY)? {
+            0 => break,
+            count => pos += count,
+        }
+    }
+
+    content.truncate(pos);
+    Ok(content)
+}
+
+/// Convert `path` into an absolute path, using the current working directory for relative paths
+fn absolute_path(path: &str) -> String {
+    if path.starts_with('/') {
+        return path.to_string();
+    }
+
+    let cwd = CWD.lock();
+    if cwd.ends_with('/') {
+        format!("{cwd}{path}")
+    } else {
+        format!("{cwd}/{path}")
+    }
+}
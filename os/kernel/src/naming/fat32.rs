@@ -0,0 +1,988 @@
+/* ╔═════════════════════════════════════════════════════════════════════════╗
+   ║ Module: fat32                                                           ║
+   ╟─────────────────────────────────────────────────────────────────────────╢
+   ║ FAT32 file system on top of a block device. Supports reading, writing,  ║
+   ║ creating files and directories as well as long file names (VFAT).       ║
+   ║ There is no block cache, all operations go directly to the device.      ║
+   ╟─────────────────────────────────────────────────────────────────────────╢
+   ║ Author: agent, 18.10.2026                                               ║
+   ╚═════════════════════════════════════════════════════════════════════════╝
+*/
+use alloc::string::String;
+use alloc::sync::Arc;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::fmt;
+use core::result::Result;
+use log::info;
+use crate::sync::Mutex;
+
+use super::stat::{MODE_DIR, MODE_FILE, Mode, Stat};
+use super::traits::{DirectoryObject, FileObject, FileSystem, NamedObject};
+use crate::storage::block::BlockDevice;
+use naming::shared_types::{DirEntry, FileType, OpenOptions};
+use syscall::return_vals::Errno;
+
+const BOOT_SIGNATURE: u16 = 0xaa55;
+const FS_INFO_LEAD_SIGNATURE: u32 = 0x41615252;
+const FS_INFO_STRUCT_SIGNATURE: u32 = 0x61417272;
+const FS_INFO_TRAIL_SIGNATURE: u32 = 0xaa550000;
+const FS_INFO_UNKNOWN: u32 = 0xffffffff;
+
+const FIRST_CLUSTER: u32 = 2; // cluster 0 and 1 are reserved
+const CLUSTER_MASK: u32 = 0x0fffffff; // upper 4 bits of a FAT entry are reserved
+const CLUSTER_FREE: u32 = 0;
+const CLUSTER_END_OF_CHAIN: u32 = 0x0ffffff8; // all values >= mark the end of a chain
+const CLUSTER_END_OF_CHAIN_MARK: u32 = 0x0fffffff;
+
+const DIR_ENTRY_SIZE: usize = 32;
+const ENTRY_END: u8 = 0x00;
+const ENTRY_DELETED: u8 = 0xe5;
+const ENTRY_KANJI_E5: u8 = 0x05; // first byte 0xe5 is stored as 0x05
+
+const ATTR_VOLUME_ID: u8 = 0x08;
+const ATTR_DIRECTORY: u8 = 0x10;
+const ATTR_ARCHIVE: u8 = 0x20;
+const ATTR_LONG_NAME: u8 = 0x0f;
+
+const CASE_LOWER_BASE: u8 = 0x08; // NT flag: base name is lower case
+const CASE_LOWER_EXT: u8 = 0x10;  // NT flag: extension is lower case
+
+const LFN_LAST_ENTRY: u8 = 0x40;
+const LFN_ORDER_MASK: u8 = 0x1f;
+const LFN_CHARS_PER_ENTRY: usize = 13;
+const LFN_CHAR_OFFSETS: [usize; LFN_CHARS_PER_ENTRY] = [1, 3, 5, 7, 9, 14, 16, 18, 20, 22, 24, 28, 30];
+const MAX_NAME_LEN: usize = 255;
+
+const DEFAULT_DATE: u16 = 0x0021; // 1980-01-01, we have no wall clock time here
+
+/// Relevant fields of the BIOS parameter block (BPB) found in the first sector
+struct BootSector {
+    bytes_per_sector: u16,
+    sectors_per_cluster: u8,
+    reserved_sectors: u16,
+    fat_count: u8,
+    total_sectors: u32,
+    fat_size: u32,
+    root_cluster: u32,
+    fs_info_sector: u16,
+}
+
+impl BootSector {
+    fn parse(sector: &[u8]) -> Result<BootSector, Errno> {
+        if read_u16(sector, 510) != BOOT_SIGNATURE {
+            return Err(Errno::EINVAL);
+        }
+
+        // FAT12/16 have a fixed root directory and a 16 bit FAT size
+        let root_entry_count = read_u16(sector, 17);
+        let fat_size_16 = read_u16(sector, 22);
+        if root_entry_count != 0 || fat_size_16 != 0 {
+            return Err(Errno::EINVAL);
+        }
+
+        let total_sectors = match read_u16(sector, 19) {
+            0 => read_u32(sector, 32),
+            count => count as u32,
+        };
+
+        let boot_sector = BootSector {
+            bytes_per_sector: read_u16(sector, 11),
+            sectors_per_cluster: sector[13],
+            reserved_sectors: read_u16(sector, 14),
+            fat_count: sector[16],
+            total_sectors,
+            fat_size: read_u32(sector, 36),
+            root_cluster: read_u32(sector, 44),
+            fs_info_sector: read_u16(sector, 48),
+        };
+
+        if !boot_sector.bytes_per_sector.is_power_of_two()
+            || boot_sector.bytes_per_sector < 512
+            || !boot_sector.sectors_per_cluster.is_power_of_two()
+            || boot_sector.fat_count == 0
+            || boot_sector.fat_size == 0
+        {
+            return Err(Errno::EINVAL);
+        }
+
+        Ok(boot_sector)
+    }
+}
+
+/// Position of a 32 byte directory entry on the device
+#[derive(Debug, Copy, Clone)]
+struct Location {
+    sector: u64,
+    offset: usize,
+}
+
+/// Raw 32 byte directory entry (short name entry or long name entry)
+#[derive(Copy, Clone)]
+struct RawEntry([u8; DIR_ENTRY_SIZE]);
+
+impl RawEntry {
+    fn new_short(short_name: &[u8; 11], attr: u8, first_cluster: u32) -> RawEntry {
+        let mut entry = RawEntry([0; DIR_ENTRY_SIZE]);
+        entry.0[0..11].copy_from_slice(short_name);
+        entry.0[11] = attr;
+        entry.0[16..18].copy_from_slice(&DEFAULT_DATE.to_le_bytes()); // creation date
+        entry.0[18..20].copy_from_slice(&DEFAULT_DATE.to_le_bytes()); // last access date
+        entry.0[24..26].copy_from_slice(&DEFAULT_DATE.to_le_bytes()); // write date
+        entry.set_first_cluster(first_cluster);
+        entry
+    }
+
+    fn new_long(name: &[u16], order: u8, last: bool, checksum: u8) -> RawEntry {
+        let mut entry = RawEntry([0; DIR_ENTRY_SIZE]);
+        entry.0[0] = if last { order | LFN_LAST_ENTRY } else { order };
+        entry.0[11] = ATTR_LONG_NAME;
+        entry.0[13] = checksum;
+
+        // Name is terminated by 0x0000 (if there is space) and padded with 0xffff
+        let start = (order as usize - 1) * LFN_CHARS_PER_ENTRY;
+        for (i, offset) in LFN_CHAR_OFFSETS.iter().enumerate() {
+            let c = match name.get(start + i) {
+                Some(c) => *c,
+                None if start + i == name.len() => 0x0000,
+                None => 0xffff,
+            };
+            entry.0[*offset..*offset + 2].copy_from_slice(&c.to_le_bytes());
+        }
+        entry
+    }
+
+    fn is_end(&self) -> bool {
+        self.0[0] == ENTRY_END
+    }
+
+    fn is_free(&self) -> bool {
+        self.0[0] == ENTRY_END || self.0[0] == ENTRY_DELETED
+    }
+
+    fn attr(&self) -> u8 {
+        self.0[11]
+    }
+
+    fn is_long_name(&self) -> bool {
+        self.attr() & ATTR_LONG_NAME == ATTR_LONG_NAME
+    }
+
+    fn is_volume_id(&self) -> bool {
+        self.attr() & ATTR_VOLUME_ID == ATTR_VOLUME_ID
+    }
+
+    fn is_dir(&self) -> bool {
+        self.attr() & ATTR_DIRECTORY == ATTR_DIRECTORY
+    }
+
+    fn short_name(&self) -> [u8; 11] {
+        let mut name = [0; 11];
+        name.copy_from_slice(&self.0[0..11]);
+        if name[0] == ENTRY_KANJI_E5 {
+            name[0] = ENTRY_DELETED;
+        }
+        name
+    }
+
+    /// Readable version of the 8.3 name, respecting the lower case flags set by Windows NT and Linux
+    fn display_short_name(&self) -> String {
+        let short_name = self.short_name();
+        let case = self.0[12];
+        let mut name = String::new();
+
+        for &c in short_name[0..8].iter().take_while(|&&c| c != b' ') {
+            name.push(convert_case(c, case & CASE_LOWER_BASE != 0));
+        }
+        if short_name[8] != b' ' {
+            name.push('.');
+            for &c in short_name[8..11].iter().take_while(|&&c| c != b' ') {
+                name.push(convert_case(c, case & CASE_LOWER_EXT != 0));
+            }
+        }
+        name
+    }
+
+    fn first_cluster(&self) -> u32 {
+        ((read_u16(&self.0, 20) as u32) << 16) | read_u16(&self.0, 26) as u32
+    }
+
+    fn set_first_cluster(&mut self, cluster: u32) {
+        self.0[20..22].copy_from_slice(&((cluster >> 16) as u16).to_le_bytes());
+        self.0[26..28].copy_from_slice(&(cluster as u16).to_le_bytes());
+    }
+
+    fn size(&self) -> u32 {
+        read_u32(&self.0, 28)
+    }
+
+    fn set_size(&mut self, size: u32) {
+        self.0[28..32].copy_from_slice(&size.to_le_bytes());
+    }
+
+    fn long_name_order(&self) -> u8 {
+        self.0[0] & LFN_ORDER_MASK
+    }
+
+    fn long_name_checksum(&self) -> u8 {
+        self.0[13]
+    }
+
+    fn long_name_chars(&self) -> [u16; LFN_CHARS_PER_ENTRY] {
+        let mut chars = [0; LFN_CHARS_PER_ENTRY];
+        for (i, offset) in LFN_CHAR_OFFSETS.iter().enumerate() {
+            chars[i] = read_u16(&self.0, *offset);
+        }
+        chars
+    }
+}
+
+/// A directory entry with its resolved (long) name and location on disk
+struct Node {
+    name: String,
+    entry: RawEntry,
+    location: Location,
+}
+
+/// Allocation information, mirrored in the FSInfo sector. \
+/// The mutex around it also serializes all modifying operations on the volume.
+struct AllocState {
+    free_count: u32,
+    next_free: u32,
+}
+
+/// Mounted FAT32 volume, shared by all file and directory objects
+struct Volume {
+    device: Arc<dyn BlockDevice + Send + Sync>,
+    boot: BootSector,
+    first_data_sector: u64,
+    cluster_count: u32,
+    alloc_state: Mutex<AllocState>,
+}
+
+impl Volume {
+    fn new(device: Arc<dyn BlockDevice + Send + Sync>) -> Result<Volume, Errno> {
+        let sector_size = device.sector_size() as usize;
+        let mut sector = vec![0u8; sector_size];
+        if device.read(0, 1, &mut sector) != 1 {
+            return Err(Errno::EIO);
+        }
+
+        let boot = BootSector::parse(&sector)?;
+        if boot.bytes_per_sector as usize != sector_size {
+            return Err(Errno::EINVAL);
+        }
+
+        let first_data_sector = boot.reserved_sectors as u64 + boot.fat_count as u64 * boot.fat_size as u64;
+        if first_data_sector >= boot.total_sectors as u64 {
+            return Err(Errno::EINVAL);
+        }
+        let cluster_count = ((boot.total_sectors as u64 - first_data_sector) / boot.sectors_per_cluster as u64) as u32;
+
+        let mut volume = Volume {
+            device,
+            boot,
+            first_data_sector,
+            cluster_count,
+            alloc_state: Mutex::new(AllocState { free_count: FS_INFO_UNKNOWN, next_free: FIRST_CLUSTER }),
+        };
+        if !volume.is_valid_cluster(volume.boot.root_cluster) {
+            return Err(Errno::EINVAL);
+        }
+
+        // FSInfo only contains hints, so we ignore it if it is broken or cannot be read
+        let fs_info_read = volume.read_sectors(volume.boot.fs_info_sector as u64, &mut sector).is_ok();
+        if fs_info_read && read_u32(&sector, 0) == FS_INFO_LEAD_SIGNATURE && read_u32(&sector, 484) == FS_INFO_STRUCT_SIGNATURE {
+            let next_free = read_u32(&sector, 492);
+            let next_free_valid = volume.is_valid_cluster(next_free);
+            let free_count = read_u32(&sector, 488);
+            let cluster_count = volume.cluster_count;
+            let state = volume.alloc_state.get_mut();
+            if free_count <= cluster_count {
+                state.free_count = free_count;
+            }
+            if next_free_valid {
+                state.next_free = next_free;
+            }
+        }
+
+        Ok(volume)
+    }
+
+    fn sector_size(&self) -> usize {
+        self.boot.bytes_per_sector as usize
+    }
+
+    fn cluster_size(&self) -> usize {
+        self.sector_size() * self.boot.sectors_per_cluster as usize
+    }
+
+    fn is_valid_cluster(&self, cluster: u32) -> bool {
+        cluster >= FIRST_CLUSTER && cluster < self.cluster_count + FIRST_CLUSTER
+    }
+
+    fn cluster_to_sector(&self, cluster: u32) -> u64 {
+        self.first_data_sector + (cluster - FIRST_CLUSTER) as u64 * self.boot.sectors_per_cluster as u64
+    }
+
+    /// Read whole sectors starting at `sector` into `buffer` (length must be a multiple of the sector size)
+    fn read_sectors(&self, sector: u64, buffer: &mut [u8]) -> Result<(), Errno> {
+        let count = buffer.len() / self.sector_size();
+        if self.device.read(sector, count, buffer) != count {
+            return Err(Errno::EIO);
+        }
+        Ok(())
+    }
+
+    /// Write whole sectors starting at `sector` from `buffer` (length must be a multiple of the sector size)
+    fn write_sectors(&self, sector: u64, buffer: &[u8]) -> Result<(), Errno> {
+        let count = buffer.len() / self.sector_size();
+        if self.device.write(sector, count, buffer) != count {
+            return Err(Errno::EIO);
+        }
+        Ok(())
+    }
+
+    fn read_cluster(&self, cluster: u32, buffer: &mut [u8]) -> Result<(), Errno> {
+        self.read_sectors(self.cluster_to_sector(cluster), buffer)
+    }
+
+    fn write_cluster(&self, cluster: u32, buffer: &[u8]) -> Result<(), Errno> {
+        self.write_sectors(self.cluster_to_sector(cluster), buffer)
+    }
+
+    /// Return sector number (relative to the FAT start) and byte offset of the FAT entry for `cluster`. \
+    /// Fails with `EIO` for invalid clusters and entries beyond the FAT (corrupted volume).
+    fn fat_position(&self, cluster: u32) -> Result<(u64, usize), Errno> {
+        let offset = cluster as u64 * 4;
+        let fat_sector = offset / self.sector_size() as u64;
+        if !self.is_valid_cluster(cluster) || fat_sector >= self.boot.fat_size as u64 {
+            return Err(Errno::EIO);
+        }
+        Ok((fat_sector, (offset % self.sector_size() as u64) as usize))
+    }
+
+    /// Set the FAT entry of `cluster` to `value` in all FAT copies
+    fn set_fat_entry(&self, cluster: u32, value: u32) -> Result<(), Errno> {
+        let (fat_sector, offset) = self.fat_position(cluster)?;
+        let mut sector = vec![0u8; self.sector_size()];
+
+        for fat in 0..self.boot.fat_count as u64 {
+            let sector_nr = self.boot.reserved_sectors as u64 + fat * self.boot.fat_size as u64 + fat_sector;
+            self.read_sectors(sector_nr, &mut sector)?;
+            let old = read_u32(&sector, offset);
+            let new = (old & !CLUSTER_MASK) | (value & CLUSTER_MASK);
+            sector[offset..offset + 4].copy_from_slice(&new.to_le_bytes());
+            self.write_sectors(sector_nr, &sector)?;
+        }
+        Ok(())
+    }
+
+    /// Follow the cluster chain starting at `first_cluster` and return all clusters of it. \
+    /// FAT sectors are cached while walking, because consecutive clusters usually share a sector.
+    fn cluster_chain(&self, first_cluster: u32) -> Result<Vec<u32>, Errno> {
+        let mut chain = Vec::new();
+        if first_cluster == CLUSTER_FREE {
+            return Ok(chain);
+        }
+
+        let mut sector = vec![0u8; self.sector_size()];
+        let mut cached_sector = None;
+        let mut cluster = first_cluster;
+        loop {
+            // A chain can never be longer than the number of clusters (protects against loops)
+            if chain.len() >= self.cluster_count as usize {
+                return Err(Errno::EIO);
+            }
+            chain.push(cluster);
+
+            let (fat_sector, offset) = self.fat_position(cluster)?;
+            if cached_sector != Some(fat_sector) {
+                self.read_sectors(self.boot.reserved_sectors as u64 + fat_sector, &mut sector)?;
+                cached_sector = Some(fat_sector);
+            }
+
+            let next = read_u32(&sector, offset) & CLUSTER_MASK;
+            if next >= CLUSTER_END_OF_CHAIN {
+                return Ok(chain);
+            }
+            cluster = next;
+        }
+    }
+
+    /// Allocate a free cluster, zero it if `zero` is set, and append it to the chain ending with `previous`.
+    fn alloc_cluster(&self, state: &mut AllocState, previous: Option<u32>, zero: bool) -> Result<u32, Errno> {
+        if state.free_count == 0 {
+            return Err(Errno::ENOSPC);
+        }
+
+        // Search free cluster, starting from the hint
+        let mut sector = vec![0u8; self.sector_size()];
+        let mut cached_sector = None;
+        let mut found = None;
+        for i in 0..self.cluster_count {
+            let cluster = FIRST_CLUSTER + (state.next_free - FIRST_CLUSTER + i) % self.cluster_count;
+            let (fat_sector, offset) = self.fat_position(cluster)?;
+            if cached_sector != Some(fat_sector) {
+                self.read_sectors(self.boot.reserved_sectors as u64 + fat_sector, &mut sector)?;
+                cached_sector = Some(fat_sector);
+            }
+
+            if read_u32(&sector, offset) & CLUSTER_MASK == CLUSTER_FREE {
+                found = Some(cluster);
+                break;
+            }
+        }
+        let cluster = found.ok_or(Errno::ENOSPC)?;
+
+        if zero {
+            self.write_cluster(cluster, &vec![0u8; self.cluster_size()])?;
+        }
+        self.set_fat_entry(cluster, CLUSTER_END_OF_CHAIN_MARK)?;
+        if let Some(previous) = previous {
+            self.set_fat_entry(previous, cluster)?;
+        }
+
+        if state.free_count != FS_INFO_UNKNOWN {
+            state.free_count -= 1;
+        }
+        state.next_free = if cluster + 1 < self.cluster_count + FIRST_CLUSTER { cluster + 1 } else { FIRST_CLUSTER };
+        self.write_fs_info(state)?;
+
+        Ok(cluster)
+    }
+
+    /// Give `cluster` back to the free clusters (it must not be part of a chain anymore)
+    fn free_cluster(&self, state: &mut AllocState, cluster: u32) -> Result<(), Errno> {
+        self.set_fat_entry(cluster, CLUSTER_FREE)?;
+        if state.free_count != FS_INFO_UNKNOWN {
+            state.free_count += 1;
+        }
+        self.write_fs_info(state)
+    }
+
+    /// Write the allocation hints back to the FSInfo sector
+    fn write_fs_info(&self, state: &AllocState) -> Result<(), Errno> {
+        let mut sector = vec![0u8; self.sector_size()];
+        if self.read_sectors(self.boot.fs_info_sector as u64, &mut sector).is_err()
+            || read_u32(&sector, 0) != FS_INFO_LEAD_SIGNATURE
+            || read_u32(&sector, 508) != FS_INFO_TRAIL_SIGNATURE {
+            return Ok(()); // No readable and valid FSInfo sector -> nothing to update
+        }
+
+        sector[488..492].copy_from_slice(&state.free_count.to_le_bytes());
+        sector[492..496].copy_from_slice(&state.next_free.to_le_bytes());
+        self.write_sectors(self.boot.fs_info_sector as u64, &sector)
+    }
+
+    fn read_entry(&self, location: Location) -> Result<RawEntry, Errno> {
+        let mut sector = vec![0u8; self.sector_size()];
+        self.read_sectors(location.sector, &mut sector)?;
+
+        let mut entry = RawEntry([0; DIR_ENTRY_SIZE]);
+        entry.0.copy_from_slice(&sector[location.offset..location.offset + DIR_ENTRY_SIZE]);
+        Ok(entry)
+    }
+
+    fn write_entry(&self, location: Location, entry: &RawEntry) -> Result<(), Errno> {
+        let mut sector = vec![0u8; self.sector_size()];
+        self.read_sectors(location.sector, &mut sector)?;
+        sector[location.offset..location.offset + DIR_ENTRY_SIZE].copy_from_slice(&entry.0);
+        self.write_sectors(location.sector, &sector)
+    }
+
+    /// Read all 32 byte slots of the directory starting at `first_cluster`, including free ones
+    fn read_dir_slots(&self, first_cluster: u32) -> Result<Vec<(Location, RawEntry)>, Errno> {
+        let mut slots = Vec::new();
+        let mut buffer = vec![0u8; self.cluster_size()];
+
+        for cluster in self.cluster_chain(first_cluster)? {
+            self.read_cluster(cluster, &mut buffer)?;
+            let first_sector = self.cluster_to_sector(cluster);
+
+            for (index, raw) in buffer.chunks_exact(DIR_ENTRY_SIZE).enumerate() {
+                let byte_offset = index * DIR_ENTRY_SIZE;
+                let location = Location {
+                    sector: first_sector + (byte_offset / self.sector_size()) as u64,
+                    offset: byte_offset % self.sector_size(),
+                };
+
+                let mut entry = RawEntry([0; DIR_ENTRY_SIZE]);
+                entry.0.copy_from_slice(raw);
+                slots.push((location, entry));
+            }
+        }
+
+        Ok(slots)
+    }
+
+    /// Read all used entries of the directory starting at `first_cluster` and combine long name entries with their short entry
+    fn read_dir(&self, first_cluster: u32) -> Result<Vec<Node>, Errno> {
+        let mut nodes = Vec::new();
+        let mut long_name: Vec<u16> = Vec::new();
+        let mut long_name_checksum = 0;
+        let mut expected_order = 0;
+
+        for (location, entry) in self.read_dir_slots(first_cluster)? {
+            if entry.is_end() {
+                break;
+            }
+            if entry.is_free() {
+                expected_order = 0;
+                continue;
+            }
+
+            if entry.is_long_name() {
+                // Long name entries are stored in reverse order in front of the short entry
+                let order = entry.long_name_order();
+                if entry.0[0] & LFN_LAST_ENTRY != 0 {
+                    long_name = vec![0xffff; order as usize * LFN_CHARS_PER_ENTRY];
+                    long_name_checksum = entry.long_name_checksum();
+                    expected_order = order;
+                } else if order == 0 || order != expected_order || entry.long_name_checksum() != long_name_checksum {
+                    expected_order = 0; // Orphaned long name entry
+                    continue;
+                }
+
+                if expected_order > 0 {
+                    let start = (order as usize - 1) * LFN_CHARS_PER_ENTRY;
+                    long_name[start..start + LFN_CHARS_PER_ENTRY].copy_from_slice(&entry.long_name_chars());
+                    expected_order = order - 1;
+                }
+                continue;
+            }
+
+            // Short entry -> use long name, if it belongs to this entry
+            let name = if expected_order == 0 && !long_name.is_empty() && checksum(&entry.short_name()) == long_name_checksum {
+                let len = long_name.iter().position(|&c| c == 0x0000 || c == 0xffff).unwrap_or(long_name.len());
+                char::decode_utf16(long_name[..len].iter().copied())
+                    .map(|c| c.unwrap_or(char::REPLACEMENT_CHARACTER))
+                    .collect()
+            } else {
+                entry.display_short_name()
+            };
+            long_name.clear();
+            expected_order = 0;
+
+            nodes.push(Node { name, entry, location });
+        }
+
+        Ok(nodes)
+    }
+
+    /// Create a new directory entry `name` in the directory starting at `dir_cluster`
+    fn create_entry(&self, state: &mut AllocState, dir_cluster: u32, name: &str, attr: u8, first_cluster: u32) -> Result<Node, Errno> {
+        check_name(name)?;
+        let nodes = self.read_dir(dir_cluster)?;
+        if nodes.iter().any(|node| node.name.eq_ignore_ascii_case(name)) {
+            return Err(Errno::EEXIST);
+        }
+
+        // Build short entry and long name entries (if needed)
+        let (short_name, needs_long_name) = short_name_for(name, &nodes);
+        let mut entries = Vec::new();
+        if needs_long_name {
+            let utf16: Vec<u16> = name.encode_utf16().collect();
+            let count = utf16.len().div_ceil(LFN_CHARS_PER_ENTRY) as u8;
+            let checksum = checksum(&short_name);
+            for order in (1..=count).rev() {
+                entries.push(RawEntry::new_long(&utf16, order, order == count, checksum));
+            }
+        }
+        let short_entry = RawEntry::new_short(&short_name, attr, first_cluster);
+        entries.push(short_entry);
+
+        // Search enough consecutive free slots, extend directory if needed
+        let mut slots = self.read_dir_slots(dir_cluster)?;
+        let start = match find_free_slots(&slots, entries.len()) {
+            Some(start) => start,
+            None => {
+                let chain = self.cluster_chain(dir_cluster)?;
+                let new_cluster = self.alloc_cluster(state, chain.last().copied(), true)?;
+                let first_sector = self.cluster_to_sector(new_cluster);
+                for index in 0..self.cluster_size() / DIR_ENTRY_SIZE {
+                    let byte_offset = index * DIR_ENTRY_SIZE;
+                    let location = Location {
+                        sector: first_sector + (byte_offset / self.sector_size()) as u64,
+                        offset: byte_offset % self.sector_size(),
+                    };
+                    slots.push((location, RawEntry([0; DIR_ENTRY_SIZE])));
+                }
+                find_free_slots(&slots, entries.len()).ok_or(Errno::ENOSPC)?
+            }
+        };
+
+        for (i, entry) in entries.iter().enumerate() {
+            self.write_entry(slots[start + i].0, entry)?;
+        }
+
+        Ok(Node {
+            name: String::from(name),
+            entry: short_entry,
+            location: slots[start + entries.len() - 1].0,
+        })
+    }
+
+    /// Create a named object for the given directory `node`
+    fn named_object(self: &Arc<Self>, node: &Node) -> NamedObject {
+        if node.entry.is_dir() {
+            let dir: Arc<dyn DirectoryObject> = Arc::new(Dir::new(Arc::clone(self), node.entry.first_cluster()));
+            dir.into()
+        } else {
+            let file: Arc<dyn FileObject> = Arc::new(File::new(Arc::clone(self), node.location));
+            file.into()
+        }
+    }
+}
+
+pub struct Fat32 {
+    root_dir: Arc<Dir>,
+}
+
+impl Fat32 {
+    /// Open the FAT32 file system on `device`. \
+    /// Returns `Err(Errno::EINVAL)` if the device does not contain a FAT32 file system.
+    pub fn new(device: Arc<dyn BlockDevice + Send + Sync>) -> Result<Fat32, Errno> {
+        let volume = Arc::new(Volume::new(device)?);
+        info!(
+            "FAT32 volume: {} clusters of {} bytes, root directory at cluster {}",
+            volume.cluster_count,
+            volume.cluster_size(),
+            volume.boot.root_cluster
+        );
+
+        let root_cluster = volume.boot.root_cluster;
+        Ok(Fat32 {
+            root_dir: Arc::new(Dir::new(volume, root_cluster)),
+        })
+    }
+}
+
+impl FileSystem for Fat32 {
+    fn root_dir(&self) -> Arc<dyn DirectoryObject> {
+        self.root_dir.clone()
+    }
+}
+
+pub struct Dir {
+    volume: Arc<Volume>,
+    first_cluster: u32,
+}
+
+impl Dir {
+    fn new(volume: Arc<Volume>, first_cluster: u32) -> Dir {
+        // '..' entries pointing to the root directory contain cluster 0
+        let first_cluster = if first_cluster == CLUSTER_FREE { volume.boot.root_cluster } else { first_cluster };
+        Dir { volume, first_cluster }
+    }
+
+    /// Visible entries of this directory (without '.', '..' and the volume label)
+    fn visible_nodes(&self) -> Result<Vec<Node>, Errno> {
+        let mut nodes = self.volume.read_dir(self.first_cluster)?;
+        nodes.retain(|node| !node.entry.is_volume_id() && node.name != "." && node.name != "..");
+        Ok(nodes)
+    }
+}
+
+impl DirectoryObject for Dir {
+    fn lookup(&self, name: &str) -> Result<NamedObject, Errno> {
+        self.visible_nodes()?
+            .iter()
+            .find(|node| node.name.eq_ignore_ascii_case(name))
+            .map(|node| self.volume.named_object(node))
+            .ok_or(Errno::ENOENT)
+    }
+
+    fn create_file(&self, name: &str, _mode: Mode) -> Result<NamedObject, Errno> {
+        let mut state = self.volume.alloc_state.lock();
+        let node = self.volume.create_entry(&mut state, self.first_cluster, name, ATTR_ARCHIVE, CLUSTER_FREE)?;
+        Ok(self.volume.named_object(&node))
+    }
+
+    fn create_dir(&self, name: &str, _mode: Mode) -> Result<NamedObject, Errno> {
+        let mut state = self.volume.alloc_state.lock();
+        let cluster = self.volume.alloc_cluster(&mut state, None, true)?;
+
+        let node = match self.volume.create_entry(&mut state, self.first_cluster, name, ATTR_DIRECTORY, cluster) {
+            Ok(node) => node,
+            Err(e) => {
+                self.volume.free_cluster(&mut state, cluster)?;
+                return Err(e);
+            }
+        };
+
+        // Every directory (except root) starts with '.' and '..'
+        let parent_cluster = if self.first_cluster == self.volume.boot.root_cluster { CLUSTER_FREE } else { self.first_cluster };
+        let first_sector = self.volume.cluster_to_sector(cluster);
+        self.volume.write_entry(Location { sector: first_sector, offset: 0 }, &RawEntry::new_short(b".          ", ATTR_DIRECTORY, cluster))?;
+        self.volume.write_entry(Location { sector: first_sector, offset: DIR_ENTRY_SIZE }, &RawEntry::new_short(b"..         ", ATTR_DIRECTORY, parent_cluster))?;
+
+        Ok(self.volume.named_object(&node))
+    }
+
+    fn stat(&self) -> Result<Stat, Errno> {
+        Ok(Stat::new(Mode::new(MODE_DIR), 0))
+    }
+
+    fn readdir(&self, index: usize) -> Result<Option<DirEntry>, Errno> {
+        Ok(self.visible_nodes()?.into_iter().nth(index).map(|node| DirEntry {
+            file_type: if node.entry.is_dir() { FileType::Directory } else { FileType::Regular },
+            name: node.name,
+        }))
+    }
+}
+
+impl fmt::Debug for Dir {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Fat32Dir").field("first_cluster", &self.first_cluster).finish()
+    }
+}
+
+/// A file only knows the location of its directory entry. \
+/// The entry is read for every operation, so that all objects for the same file see the same size.
+pub struct File {
+    volume: Arc<Volume>,
+    location: Location,
+}
+
+impl File {
+    fn new(volume: Arc<Volume>, location: Location) -> File {
+        File { volume, location }
+    }
+
+    /// Release all clusters behind the first `size` bytes (`state` must be locked by the caller)
+    fn shrink(&self, state: &mut AllocState, size: usize) -> Result<(), Errno> {
+        let mut entry = self.volume.read_entry(self.location)?;
+        let chain = self.volume.cluster_chain(entry.first_cluster())?;
+
+        let keep = size.div_ceil(self.volume.cluster_size());
+        if keep == 0 {
+            entry.set_first_cluster(CLUSTER_FREE);
+        } else if keep < chain.len() {
+            self.volume.set_fat_entry(chain[keep - 1], CLUSTER_END_OF_CHAIN_MARK)?;
+        }
+        entry.set_size(size as u32);
+        self.volume.write_entry(self.location, &entry)?;
+
+        // Entry does not reference the clusters anymore, so they can be freed now
+        for cluster in chain.iter().skip(keep) {
+            self.volume.free_cluster(state, *cluster)?;
+        }
+        Ok(())
+    }
+
+    /// Write `buf` at `offset`, extending the file if necessary (`state` must be locked by the caller)
+    fn write_at(&self, state: &mut AllocState, buf: &[u8], offset: usize) -> Result<usize, Errno> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+        let end = offset.checked_add(buf.len()).filter(|&end| end <= u32::MAX as usize).ok_or(Errno::ENOSPC)?;
+
+        let mut entry = self.volume.read_entry(self.location)?;
+        let cluster_size = self.volume.cluster_size();
+
+        // Make sure, the cluster chain is long enough (new clusters are zeroed to avoid leaking old data)
+        let mut chain = self.volume.cluster_chain(entry.first_cluster())?;
+        while chain.len() < end.div_ceil(cluster_size) {
+            let cluster = self.volume.alloc_cluster(state, chain.last().copied(), true)?;
+            if chain.is_empty() {
+                entry.set_first_cluster(cluster);
+            }
+            chain.push(cluster);
+        }
+
+        let mut cluster_buffer = vec![0u8; cluster_size];
+        let mut done = 0;
+        while done < buf.len() {
+            let pos = offset + done;
+            let cluster = chain[pos / cluster_size];
+            let start = pos % cluster_size;
+            let count = (cluster_size - start).min(buf.len() - done);
+
+            if count == cluster_size {
+                self.volume.write_cluster(cluster, &buf[done..done + count])?;
+            } else {
+                self.volume.read_cluster(cluster, &mut cluster_buffer)?;
+                cluster_buffer[start..start + count].copy_from_slice(&buf[done..done + count]);
+                self.volume.write_cluster(cluster, &cluster_buffer)?;
+            }
+            done += count;
+        }
+
+        if end > entry.size() as usize {
+            entry.set_size(end as u32);
+        }
+        entry.0[11] |= ATTR_ARCHIVE;
+        self.volume.write_entry(self.location, &entry)?;
+
+        Ok(buf.len())
+    }
+}
+
+impl FileObject for File {
+    fn stat(&self) -> Result<Stat, Errno> {
+        let entry = self.volume.read_entry(self.location)?;
+        Ok(Stat::new(Mode::new(MODE_FILE), entry.size() as usize))
+    }
+
+    fn read(&self, buf: &mut [u8], offset: usize, _options: OpenOptions) -> Result<usize, Errno> {
+        let entry = self.volume.read_entry(self.location)?;
+        let size = entry.size() as usize;
+        if offset >= size {
+            return Ok(0);
+        }
+
+        let len = buf.len().min(size - offset);
+        let cluster_size = self.volume.cluster_size();
+        let chain = self.volume.cluster_chain(entry.first_cluster())?;
+        let mut cluster_buffer = vec![0u8; cluster_size];
+
+        let mut done = 0;
+        while done < len {
+            let pos = offset + done;
+            let cluster = *chain.get(pos / cluster_size).ok_or(Errno::EIO)?;
+            let start = pos % cluster_size;
+            let count = (cluster_size - start).min(len - done);
+
+            self.volume.read_cluster(cluster, &mut cluster_buffer)?;
+            buf[done..done + count].copy_from_slice(&cluster_buffer[start..start + count]);
+            done += count;
+        }
+
+        Ok(len)
+    }
+
+    fn write(&self, buf: &[u8], offset: usize, _options: OpenOptions) -> Result<usize, Errno> {
+        let mut state = self.volume.alloc_state.lock();
+        self.write_at(&mut state, buf, offset)
+    }
+
+    fn truncate(&self, size: usize) -> Result<(), Errno> {
+        // Size check and resizing must not be interleaved with other writers
+        let mut state = self.volume.alloc_state.lock();
+        let current_size = self.volume.read_entry(self.location)?.size() as usize;
+        if size > current_size {
+            // Clusters behind the end of file are not necessarily zeroed
+            self.write_at(&mut state, &vec![0u8; size - current_size], current_size).map(|_| ())
+        } else {
+            self.shrink(&mut state, size)
+        }
+    }
+}
+
+impl fmt::Debug for File {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Fat32File").field("location", &self.location).finish()
+    }
+}
+
+/// Characters allowed in short names besides letters and digits
+const SHORT_NAME_SPECIAL_CHARS: &[u8] = b"$%'-_@~`!(){}^#&";
+
+/// Characters not allowed in long names
+const INVALID_NAME_CHARS: &[char] = &['"', '*', '/', ':', '<', '>', '?', '\\', '|'];
+
+fn check_name(name: &str) -> Result<(), Errno> {
+    if name.is_empty()
+        || name == "."
+        || name == ".."
+        || name.encode_utf16().count() > MAX_NAME_LEN
+        || name.chars().any(|c| (c as u32) < 0x20 || INVALID_NAME_CHARS.contains(&c))
+    {
+        return Err(Errno::EINVAL);
+    }
+    Ok(())
+}
+
+/// Compute a unique 8.3 name for `name` within the directory containing `nodes`. \
+/// Returns the short name and whether long name entries are needed.
+fn short_name_for(name: &str, nodes: &[Node]) -> ([u8; 11], bool) {
+    let trimmed = name.trim_start_matches('.');
+    let (base, ext) = match trimmed.rfind('.') {
+        Some(pos) => (&trimmed[..pos], &trimmed[pos + 1..]),
+        None => (trimmed, ""),
+    };
+
+    let mut lossy = trimmed.len() != name.len();
+    let mut convert = |part: &str, max: usize| -> Vec<u8> {
+        let mut converted = Vec::new();
+        for c in part.chars() {
+            let c = match c {
+                ' ' | '.' => {
+                    lossy = true;
+                    continue;
+                }
+                c if c.is_ascii_alphanumeric() || (c.is_ascii() && SHORT_NAME_SPECIAL_CHARS.contains(&(c as u8))) => c.to_ascii_uppercase() as u8,
+                _ => {
+                    lossy = true;
+                    b'_'
+                }
+            };
+            if converted.len() == max {
+                lossy = true;
+                break;
+            }
+            converted.push(c);
+        }
+        converted
+    };
+    let base = convert(base, 8);
+    let ext = convert(ext, 3);
+
+    let mut short_name = [b' '; 11];
+    short_name[8..8 + ext.len()].copy_from_slice(&ext);
+
+    // Name fits exactly (only upper case) -> no long name needed
+    if !lossy && !base.is_empty() && base.iter().chain(ext.iter()).copied().eq(name.bytes().filter(|&c| c != b'.')) {
+        short_name[..base.len()].copy_from_slice(&base);
+        return (short_name, false);
+    }
+
+    // Generate numeric tail (e.g. 'LONGFI~1.TXT'), that does not exist yet in the directory
+    let base = if base.is_empty() { vec![b'_'] } else { base };
+    for n in 1u32.. {
+        let tail = alloc::format!("~{n}");
+        let base_len = base.len().min(8 - tail.len());
+        let mut candidate = short_name;
+        candidate[..base_len].copy_from_slice(&base[..base_len]);
+        candidate[base_len..base_len + tail.len()].copy_from_slice(tail.as_bytes());
+
+        if !nodes.iter().any(|node| node.entry.short_name() == candidate) {
+            return (candidate, true);
+        }
+    }
+    unreachable!()
+}
+
+/// Checksum of a short name, stored in all long name entries belonging to it
+fn checksum(short_name: &[u8; 11]) -> u8 {
+    short_name.iter().fold(0u8, |sum, &c| (sum >> 1).wrapping_add(sum << 7).wrapping_add(c))
+}
+
+/// Find `count` consecutive free slots and return the index of the first one
+fn find_free_slots(slots: &[(Location, RawEntry)], count: usize) -> Option<usize> {
+    let mut run = 0;
+    for (index, (_, entry)) in slots.iter().enumerate() {
+        if entry.is_free() {
+            run += 1;
+            if run == count {
+                return Some(index + 1 - count);
+            }
+        } else {
+            run = 0;
+        }
+    }
+    None
+}
+
+fn convert_case(c: u8, lower: bool) -> char {
+    if lower { c.to_ascii_lowercase() as char } else { c as char }
+}
+
+fn read_u16(buffer: &[u8], offset: usize) -> u16 {
+    u16::from_le_bytes([buffer[offset], buffer[offset + 1]])
+}
+
+fn read_u32(buffer: &[u8], offset: usize) -> u32 {
+    u32::from_le_bytes([buffer[offset], buffer[offset + 1], buffer[offset + 2], buffer[offset + 3]])
+}
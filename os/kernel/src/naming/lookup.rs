@@ -7,9 +7,9 @@
    ╚═════════════════════════════════════════════════════════════════════════╝
 */
 use alloc::string::String;
-use alloc::vec::Vec;
 use alloc::sync::Arc;
 use super::api::ROOT;
+use super::mount;
 use super::traits;
 use super::traits::{NamedObject, DirectoryObject};
 use syscall::return_vals::Errno;
@@ -23,43 +23,29 @@ pub(super) fn lookup_dir(path: &String) -> Result<Arc<dyn DirectoryObject>, Errn
 }
 
 /// Resolves absolute `path` into a named object. \
+/// If a directory on the way is a mount point, the lookup continues in the root directory of the mounted file system. \
 /// Returns `Ok(NamedObject)` or `Err`
 pub(super) fn lookup_named_object(path: &String) -> Result<NamedObject, Errno> {
-    let mut found_named_object;
-
-    if check_absolute_path(path) {
-        if path == "/" {
-            found_named_object = traits::as_named_object(ROOT.get().unwrap().root_dir());
-            return Ok(found_named_object);
-        }
-        let mut components: Vec<&str> = path.split("/").collect();
-        components.remove(0); // remove empty string at position 0
+    if !check_absolute_path(path) {
+        return Err(Errno::ENOENT);
+    }
 
-        // get root directory and open the desired file
-        let mut current_dir = ROOT.get().unwrap().root_dir();
-        let mut len = components.len();
-        let mut found;
-        for component in &components {
-            found = current_dir.lookup(component);
-            if found.is_err() {
-                return Err(Errno::ENOENT);
-            }
-            found_named_object = found.unwrap();
+    // start at root directory and walk through all components (empty ones and '.' are skipped, e.g. trailing '/')
+    let mut found_named_object = traits::as_named_object(ROOT.get().unwrap().root_dir());
+    let mut current_path = String::new();
+    for component in path.split("/").filter(|component| !component.is_empty() && *component != ".") {
+        // we are about to descend, so the object found so far must be a directory
+        let current_dir = found_named_object.as_dir().map_err(|_| Errno::ENOENT)?.clone();
+        found_named_object = current_dir.lookup(component).map_err(|_| Errno::ENOENT)?;
 
-            // if not last component, this must be a directory
-            if len > 1 {
-                if !found_named_object.is_dir() {
-                    return Err(Errno::ENOENT);
-                }
-                current_dir = found_named_object.as_dir().unwrap().clone();
-            // if this is the last component, this must be a file or directory (see flags)
-            } else {
-                return Ok(found_named_object.clone());
-            }
-            len -= 1;
+        current_path.push('/');
+        current_path.push_str(component);
+        if let Some(mounted_dir) = mount::mounted_root_dir(&current_path) {
+            found_named_object = traits::as_named_object(mounted_dir);
         }
     }
-    Err(Errno::ENOENT)
+
+    Ok(found_named_object)
 }
 
 /// Helper function for checking if `path` is an abolute path
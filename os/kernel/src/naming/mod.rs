@@ -1,7 +1,12 @@
 pub mod api;
 pub mod stat;
+pub mod open_objects;
 
-mod open_objects;
 mod tmpfs;
+mod fat32;
+mod tarfs;
+pub mod pipe;
+mod terminal;
 mod lookup;
+mod mount;
 mod traits;
\ No newline at end of file
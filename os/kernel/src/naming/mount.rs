@@ -0,0 +1,101 @@
+/* ╔═════════════════════════════════════════════════════════════════════════╗
+   ║ Module: mount                                                           ║
+   ╟─────────────────────────────────────────────────────────────────────────╢
+   ║ Mount table of the naming service. Each entry maps an absolute path to  ║
+   ║ the file system mounted there. The lookup consults the table whenever   ║
+   ║ it crosses a directory, to continue in the mounted file system.         ║
+   ╟─────────────────────────────────────────────────────────────────────────╢
+   ║ Author: agent, 18.10.2026                                               ║
+   ╚═════════════════════════════════════════════════════════════════════════╝
+*/
+use alloc::string::String;
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use spin::RwLock;
+use crate::sync::Mutex;
+
+use super::lookup;
+use super::traits::{DirectoryObject, FileSystem};
+use syscall::return_vals::Errno;
+
+struct MountPoint {
+    path: String,
+    fs: Arc<dyn FileSystem>,
+}
+
+static MOUNT_TABLE: RwLock<Vec<MountPoint>> = RwLock::new(Vec::new());
+
+/// Serializes `mount` and `umount`, so that the checks of the mount point and the update of the table are atomic
+/// (the table itself cannot stay locked, because looking up the mount point needs it)
+static MOUNT_LOCK: Mutex<()> = Mutex::new(());
+
+/// Mount `fs` on the existing directory `path`. \
+/// Returns `Ok(0)` or `Err(errno)`
+pub(super) fn mount(path: &str, fs: Arc<dyn FileSystem>) -> Result<usize, Errno> {
+    let path = normalize(path)?;
+    if path == "/" {
+        return Err(Errno::EBUSY); // root file system is fixed
+    }
+
+    let _mount_lock = MOUNT_LOCK.lock();
+    if MOUNT_TABLE.read().iter().any(|mount_point| mount_point.path == path) {
+        return Err(Errno::EBUSY);
+    }
+
+    // Mount point must be an existing directory (lookup needs the table, so we lock it afterwards)
+    lookup::lookup_dir(&path)?;
+
+    MOUNT_TABLE.write().push(MountPoint { path, fs });
+    Ok(0)
+}
+
+/// Unmount the file system mounted on `path`. \
+/// Returns `Ok(0)` or `Err(errno)`
+pub(super) fn umount(path: &str) -> Result<usize, Errno> {
+    let path = normalize(path)?;
+    let _mount_lock = MOUNT_LOCK.lock();
+    let mut table = MOUNT_TABLE.write();
+
+    // File systems mounted below `path` must be unmounted first
+    let prefix = path.clone() + "/";
+    if table.iter().any(|mount_point| mount_point.path.starts_with(&prefix)) {
+        return Err(Errno::EBUSY);
+    }
+
+    let index = table
+        .iter()
+        .position(|mount_point| mount_point.path == path)
+        .ok_or(Errno::EINVAL)?;
+    table.remove(index);
+    Ok(0)
+}
+
+/// Return the root directory of the file system mounted on `path` (must be normalized), if any.
+pub(super) fn mounted_root_dir(path: &str) -> Option<Arc<dyn DirectoryObject>> {
+    MOUNT_TABLE
+        .read()
+        .iter()
+        .find(|mount_point| mount_point.path == path)
+        .map(|mount_point| mount_point.fs.root_dir())
+}
+
+/// Remove empty components (e.g. trailing or double slashes) and resolve `.` and `..` in absolute `path`
+/// (relative paths must be converted by the caller, see `api::absolute_path`). \
+/// Like in POSIX, `..` in the root directory refers to the root directory itself.
+fn normalize(path: &str) -> Result<String, Errno> {
+    if !path.starts_with('/') {
+        return Err(Errno::EINVAL);
+    }
+
+    let mut components: Vec<&str> = Vec::new();
+    for component in path.split('/') {
+        match component {
+            "" | "." => {}
+            ".." => {
+                components.pop();
+            }
+            _ => components.push(component),
+        }
+    }
+    Ok(String::from("/") + &components.join("/"))
+}
@@ -1,44 +1,40 @@
 /* ╔═════════════════════════════════════════════════════════════════════════╗
    ║ Module: open_objects                                                    ║
    ╟─────────────────────────────────────────────────────────────────────────╢
-   ║ Managing opened objects in a global table (OPEN_OBJECTS). And providing ║
-   ║ all major functions for the naming service.                             ║
+   ║ Managing opened objects in a table per process (OpenObjectTable). And   ║
+   ║ providing all major functions for the naming service.                   ║
    ╟─────────────────────────────────────────────────────────────────────────╢
    ║ Author: Michael Schoettner, Univ. Duesseldorf, 30.12.2024               ║
    ╚═════════════════════════════════════════════════════════════════════════╝
 */
 use alloc::string::String;
-use alloc::boxed::Box;
 use alloc::sync::Arc;
 use alloc::vec::Vec;
 use core::result::Result;
 use core::sync::atomic::{AtomicUsize, Ordering};
-use spin::{Mutex, Once};
 
-use super::traits::NamedObject;
+use super::traits::{FileObject, NamedObject};
 use super::lookup;
+use super::pipe;
+use super::terminal::Terminal;
+use crate::process_manager;
 use naming::shared_types::{DirEntry, OpenOptions, SeekOrigin};
 use syscall::return_vals::{Errno, SyscallResult};
 
 
-/// Max. number of open objetcs
-const MAX_OPEN_OBJECTS: usize = 0x1000;
+/// Max. number of open objects per process
+const MAX_OPEN_OBJECTS: usize = 0x100;
 
-/// Helper function returning safe access to the open object table (oot)
-fn get_open_object_table() -> Arc<Mutex<Box<OpenObjectTable>>> {
-    OPEN_OBJECTS.get().unwrap().clone()
+/// Helper function returning the opened object for `fh` from the table of the current process. \
+/// The table is not locked during the operation on the object, because this might block (e.g. terminal input).
+fn lookup_opened_object(fh: usize) -> Result<Arc<OpenedObject>, Errno> {
+    process_manager().read().current_process().open_objects.lock().lookup_opened_object(fh)
 }
 
-static OPEN_OBJECTS: Once<Arc<Mutex<Box<OpenObjectTable>>>> = Once::new();
-
-struct OpenObjectTable {
-    open_handles: Vec<(usize, Option<Arc<OpenedObject>>)>,
-    free_handles: Box<[usize; MAX_OPEN_OBJECTS]>,
-}
-
-
-pub(super) fn open_object_table_init() {
-    OPEN_OBJECTS.call_once(|| Arc::new(Mutex::new(OpenObjectTable::new())));
+/// Table of opened objects, owned by a process. \
+/// Handles are indices into `open_handles`, free handles are `None` and reused (lowest first).
+pub struct OpenObjectTable {
+    open_handles: Vec<Option<Arc<OpenedObject>>>,
 }
 
 pub(super) fn open(path: &String, flags: OpenOptions) -> Result<usize, Errno> {
@@ -56,8 +52,16 @@ pub(super) fn open(path: &String, flags: OpenOptions) -> Result<usize, Errno> {
         }
     }
 
+    // discard old content of a file, if requested
+    if flags.contains(OpenOptions::TRUNCATE) {
+        found_named_object.as_file()?.truncate(0)?;
+    }
+
     // try to allocate an new handle
-    get_open_object_table()
+    process_manager()
+        .read()
+        .current_process()
+        .open_objects
         .lock()
         .allocate_handle(Arc::new(OpenedObject::new(
             Arc::new(found_named_object),
@@ -67,144 +71,175 @@ pub(super) fn open(path: &String, flags: OpenOptions) -> Result<usize, Errno> {
 }
 
 pub(super) fn write(fh: usize, buf: &[u8]) -> Result<usize, Errno> {
-    get_open_object_table()
-        .lock()
-        .lookup_opened_object(fh)
-        .and_then(|opened_object| {
-            // Make `opened_object` mutable here
-            opened_object.named_object.as_file().and_then(|file| {
-                let pos = opened_object.pos.load(Ordering::SeqCst);
-                let bytes_written = file.write(buf, pos, opened_object.options)?;
-                opened_object
-                    .pos
-                    .store(pos + bytes_written, Ordering::SeqCst);
-                Ok(bytes_written) // Return the bytes written
-            })
-        })
+    let opened_object = lookup_opened_object(fh)?;
+    opened_object.named_object.as_file().and_then(|file| {
+        let pos = opened_object.pos.load(Ordering::SeqCst);
+        let bytes_written = file.write(buf, pos, opened_object.options)?;
+        opened_object
+            .pos
+            .store(pos + bytes_written, Ordering::SeqCst);
+        Ok(bytes_written) // Return the bytes written
+    })
 }
 
 pub(super) fn read(fh: usize, buf: &mut [u8]) -> Result<usize, Errno> {
-    get_open_object_table()
-        .lock()
-        .lookup_opened_object(fh)
-        .and_then(|opened_object| {
-            // Make `opened_object` mutable here
-            opened_object.named_object.as_file().and_then(|file| {
-                let pos = opened_object.pos.load(Ordering::SeqCst);
-                let bytes_read = file.read(buf, pos, opened_object.options)?;
-                opened_object.pos.store(pos + bytes_read, Ordering::SeqCst);
-                Ok(bytes_read) // Return the bytes read
-            })
-        })
+    let opened_object = lookup_opened_object(fh)?;
+    opened_object.named_object.as_file().and_then(|file| {
+        let pos = opened_object.pos.load(Ordering::SeqCst);
+        let bytes_read = file.read(buf, pos, opened_object.options)?;
+        opened_object.pos.store(pos + bytes_read, Ordering::SeqCst);
+        Ok(bytes_read) // Return the bytes read
+    })
+}
+
+/// Read from the file referenced by `fh` at `offset` into `buf`, without moving the file pointer
+pub(super) fn read_at(fh: usize, buf: &mut [u8], offset: usize) -> Result<usize, Errno> {
+    let opened_object = lookup_opened_object(fh)?;
+    opened_object.named_object.as_file().and_then(|file| file.read(buf, offset, opened_object.options))
 }
 
 pub fn seek(fh: usize, offset: usize, origin: SeekOrigin) -> Result<usize, Errno> {
-    get_open_object_table()
-        .lock()
-        .lookup_opened_object(fh)
-        .and_then(|opened_object| {
-            // Make `opened_object` mutable here
-            opened_object.named_object.as_file().and_then(|file| {
-                let new_pos = match origin {
-                    SeekOrigin::Start => offset,
-                    SeekOrigin::End => file.stat()?.size + offset,
-                    SeekOrigin::Current => opened_object.pos.load(Ordering::SeqCst) + offset,
-                };
-                opened_object.pos.store(new_pos, Ordering::SeqCst);
-                Ok(0) // Success
-            })
-        })
+    let opened_object = lookup_opened_object(fh)?;
+    opened_object.named_object.as_file().and_then(|file| {
+        let new_pos = match origin {
+            SeekOrigin::Start => offset,
+            SeekOrigin::End => file.stat()?.size + offset,
+            SeekOrigin::Current => opened_object.pos.load(Ordering::SeqCst) + offset,
+        };
+        opened_object.pos.store(new_pos, Ordering::SeqCst);
+        Ok(0) // Success
+    })
 }
 
 pub(super) fn readdir(fh: usize) -> Result<Option<DirEntry>, Errno> {
-    get_open_object_table()
-        .lock()
-        .lookup_opened_object(fh)
-        .and_then(|opened_object| {
-            // Make `opened_object` mutable here
-            opened_object.named_object.as_dir().and_then(|dir| {
-                let pos = opened_object.pos.load(Ordering::SeqCst);
-                let dir_entry = dir.readdir(pos)?;
-                opened_object.pos.store(pos + 1, Ordering::SeqCst);
-                Ok(dir_entry) // Return the DirEntry
-            })
-        })
+    let opened_object = lookup_opened_object(fh)?;
+    opened_object.named_object.as_dir().and_then(|dir| {
+        let pos = opened_object.pos.load(Ordering::SeqCst);
+        let dir_entry = dir.readdir(pos)?;
+        opened_object.pos.store(pos + 1, Ordering::SeqCst);
+        Ok(dir_entry) // Return the DirEntry
+    })
 }
 
-pub(super) fn close(handle: usize) -> Result<usize, Errno> {
-    get_open_object_table().lock().free_handle(handle)
+/// Create a pipe and return the handles for the read and the write end
+pub(super) fn pipe() -> Result<(usize, usize), Errno> {
+    let (reader, writer) = pipe::create();
+    let reader: Arc<dyn FileObject> = reader;
+    let writer: Arc<dyn FileObject> = writer;
+
+    let process = process_manager().read().current_process();
+    let mut table = process.open_objects.lock();
+    let read_handle = table.allocate_handle(Arc::new(OpenedObject::new(
+        Arc::new(NamedObject::from(reader)),
+        AtomicUsize::new(0),
+        OpenOptions::READONLY,
+    )))?;
+    let write_handle = table.allocate_handle(Arc::new(OpenedObject::new(
+        Arc::new(NamedObject::from(writer)),
+        AtomicUsize::new(0),
+        OpenOptions::READWRITE,
+    )));
+
+    match write_handle {
+        Ok(write_handle) => Ok((read_handle, write_handle)),
+        Err(e) => {
+            table.free_handle(read_handle)?;
+            Err(e)
+        }
+    }
 }
 
-/*pub(super) fn dump() {
-    get_open_object_table().lock().dump();
-}*/
+pub(super) fn close(handle: usize) -> Result<usize, Errno> {
+    process_manager().read().current_process().open_objects.lock().free_handle(handle)
+}
 
 
 impl OpenObjectTable {
-    /// Create a new OpenObjectTable
-    fn new() -> Box<OpenObjectTable> {
-        Box::new(OpenObjectTable {
+    /// Create a new (empty) OpenObjectTable
+    pub fn new() -> OpenObjectTable {
+        OpenObjectTable {
             open_handles: Vec::new(),
-            free_handles: Box::new([0; MAX_OPEN_OBJECTS]),
-        })
+        }
+    }
+
+    /// Create a new OpenObjectTable with the standard handles 0, 1 and 2 (stdin, stdout, stderr) referring to the terminal
+    pub fn with_standard_handles() -> OpenObjectTable {
+        let terminal: Arc<dyn FileObject> = Arc::new(Terminal);
+        let named_object = Arc::new(NamedObject::from(terminal));
+
+        let mut table = OpenObjectTable::new();
+        for options in [OpenOptions::READONLY, OpenOptions::READWRITE, OpenOptions::READWRITE] {
+            table.open_handles.push(Some(Arc::new(OpenedObject::new(named_object.clone(), AtomicUsize::new(0), options))));
+        }
+        table
+    }
+
+    /// Create a new OpenObjectTable for a child process, whose standard handles 0, 1 and 2 refer to
+    /// the objects opened as `handles` in `parent`. The opened objects (including the position) are shared.
+    pub fn with_handles_from(parent: &OpenObjectTable, handles: &[usize; 3]) -> Result<OpenObjectTable, Errno> {
+        let mut table = OpenObjectTable::new();
+        for handle in handles {
+            table.open_handles.push(Some(parent.lookup_opened_object(*handle)?));
+        }
+        Ok(table)
+    }
+
+    /// Create a copy of this table for a forked process. All handles refer to the same opened objects
+    /// (including the position), as in the table of the forking process.
+    pub fn duplicate(&self) -> OpenObjectTable {
+        OpenObjectTable {
+            open_handles: self.open_handles.clone(),
+        }
+    }
+
+    /// Close all opened objects (called, when the owning process exits)
+    pub fn close_all(&mut self) {
+        self.open_handles.clear();
     }
 
     /// Lookup an 'OpenedObject' for a given handle
-    fn lookup_opened_object(
-        &mut self,
-        opened_object_handle: usize,
-    ) -> Result<&mut Arc<OpenedObject>, Errno> {
+    fn lookup_opened_object(&self, opened_object_handle: usize) -> Result<Arc<OpenedObject>, Errno> {
         self.open_handles
-            .iter_mut()
-            .find(|(h, _)| *h == opened_object_handle)
+            .get(opened_object_handle)
+            .and_then(|opened_object| opened_object.clone())
             .ok_or(Errno::EINVALH) // Handle not found in the table
-            .and_then(|(_, named_obj)| named_obj.as_mut().ok_or(Errno::EINVALH))
     }
 
     /// Allocate a new handle for a given 'OpenObject'
     fn allocate_handle(&mut self, opened_object: Arc<OpenedObject>) -> Result<usize, Errno> {
-        let res = self.find_free_handle();
-        if res.is_none() {
-            return Err(Errno::ENOHANDLES);
+        match self.open_handles.iter().position(|entry| entry.is_none()) {
+            Some(free_handle) => {
+                self.open_handles[free_handle] = Some(opened_object);
+                Ok(free_handle)
+            }
+            None if self.open_handles.len() < MAX_OPEN_OBJECTS => {
+                self.open_handles.push(Some(opened_object));
+                Ok(self.open_handles.len() - 1)
+            }
+            None => Err(Errno::ENOHANDLES),
         }
-        let new_handle = res.unwrap();
-        self.open_handles.push((new_handle, Some(opened_object)));
-        Ok(new_handle)
     }
 
     /// Free handle
     fn free_handle(&mut self, opened_object_handle: usize) -> SyscallResult {
-        // Find the position of the file handle in the `open_handles` vector
-        if let Some(index) = self
-            .open_handles
-            .iter()
-            .position(|(h, _)| *h == opened_object_handle)
-        {
-            // Remove the handle from `open_handles`
-            self.open_handles.swap_remove(index);
-            // set handle as free
-            self.free_handles[index] = 0;
-            Ok(0)
-        } else {
-            // Handle not found
-            Err(Errno::EINVALH)
-        }
-    }
+        match self.open_handles.get_mut(opened_object_handle) {
+            Some(entry) if entry.is_some() => {
+                *entry = None;
 
-    /// Helper function of 'allocate' to find a free handle
-    fn find_free_handle(&mut self) -> Option<usize> {
-        self.free_handles
-            .iter_mut()
-            .position(|value| *value == 0)
-            .inspect(|index| {
-                self.free_handles[*index] = 1;
-            })
+                // shrink table, if handles at the end are free
+                while self.open_handles.last().is_some_and(|entry| entry.is_none()) {
+                    self.open_handles.pop();
+                }
+                Ok(0)
+            }
+            _ => Err(Errno::EINVALH), // Handle not found
+        }
     }
 
-/*
+    /*
     fn dump(&self) {
         info!("OpenObjectTable: dumping used handles");
-        for (handle, opened_object) in &self.open_handles {
+        for (handle, opened_object) in self.open_handles.iter().enumerate() {
             if let Some(opened_object) = opened_object {
                 info!(
                     "    handle = {:?}, named object = {:?}",
@@ -0,0 +1,190 @@
+/* ╔═════════════════════════════════════════════════════════════════════════╗
+   ║ Module: pipe                                                            ║
+   ╟─────────────────────────────────────────────────────────────────────────╢
+   ║ Unidirectional pipe with a bounded ring buffer. Both ends are file      ║
+   ║ objects. Readers block on an empty pipe, writers block on a full pipe.  ║
+   ║ Readers get EOF (0 bytes) once the write end has been closed, writers   ║
+   ║ get EPIPE once the read end has been closed. Each end is closed, when   ║
+   ║ the last handle referring to it is closed.                              ║
+   ╟─────────────────────────────────────────────────────────────────────────╢
+   ║ Author: agent, 18.10.2026                                               ║
+   ╚═════════════════════════════════════════════════════════════════════════╝
+*/
+use alloc::collections::VecDeque;
+use alloc::sync::{Arc, Weak};
+use alloc::vec::Vec;
+use core::fmt;
+use core::mem;
+use core::result::Result;
+use spin::Mutex;
+
+use super::stat::{MODE_FILE, Mode, Stat};
+use super::traits::FileObject;
+use crate::process::thread::Thread;
+use crate::scheduler;
+use naming::shared_types::OpenOptions;
+use syscall::return_vals::Errno;
+
+/// Max. number of bytes buffered in a pipe
+const PIPE_CAPACITY: usize = 4096;
+
+/// All pipes, which may still have waiting threads (see `remove_process`)
+static PIPES: Mutex<Vec<Weak<Pipe>>> = Mutex::new(Vec::new());
+
+struct PipeState {
+    buffer: VecDeque<u8>,
+    reader_closed: bool, // read end has been dropped (the last handle referring to it has been closed)
+    writer_closed: bool, // write end has been dropped
+    waiting_readers: Vec<Arc<Thread>>,
+    waiting_writers: Vec<Arc<Thread>>,
+}
+
+struct Pipe {
+    state: Mutex<PipeState>,
+}
+
+/// Create a new pipe and return its read and write end
+pub(super) fn create() -> (Arc<PipeReader>, Arc<PipeWriter>) {
+    let pipe = Arc::new(Pipe {
+        state: Mutex::new(PipeState {
+            buffer: VecDeque::with_capacity(PIPE_CAPACITY),
+            reader_closed: false,
+            writer_closed: false,
+            waiting_readers: Vec::new(),
+            waiting_writers: Vec::new(),
+        }),
+    });
+
+    PIPES.lock().push(Arc::downgrade(&pipe));
+    (Arc::new(PipeReader { pipe: Arc::clone(&pipe) }), Arc::new(PipeWriter { pipe }))
+}
+
+/// Remove the threads of the exited process `process_id` from the waiting readers and writers of all pipes.
+/// They have been killed while blocked and would otherwise be kept, until the other end uses the pipe again.
+pub fn remove_process(process_id: usize) {
+    PIPES.lock().retain(|pipe| {
+        let Some(pipe) = pipe.upgrade() else {
+            return false; // Both ends have been closed
+        };
+
+        let mut state = pipe.state.lock();
+        state.waiting_readers.retain(|thread| thread.process().id() != process_id);
+        state.waiting_writers.retain(|thread| thread.process().id() != process_id);
+        true
+    });
+}
+
+pub(super) struct PipeReader {
+    pipe: Arc<Pipe>,
+}
+
+impl FileObject for PipeReader {
+    fn stat(&self) -> Result<Stat, Errno> {
+        Ok(Stat::new(Mode::new(MODE_FILE), self.pipe.state.lock().buffer.len()))
+    }
+
+    /// Read available bytes (at least one), blocks while the pipe is empty. \
+    /// Returns `Ok(0)` if the pipe is empty and the write end is closed.
+    fn read(&self, buf: &mut [u8], _offset: usize, _options: OpenOptions) -> Result<usize, Errno> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+
+        loop {
+            let mut state = self.pipe.state.lock();
+            if !state.buffer.is_empty() {
+                let count = buf.len().min(state.buffer.len());
+                for (dst, src) in buf.iter_mut().zip(state.buffer.drain(..count)) {
+                    *dst = src;
+                }
+
+                let waiting_writers = mem::take(&mut state.waiting_writers);
+                drop(state);
+                scheduler().wake_up(waiting_writers);
+                return Ok(count);
+            }
+
+            if state.writer_closed {
+                return Ok(0); // EOF
+            }
+
+            state.waiting_readers.push(scheduler().current_thread());
+            scheduler().block_and_unlock(state);
+        }
+    }
+}
+
+impl Drop for PipeReader {
+    fn drop(&mut self) {
+        let mut state = self.pipe.state.lock();
+        state.reader_closed = true;
+
+        // Blocked writers must notice, that nobody will read their data
+        let waiting_writers = mem::take(&mut state.waiting_writers);
+        drop(state);
+        scheduler().wake_up(waiting_writers);
+    }
+}
+
+impl fmt::Debug for PipeReader {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PipeReader").finish()
+    }
+}
+
+pub(super) struct PipeWriter {
+    pipe: Arc<Pipe>,
+}
+
+impl FileObject for PipeWriter {
+    fn stat(&self) -> Result<Stat, Errno> {
+        Ok(Stat::new(Mode::new(MODE_FILE), self.pipe.state.lock().buffer.len()))
+    }
+
+    /// Write as many bytes as fit into the pipe (at least one), blocks while the pipe is full. \
+    /// Returns `Err(EPIPE)` if the read end is closed.
+    fn write(&self, buf: &[u8], _offset: usize, _options: OpenOptions) -> Result<usize, Errno> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+
+        loop {
+            let mut state = self.pipe.state.lock();
+            if state.reader_closed {
+                return Err(Errno::EPIPE);
+            }
+
+            let free = PIPE_CAPACITY - state.buffer.len();
+            if free > 0 {
+                let count = buf.len().min(free);
+                state.buffer.extend(&buf[..count]);
+
+                let waiting_readers = mem::take(&mut state.waiting_readers);
+                drop(state);
+                scheduler().wake_up(waiting_readers);
+                return Ok(count);
+            }
+
+            state.waiting_writers.push(scheduler().current_thread());
+            scheduler().block_and_unlock(state);
+        }
+    }
+}
+
+impl Drop for PipeWriter {
+    fn drop(&mut self) {
+        let mut state = self.pipe.state.lock();
+        state.writer_closed = true;
+
+        // Blocked readers must notice the end of the data
+        let waiting_readers = mem::take(&mut state.waiting_readers);
+        drop(state);
+        scheduler().wake_up(waiting_readers);
+    }
+}
+
+impl fmt::Debug for PipeWriter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PipeWriter").finish()
+    }
+}
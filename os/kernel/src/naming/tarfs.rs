@@ -0,0 +1,191 @@
+/* ╔═════════════════════════════════════════════════════════════════════════╗
+   ║ Module: tarfs                                                           ║
+   ╟─────────────────────────────────────────────────────────────────────────╢
+   ║ Read-only file system backed by a tar archive (used for the initrd).    ║
+   ║ The directory tree is built once from the archive entries, file data    ║
+   ║ is not copied but read directly from the archive in memory.             ║
+   ╟─────────────────────────────────────────────────────────────────────────╢
+   ║ Author: agent, 18.10.2026                                               ║
+   ╚═════════════════════════════════════════════════════════════════════════╝
+*/
+use alloc::string::{String, ToString};
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use core::fmt;
+use core::result::Result;
+use log::warn;
+use tar_no_std::TarArchiveRef;
+
+use super::stat::{MODE_DIR, MODE_FILE, Mode, Stat};
+use super::traits::{DirectoryObject, FileObject, FileSystem, NamedObject};
+use naming::shared_types::{DirEntry, FileType, OpenOptions};
+use syscall::return_vals::Errno;
+
+pub struct TarFs {
+    root_dir: Arc<Dir>,
+}
+
+impl TarFs {
+    /// Build the directory tree for all entries of `archive`. \
+    /// Intermediate directories are created, even if the archive has no entries for them.
+    /// `.` and `..` components are skipped. Entries conflicting with an earlier entry of another type
+    /// (e.g. a file, where a directory already exists) are skipped as well.
+    pub fn new(archive: &'static TarArchiveRef<'static>) -> TarFs {
+        let mut root_dir = Arc::new(Dir::new());
+
+        for entry in archive.entries() {
+            let path = match entry.filename().as_str() {
+                Ok(path) => path.to_string(),
+                Err(_) => continue, // Skip entries with invalid names
+            };
+
+            // Directory entries end with '/' (e.g. 'data/')
+            let is_dir = path.ends_with('/');
+            let components: Vec<&str> = path
+                .split('/')
+                .filter(|component| !component.is_empty() && *component != "." && *component != "..")
+                .collect();
+            if let Some((name, parents)) = components.split_last() {
+                let inserted = match Arc::get_mut(&mut root_dir).unwrap().create_path(parents) {
+                    Some(dir) if is_dir => dir.create_path(&[*name]).is_some(),
+                    Some(dir) => dir.insert_file(name, entry.data()),
+                    None => false,
+                };
+                if !inserted {
+                    warn!("TarFs: Skipping [{}], because it conflicts with an earlier entry", path);
+                }
+            }
+        }
+
+        TarFs { root_dir }
+    }
+}
+
+impl FileSystem for TarFs {
+    fn root_dir(&self) -> Arc<dyn DirectoryObject> {
+        self.root_dir.clone()
+    }
+}
+
+enum TarFsINode {
+    File(Arc<File>),
+    Directory(Arc<Dir>),
+}
+
+/// The entries are only modified while building the tree (the Arcs are not shared yet at this point).
+pub struct Dir {
+    entries: Vec<(String, TarFsINode)>,
+}
+
+impl Dir {
+    fn new() -> Dir {
+        Dir { entries: Vec::new() }
+    }
+
+    /// Walk down `components`, creating missing directories, and return the last one. \
+    /// Returns `None`, if one of the components already exists as a file.
+    fn create_path(&mut self, components: &[&str]) -> Option<&mut Dir> {
+        let (name, rest) = match components.split_first() {
+            Some(split) => split,
+            None => return Some(self),
+        };
+
+        let index = match self.entries.iter().position(|(entry_name, _)| entry_name == name) {
+            Some(index) => index,
+            None => {
+                self.entries.push((name.to_string(), TarFsINode::Directory(Arc::new(Dir::new()))));
+                self.entries.len() - 1
+            }
+        };
+
+        match &mut self.entries[index].1 {
+            TarFsINode::Directory(dir) => Arc::get_mut(dir).unwrap().create_path(rest),
+            TarFsINode::File(_) => None,
+        }
+    }
+
+    /// Insert the file `name` with the content `data`. \
+    /// Returns `false`, if a directory with the same name already exists.
+    fn insert_file(&mut self, name: &str, data: &'static [u8]) -> bool {
+        let file = TarFsINode::File(Arc::new(File { data }));
+        match self.entries.iter_mut().find(|(entry_name, _)| entry_name == name) {
+            // A later entry with the same name replaces the earlier one (like 'tar -x' does)
+            Some((_, inode @ TarFsINode::File(_))) => *inode = file,
+            Some((_, TarFsINode::Directory(_))) => return false,
+            None => self.entries.push((name.to_string(), file)),
+        }
+        true
+    }
+}
+
+impl DirectoryObject for Dir {
+    fn lookup(&self, name: &str) -> Result<NamedObject, Errno> {
+        match self.entries.iter().find(|(entry_name, _)| entry_name == name) {
+            Some((_, TarFsINode::File(file))) => Ok((file.clone() as Arc<dyn FileObject>).into()),
+            Some((_, TarFsINode::Directory(dir))) => Ok((dir.clone() as Arc<dyn DirectoryObject>).into()),
+            None => Err(Errno::ENOENT),
+        }
+    }
+
+    fn create_file(&self, _name: &str, _mode: Mode) -> Result<NamedObject, Errno> {
+        Err(Errno::EROFS)
+    }
+
+    fn create_dir(&self, _name: &str, _mode: Mode) -> Result<NamedObject, Errno> {
+        Err(Errno::EROFS)
+    }
+
+    fn stat(&self) -> Result<Stat, Errno> {
+        Ok(Stat::new(Mode::new(MODE_DIR), 0))
+    }
+
+    fn readdir(&self, index: usize) -> Result<Option<DirEntry>, Errno> {
+        Ok(self.entries.get(index).map(|(name, inode)| DirEntry {
+            file_type: match inode {
+                TarFsINode::File(_) => FileType::Regular,
+                TarFsINode::Directory(_) => FileType::Directory,
+            },
+            name: name.clone(),
+        }))
+    }
+}
+
+impl fmt::Debug for Dir {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TarFsDir").field("entries", &self.entries.len()).finish()
+    }
+}
+
+pub struct File {
+    data: &'static [u8],
+}
+
+impl FileObject for File {
+    fn stat(&self) -> Result<Stat, Errno> {
+        Ok(Stat::new(Mode::new(MODE_FILE), self.data.len()))
+    }
+
+    fn read(&self, buf: &mut [u8], offset: usize, _options: OpenOptions) -> Result<usize, Errno> {
+        if offset >= self.data.len() {
+            return Ok(0);
+        }
+
+        let len = buf.len().min(self.data.len() - offset);
+        buf[..len].copy_from_slice(&self.data[offset..offset + len]);
+        Ok(len)
+    }
+
+    fn write(&self, _buf: &[u8], _offset: usize, _options: OpenOptions) -> Result<usize, Errno> {
+        Err(Errno::EROFS)
+    }
+
+    fn truncate(&self, _size: usize) -> Result<(), Errno> {
+        Err(Errno::EROFS)
+    }
+}
+
+impl fmt::Debug for File {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TarFsFile").field("size", &self.data.len()).finish()
+    }
+}
@@ -0,0 +1,57 @@
+/* ╔═════════════════════════════════════════════════════════════════════════╗
+   ║ Module: terminal                                                        ║
+   ╟─────────────────────────────────────────────────────────────────────────╢
+   ║ File object for the terminal, used for the standard handles 0, 1 and 2  ║
+   ║ (stdin, stdout, stderr) of each process.                                ║
+   ╟─────────────────────────────────────────────────────────────────────────╢
+   ║ Author: agent, 18.10.2026                                               ║
+   ╚═════════════════════════════════════════════════════════════════════════╝
+*/
+use core::fmt;
+use core::result::Result;
+use core::str::from_utf8;
+
+use super::stat::{MODE_FILE, Mode, Stat};
+use super::traits::FileObject;
+use crate::terminal;
+use naming::shared_types::OpenOptions;
+use syscall::return_vals::Errno;
+
+pub struct Terminal;
+
+impl FileObject for Terminal {
+    fn stat(&self) -> Result<Stat, Errno> {
+        Ok(Stat::new(Mode::new(MODE_FILE), 0))
+    }
+
+    /// Wait for the next input character and return it (one byte per call). \
+    /// Returns `Err(EINTR)`, if the wait has been interrupted (by Ctrl-C or because the calling thread has been killed).
+    fn read(&self, buf: &mut [u8], _offset: usize, _options: OpenOptions) -> Result<usize, Errno> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+
+        match terminal().read_byte() {
+            -1 => Err(Errno::EINTR),
+            c => {
+                buf[0] = c as u8;
+                Ok(1)
+            }
+        }
+    }
+
+    fn write(&self, buf: &[u8], _offset: usize, _options: OpenOptions) -> Result<usize, Errno> {
+        let terminal = terminal();
+        match from_utf8(buf) {
+            Ok(string) => terminal.write_str(string),
+            Err(_) => buf.iter().for_each(|b| terminal.write_byte(*b)),
+        }
+        Ok(buf.len())
+    }
+}
+
+impl fmt::Debug for Terminal {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Terminal").finish()
+    }
+}
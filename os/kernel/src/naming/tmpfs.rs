@@ -168,7 +168,10 @@ impl File {
 
 impl FileObject for File {
     fn stat(&self) -> Result<Stat, Errno> {
-        Ok(self.stat)
+        Ok(Stat {
+            size: self.data.read().len(),
+            ..self.stat
+        })
     }
 
     fn read(&self, buf: &mut [u8], offset: usize, _options: OpenOptions) -> Result<usize, Errno> {
@@ -196,6 +199,11 @@ impl FileObject for File {
         data[offset..offset + buf.len()].clone_from_slice(buf);
         Ok(buf.len())
     }
+
+    fn truncate(&self, size: usize) -> Result<(), Errno> {
+        self.data.write().resize(size, 0);
+        Ok(())
+    }
 }
 
 impl fmt::Debug for File {
@@ -38,6 +38,11 @@ pub trait FileObject: Debug + Send + Sync {
     fn write(&self, _buf: &[u8], _offset: usize, _options: OpenOptions) -> Result<usize, Errno> {
         Err(Errno::EBADF)
     }
+
+    /// Shrink or extend (with zeros) the file to `size` bytes
+    fn truncate(&self, _size: usize) -> Result<(), Errno> {
+        Err(Errno::EBADF)
+    }
 }
 
 
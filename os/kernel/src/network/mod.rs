@@ -9,6 +9,7 @@ use smoltcp::socket::udp;
 use smoltcp::time::Instant;
 use smoltcp::wire::Ipv4Address;
 use spin::{Once, RwLock};
+use syscall::priority::MIN_PRIORITY;
 use crate::device::rtl8139::Rtl8139;
 use crate::{pci_bus, scheduler, timer};
 use crate::process::thread::Thread;
@@ -38,9 +39,12 @@ pub fn init() {
     }
 
     if RTL8139.get().is_some() {
-        scheduler().ready(Thread::new_kernel_thread(|| loop {
+        // The polling thread never blocks, so it runs with the lowest priority to keep interactive threads responsive
+        let thread = Thread::new_kernel_thread(|| loop {
             poll_sockets();
-        }, "RTL8139"));
+        }, "RTL8139");
+        thread.set_priority(MIN_PRIORITY);
+        scheduler().ready(thread);
     }
 }
 
@@ -0,0 +1,222 @@
+/* ╔═════════════════════════════════════════════════════════════════════════╗
+   ║ Module: image                                                           ║
+   ╟─────────────────────────────────────────────────────────────────────────╢
+   ║ Executable images of applications, loaded page by page on demand.       ║
+   ║                                                                         ║
+   ║ The 'PT_LOAD' segments of an ELF file are registered as code VMAs, but  ║
+   ║ not mapped, when a process is created. A page is populated from the     ║
+   ║ image, when it is accessed for the first time (see 'handle_page_fault'  ║
+   ║ and 'Process::map_on_demand'). Bytes beyond the file size of a segment  ║
+   ║ (.bss) are zero filled.                                                 ║
+   ║                                                                         ║
+   ║ All processes running the same binary share one image. Pages, which are ║
+   ║ mapped read-only, are also shared: the image keeps a reference to their ║
+   ║ frames (see 'frames::share'), so that later instances can map them      ║
+   ║ without copying. Writable pages are private copies of the image.        ║
+   ║                                                                         ║
+   ║ Public functions:                                                       ║
+   ║  - load         return the (possibly shared) image for an ELF file      ║
+   ║  - entry        entry point of the application                          ║
+   ║  - segments     loadable segments (pages and access rights)             ║
+   ║  - tls_template template for thread local storage, if any               ║
+   ║  - map_page     populate and map a page of a process, called on faults  ║
+   ╟─────────────────────────────────────────────────────────────────────────╢
+   ║ Author: agent, 18.10.2026                                               ║
+   ╚═════════════════════════════════════════════════════════════════════════╝
+*/
+use alloc::collections::BTreeMap;
+use alloc::sync::{Arc, Weak};
+use alloc::vec::Vec;
+use core::{cmp, slice};
+use goblin::elf::Elf;
+use goblin::elf64;
+use log::info;
+use spin::Mutex;
+use syscall::return_vals::Errno;
+use x86_64::VirtAddr;
+use x86_64::structures::paging::frame::PhysFrameRange;
+use x86_64::structures::paging::page::PageRange;
+use x86_64::structures::paging::{Page, PageTableFlags, PhysFrame};
+
+use crate::consts::{USER_SPACE_CODE_START, USER_SPACE_ENV_START};
+use crate::memory::frames;
+use crate::memory::vma::VirtualMemoryArea;
+use crate::memory::vmm::VirtualAddressSpace;
+use crate::memory::PAGE_SIZE;
+use crate::process::process::TlsTemplate;
+
+/// All images, which are in use by at least one process (used for sharing images between processes)
+static IMAGES: Mutex<Vec<Weak<ExecutableImage>>> = Mutex::new(Vec::new());
+
+/// Loadable segment (`PT_LOAD`) of an executable image
+pub struct Segment {
+    pub pages: PageRange,
+    pub flags: PageTableFlags, // access rights for the pages of the segment (never writable and executable)
+    file_offset: usize,
+    file_size: usize, // the rest of the segment is zero filled
+}
+
+pub struct ExecutableImage {
+    data: Vec<u8>, // content of the ELF file
+    entry: VirtAddr,
+    segments: Vec<Segment>,
+    tls_template: Option<TlsTemplate>,
+    shared_frames: Mutex<BTreeMap<Page, PhysFrame>>, // populated pages, which are mapped read-only
+}
+
+impl ExecutableImage {
+    /// Return the image for the ELF file `data`. If another process runs the same binary, its image is shared. \
+    /// Returns `Err(ENOEXEC)`, if `data` is not a valid executable (segments must be page aligned, must not be
+    /// writable and executable at the same time and must not overlap each other or lie outside the code area).
+    pub fn load(data: Vec<u8>) -> Result<Arc<ExecutableImage>, Errno> {
+        let mut images = IMAGES.lock();
+        images.retain(|image| image.strong_count() > 0);
+        if let Some(image) = images.iter().filter_map(Weak::upgrade).find(|image| image.data == data) {
+            return Ok(image);
+        }
+
+        let image = Arc::new(ExecutableImage::parse(data)?);
+        images.push(Arc::downgrade(&image));
+        Ok(image)
+    }
+
+    /// Return the entry point of the application
+    pub fn entry(&self) -> VirtAddr {
+        self.entry
+    }
+
+    /// Return the loadable segments, which must be registered as code VMAs of a process running the image
+    pub fn segments(&self) -> &[Segment] {
+        &self.segments
+    }
+
+    /// Return the template for the thread local storage, if the application uses it
+    pub fn tls_template(&self) -> Option<&TlsTemplate> {
+        self.tls_template.as_ref()
+    }
+
+    /// Populate `page` of the code area `vma` from the image and map it in `address_space` with the flags of `vma`. \
+    /// Read-only pages are shared with all processes running the image, writable pages are copied.
+    /// Returns `Err(EFAULT)`, if `vma` has been removed or changed by another thread meanwhile.
+    pub fn map_page(&self, address_space: &VirtualAddressSpace, vma: &VirtualMemoryArea, page: Page) -> Result<(), Errno> {
+        // Page faults on the image are serialized, so that a page faulted on by several threads of a process is only mapped once
+        let mut shared_frames = self.shared_frames.lock();
+        if address_space.translate(page.start_address()).is_some() {
+            return Ok(()); // Already mapped by another thread of the process
+        }
+
+        let frame = if vma.flags.contains(PageTableFlags::WRITABLE) {
+            let frame = frames::alloc(1).start;
+            self.fill_frame(frame, page);
+            frame
+        } else {
+            let frame = *shared_frames.entry(page).or_insert_with(|| {
+                let frame = frames::alloc(1).start;
+                self.fill_frame(frame, page);
+                frame
+            });
+            frames::share(frame); // The mapping is released, when the page is unmapped
+            frame
+        };
+
+        address_space.map_pfr_for_partial_vma(vma,
+            PhysFrameRange { start: frame, end: frame + 1 },
+            PageRange { start: page, end: page + 1 },
+            vma.flags,
+        ).inspect_err(|_| {
+            unsafe { frames::release(frame); } // Drop the reference of the mapping (or the private copy)
+        })
+    }
+
+    /// Helper function parsing the ELF file `data`
+    fn parse(data: Vec<u8>) -> Result<ExecutableImage, Errno> {
+        let elf = Elf::parse(&data).map_err(|_| Errno::ENOEXEC)?;
+
+        let tls_template = elf.program_headers.iter()
+            .find(|header| header.p_type == elf64::program_header::PT_TLS)
+            .map(|header| {
+                if header.p_filesz > header.p_memsz || !header.p_align.max(1).is_power_of_two() {
+                    return Err(Errno::ENOEXEC);
+                }
+                let data_start = header.p_offset as usize;
+                let tls_data = data.get(data_start..data_start.saturating_add(header.p_filesz as usize)).ok_or(Errno::ENOEXEC)?;
+                Ok(TlsTemplate {
+                    data: tls_data.to_vec(),
+                    size: header.p_memsz as usize,
+                    align: header.p_align.max(1) as usize,
+                })
+            })
+            .transpose()?;
+
+        let mut segments = Vec::new();
+        for header in elf.program_headers.iter().filter(|header| header.p_type == elf64::program_header::PT_LOAD) {
+            let start = Page::from_start_address(VirtAddr::try_new(header.p_vaddr).map_err(|_| Errno::ENOEXEC)?)
+                .map_err(|_| Errno::ENOEXEC)?;
+            if header.is_write() && header.is_executable() {
+                return Err(Errno::ENOEXEC);
+            }
+            if header.p_filesz > header.p_memsz || header.p_offset.saturating_add(header.p_filesz) > data.len() as u64 {
+                return Err(Errno::ENOEXEC);
+            }
+            if header.p_memsz == 0 {
+                continue;
+            }
+
+            // Segments must lie in the code area of the user space (below the environment and the stacks)
+            let end = header.p_vaddr.checked_add(header.p_memsz)
+                .filter(|&end| header.p_vaddr >= USER_SPACE_CODE_START as u64 && end <= USER_SPACE_ENV_START as u64)
+                .ok_or(Errno::ENOEXEC)?;
+
+            // Access rights of the segment: text is executable, rodata read-only and data writable (never W+X)
+            let mut flags = PageTableFlags::PRESENT | PageTableFlags::USER_ACCESSIBLE;
+            if header.is_write() {
+                flags |= PageTableFlags::WRITABLE;
+            }
+            if !header.is_executable() {
+                flags |= PageTableFlags::NO_EXECUTE;
+            }
+
+            segments.push(Segment {
+                pages: PageRange { start, end: Page::containing_address(VirtAddr::new(end.next_multiple_of(PAGE_SIZE as u64))) },
+                flags,
+                file_offset: header.p_offset as usize,
+                file_size: header.p_filesz as usize,
+            });
+        }
+
+        // Segments must not overlap each other
+        segments.sort_by_key(|segment| segment.pages.start);
+        if segments.windows(2).any(|pair| pair[0].pages.end > pair[1].pages.start) {
+            return Err(Errno::ENOEXEC);
+        }
+
+        let entry = VirtAddr::try_new(elf.entry).map_err(|_| Errno::ENOEXEC)?;
+        info!("Loaded executable image ({} bytes, {} segments)", data.len(), segments.len());
+        Ok(ExecutableImage { data, entry, segments, tls_template, shared_frames: Mutex::new(BTreeMap::new()) })
+    }
+
+    /// Helper function filling `frame` with the content of `page` (file content of its segment or zeros)
+    fn fill_frame(&self, frame: PhysFrame, page: Page) {
+        let buffer = unsafe { slice::from_raw_parts_mut(frame.start_address().as_u64() as *mut u8, PAGE_SIZE) };
+        buffer.fill(0);
+
+        let segment = self.segments.iter()
+            .find(|segment| segment.pages.start <= page && page < segment.pages.end)
+            .expect("Page does not belong to a segment of the executable image");
+        let offset = (page - segment.pages.start) as usize * PAGE_SIZE;
+        if offset < segment.file_size {
+            let count = cmp::min(PAGE_SIZE, segment.file_size - offset);
+            let start = segment.file_offset + offset;
+            buffer[..count].copy_from_slice(&self.data[start..start + count]);
+        }
+    }
+}
+
+impl Drop for ExecutableImage {
+    fn drop(&mut self) {
+        // Frames are freed, after the last process mapping them has been dropped
+        for frame in self.shared_frames.get_mut().values() {
+            unsafe { frames::release(*frame); }
+        }
+    }
+}
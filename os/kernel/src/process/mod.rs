@@ -1,4 +1,7 @@
 pub mod scheduler;
 pub mod thread;
 pub mod process;
-pub mod process_manager;
\ No newline at end of file
+pub mod process_manager;
+pub mod signal;
+pub mod policy;
+pub mod image;
\ No newline at end of file
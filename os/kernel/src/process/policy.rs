@@ -0,0 +1,168 @@
+/* ╔═════════════════════════════════════════════════════════════════════════╗
+   ║ Module: policy                                                          ║
+   ╟─────────────────────────────────────────────────────────────────────────╢
+   ║ Scheduling policies, managing the ready threads of the scheduler.       ║
+   ║                                                                         ║
+   ║   - RoundRobin               all threads in one queue, priorities are   ║
+   ║                              ignored                                    ║
+   ║   - MultilevelFeedbackQueue  one queue per priority, threads using up   ║
+   ║                              their time slice are moved down, threads   ║
+   ║                              becoming ready are moved up to their       ║
+   ║                              priority again                             ║
+   ╟─────────────────────────────────────────────────────────────────────────╢
+   ║ Author: agent, 18.10.2026                                               ║
+   ╚═════════════════════════════════════════════════════════════════════════╝
+*/
+use alloc::boxed::Box;
+use alloc::collections::VecDeque;
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use syscall::priority::NUM_PRIORITIES;
+use crate::process::thread::Thread;
+use crate::timer;
+
+/// Interval (in ms) after which all threads of the multilevel feedback queue are moved up to their priority,
+/// so that threads moved down by a busy thread of higher priority do not starve
+const BOOST_INTERVAL_MS: usize = 1000;
+
+/// Policy deciding which ready thread is executed next (see `Scheduler`)
+pub trait SchedulingPolicy {
+    /// Insert a thread, which has become ready (new or woken up)
+    fn insert(&mut self, thread: Arc<Thread>);
+
+    /// Insert the thread, which has been running until now. \
+    /// `preempted` is true, if the thread has used up its time slice (and has not given up the cpu voluntarily).
+    fn requeue(&mut self, thread: Arc<Thread>, preempted: bool);
+
+    /// Remove and return the thread to be executed next
+    fn next(&mut self) -> Option<Arc<Thread>>;
+
+    /// Iterate over all ready threads
+    fn iter(&self) -> Box<dyn Iterator<Item = &Arc<Thread>> + '_>;
+
+    /// Keep only the ready threads, for which `keep` returns true
+    fn retain(&mut self, keep: &mut dyn FnMut(&Arc<Thread>) -> bool);
+
+    /// Remove and return a thread, for which `stealable` returns true, to be moved to another core
+    /// (preferably a thread, which would be executed late)
+    fn steal(&mut self, stealable: &dyn Fn(&Arc<Thread>) -> bool) -> Option<Arc<Thread>>;
+}
+
+/// All ready threads in a single queue (priorities are ignored)
+pub struct RoundRobin {
+    queue: VecDeque<Arc<Thread>>,
+}
+
+impl RoundRobin {
+    pub fn new() -> Self {
+        Self { queue: VecDeque::new() }
+    }
+}
+
+impl SchedulingPolicy for RoundRobin {
+    fn insert(&mut self, thread: Arc<Thread>) {
+        self.queue.push_front(thread);
+    }
+
+    fn requeue(&mut self, thread: Arc<Thread>, _preempted: bool) {
+        self.queue.push_front(thread);
+    }
+
+    fn next(&mut self) -> Option<Arc<Thread>> {
+        self.queue.pop_back()
+    }
+
+    fn iter(&self) -> Box<dyn Iterator<Item = &Arc<Thread>> + '_> {
+        Box::new(self.queue.iter())
+    }
+
+    fn retain(&mut self, keep: &mut dyn FnMut(&Arc<Thread>) -> bool) {
+        self.queue.retain(|thread| keep(thread));
+    }
+
+    fn steal(&mut self, stealable: &dyn Fn(&Arc<Thread>) -> bool) -> Option<Arc<Thread>> {
+        let index = self.queue.iter().position(stealable)?;
+        self.queue.remove(index)
+    }
+}
+
+/// One queue per priority, the highest non-empty queue is served first (round robin within a queue). \
+/// A thread is inserted with its effective priority, which starts at the priority of the thread.
+/// It is decremented each time the thread uses up its time slice, so busy threads sink below
+/// interactive threads, which block before their time slice ends and start again at their priority,
+/// when they are woken up.
+pub struct MultilevelFeedbackQueue {
+    queues: [VecDeque<Arc<Thread>>; NUM_PRIORITIES],
+    last_boost: usize, // system time (ms) of the last boost
+}
+
+impl MultilevelFeedbackQueue {
+    pub fn new() -> Self {
+        Self {
+            queues: core::array::from_fn(|_| VecDeque::new()),
+            last_boost: 0,
+        }
+    }
+
+    /// Insert `thread` into the queue of its effective priority
+    fn push(&mut self, thread: Arc<Thread>) {
+        let level = thread.effective_priority();
+        self.queues[level].push_front(thread);
+    }
+
+    /// Move all ready threads up to their priority again
+    fn boost(&mut self) {
+        let threads = self.queues.iter_mut()
+            .flat_map(|queue| queue.drain(..))
+            .collect::<Vec<Arc<Thread>>>();
+
+        for thread in threads {
+            thread.set_effective_priority(thread.priority());
+            self.push(thread);
+        }
+    }
+}
+
+impl SchedulingPolicy for MultilevelFeedbackQueue {
+    fn insert(&mut self, thread: Arc<Thread>) {
+        thread.set_effective_priority(thread.priority());
+        self.push(thread);
+    }
+
+    fn requeue(&mut self, thread: Arc<Thread>, preempted: bool) {
+        if preempted {
+            thread.set_effective_priority(thread.effective_priority().saturating_sub(1));
+        }
+        self.push(thread);
+    }
+
+    fn next(&mut self) -> Option<Arc<Thread>> {
+        let time = timer().systime_ms();
+        if time >= self.last_boost + BOOST_INTERVAL_MS {
+            self.boost();
+            self.last_boost = time;
+        }
+
+        self.queues.iter_mut()
+            .rev()
+            .find_map(|queue| queue.pop_back())
+    }
+
+    fn iter(&self) -> Box<dyn Iterator<Item = &Arc<Thread>> + '_> {
+        Box::new(self.queues.iter().flat_map(|queue| queue.iter()))
+    }
+
+    fn retain(&mut self, keep: &mut dyn FnMut(&Arc<Thread>) -> bool) {
+        for queue in self.queues.iter_mut() {
+            queue.retain(|thread| keep(thread));
+        }
+    }
+
+    fn steal(&mut self, stealable: &dyn Fn(&Arc<Thread>) -> bool) -> Option<Arc<Thread>> {
+        // Threads of the lowest priority are executed last
+        self.queues.iter_mut().find_map(|queue| {
+            let index = queue.iter().position(stealable)?;
+            queue.remove(index)
+        })
+    }
+}
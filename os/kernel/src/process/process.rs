@@ -10,15 +10,23 @@ use alloc::sync::Arc;
 use alloc::vec::Vec;
 use log::trace;
 use x86_64::structures::paging::page::PageRange;
-use x86_64::structures::paging::{Page, PageTableFlags};
+use x86_64::structures::paging::Page;
 use x86_64::VirtAddr;
-use core::sync::atomic::AtomicUsize;
+use core::mem;
+use core::sync::atomic::{AtomicBool, AtomicUsize};
 use core::sync::atomic::Ordering::Relaxed;
+use syscall::return_vals::Errno;
+use syscall::signal::{Signal, NUM_SIGNALS};
 use crate::memory::MemorySpace;
 use crate::{ process_manager, scheduler};
 use crate::memory::pages::Paging;
 use crate::memory::vmm::VirtualAddressSpace;
-use crate::memory::vma::VirtualMemoryArea;
+use crate::memory::vma::{VirtualMemoryArea, VmaType};
+use crate::naming::open_objects::OpenObjectTable;
+use crate::process::image::ExecutableImage;
+use crate::process::signal::SignalHandler;
+use crate::process::thread::Thread;
+use spin::{Mutex, Once};
 
 static PROCESS_ID_COUNTER: AtomicUsize = AtomicUsize::new(1);
 
@@ -27,15 +35,48 @@ fn next_process_id() -> usize {
 }
 
 
+/// Initialization image of the thread local storage (ELF segment `PT_TLS`), copied into the TLS block of each thread
+#[derive(Clone)]
+pub struct TlsTemplate {
+    pub data: Vec<u8>, // initialized part (.tdata), the rest of the block is zeroed (.tbss)
+    pub size: usize,   // size of the TLS block
+    pub align: usize,
+}
+
+/// Exit status of a process and threads waiting for it (see `Process::wait`)
+struct ExitState {
+    status: Option<isize>,
+    waiting_threads: Vec<Arc<Thread>>,
+}
+
 pub struct Process {
     pub id: usize,
+    parent_id: usize, // 0 for the kernel process
     pub virtual_address_space: VirtualAddressSpace,
+    pub open_objects: Mutex<OpenObjectTable>, // handles for the naming service
+    exit_state: Mutex<ExitState>,
+    exited: AtomicBool, // set together with the exit status, but can be checked without locking
+    waiting_for_children: AtomicUsize, // number of threads blocked in `wait_for_child`
+    signal_handlers: Mutex<[Option<SignalHandler>; NUM_SIGNALS]>, // `None` -> default action
+    tls_template: Once<TlsTemplate>, // not set, if the application does not use thread local storage
+    image: Once<Arc<ExecutableImage>>, // backing of the code areas, not set for the kernel process
 }
 
 
 impl Process {
-    pub fn new(page_tables: Arc<Paging>) -> Self {
-        Self { id: next_process_id(), virtual_address_space: VirtualAddressSpace::new(page_tables) }
+    pub fn new(page_tables: Arc<Paging>, parent_id: usize) -> Self {
+        Self {
+            id: next_process_id(),
+            parent_id,
+            virtual_address_space: VirtualAddressSpace::new(page_tables),
+            open_objects: Mutex::new(OpenObjectTable::with_standard_handles()),
+            exit_state: Mutex::new(ExitState { status: None, waiting_threads: Vec::new() }),
+            exited: AtomicBool::new(false),
+            waiting_for_children: AtomicUsize::new(0),
+            signal_handlers: Mutex::new([None; NUM_SIGNALS]),
+            tls_template: Once::new(),
+            image: Once::new(),
+        }
     }
 
     /// Return the id of the process
@@ -43,8 +84,113 @@ impl Process {
         self.id
     }
 
-    pub fn exit(&self) {
-        process_manager().write().exit(self.id);
+    /// Return the id of the process, which has created this process
+    pub fn parent_id(&self) -> usize {
+        self.parent_id
+    }
+
+    /// Exit the process with `status`, which is reported to the parent (see `wait`)
+    pub fn exit(&self, status: isize) {
+        process_manager().write().exit(self.id, status);
+    }
+
+    /// Record the exit `status` and wake up all threads waiting for it. Called once by the process manager.
+    pub fn set_exit_status(&self, status: isize) {
+        let mut exit_state = self.exit_state.lock();
+        exit_state.status = Some(status);
+        self.exited.store(true, Relaxed);
+        let waiting_threads = mem::take(&mut exit_state.waiting_threads);
+        drop(exit_state);
+
+        scheduler().wake_up(waiting_threads);
+    }
+
+    /// Block the calling thread until the process has exited and return its exit status
+    pub fn wait(&self) -> isize {
+        loop {
+            let mut exit_state = self.exit_state.lock();
+            if let Some(status) = exit_state.status {
+                return status;
+            }
+
+            exit_state.waiting_threads.push(scheduler().current_thread());
+            scheduler().block_and_unlock(exit_state);
+        }
+    }
+
+    /// Remove the threads of the exited process `process_id` from the threads waiting for this process.
+    /// They have been killed while blocked and would otherwise be kept, until this process exits.
+    pub fn remove_waiting_threads(&self, process_id: usize) {
+        self.exit_state.lock().waiting_threads.retain(|thread| thread.process().id() != process_id);
+    }
+
+    /// Check if the process has exited (or has been killed)
+    pub fn is_exited(&self) -> bool {
+        self.exited.load(Relaxed)
+    }
+
+    /// Block the calling thread (of this process) until `child` has exited and return its exit status. \
+    /// While waiting, the children of this process are in the foreground (see `ProcessManager::foreground_process_ids`).
+    pub fn wait_for_child(&self, child: &Process) -> isize {
+        self.waiting_for_children.fetch_add(1, Relaxed);
+        let status = child.wait();
+        self.waiting_for_children.fetch_sub(1, Relaxed);
+        status
+    }
+
+    /// Check if a thread of the process is waiting for a child
+    pub fn is_waiting_for_children(&self) -> bool {
+        self.waiting_for_children.load(Relaxed) > 0
+    }
+
+    /// Return the handler for `signal` (`None` -> default action)
+    pub fn signal_handler(&self, signal: Signal) -> Option<SignalHandler> {
+        self.signal_handlers.lock()[usize::from(signal)]
+    }
+
+    /// Set the handler for `signal` (`None` -> default action)
+    pub fn set_signal_handler(&self, signal: Signal, handler: Option<SignalHandler>) {
+        self.signal_handlers.lock()[usize::from(signal)] = handler;
+    }
+
+    /// Take over the signal handlers of `parent` (used for forked processes, which share the code of the parent)
+    pub fn inherit_signal_handlers(&self, parent: &Process) {
+        let handlers = *parent.signal_handlers.lock();
+        *self.signal_handlers.lock() = handlers;
+    }
+
+    /// Return the template for the thread local storage of new threads, if the application uses it
+    pub fn tls_template(&self) -> Option<&TlsTemplate> {
+        self.tls_template.get()
+    }
+
+    /// Set the template for the thread local storage (called once, when the application is loaded)
+    pub fn set_tls_template(&self, template: TlsTemplate) {
+        self.tls_template.call_once(|| template);
+    }
+
+    /// Take over the template for the thread local storage of `parent` (used for forked processes)
+    pub fn inherit_tls_template(&self, parent: &Process) {
+        if let Some(template) = parent.tls_template() {
+            self.set_tls_template(template.clone());
+        }
+    }
+
+    /// Return the executable image of the application, from which its code areas are loaded on demand
+    pub fn image(&self) -> Option<&Arc<ExecutableImage>> {
+        self.image.get()
+    }
+
+    /// Set the executable image (called once, when the application is loaded)
+    pub fn set_image(&self, image: Arc<ExecutableImage>) {
+        self.image.call_once(|| image);
+    }
+
+    /// Take over the executable image of `parent` (used for forked processes)
+    pub fn inherit_image(&self, parent: &Process) {
+        if let Some(image) = parent.image() {
+            self.set_image(Arc::clone(image));
+        }
     }
 
     /// Return the ids of all threads of the process
@@ -55,27 +201,39 @@ impl Process {
             }).copied().collect()
     }
 
+    /// Close all handles of the process
+    pub fn close_all_objects(&self) {
+        self.open_objects.lock().close_all();
+    }
+
     pub fn kill_all_threads_but_current(&self) {
         self.thread_ids().iter()
             .filter(|&&thread_id| thread_id != scheduler().current_thread().id())
             .for_each(|&thread_id| scheduler().kill(thread_id));
     }
 
-    /// Grow the heap.
+    /// Map a page of a user stack, the heap, an anonymous mapping (see `Mmap`) or the code with the flags of its VMA.
+    /// Code pages are populated from the executable image, all others are zero filled.
     /// 
     /// This is called from the page fault handler if we have a page fault in
-    /// memory that is part of the heap VMA, but not yet mapped.
-    pub fn grow_heap(&self, heap: &VirtualMemoryArea, fault_addr: VirtAddr) {
+    /// memory that is part of such a VMA, but not yet mapped.
+    /// Returns `Err(EFAULT)`, if the VMA has been removed or changed by another thread meanwhile.
+    pub fn map_on_demand(&self, vma: &VirtualMemoryArea, fault_addr: VirtAddr) -> Result<(), Errno> {
         let page = Page::containing_address(fault_addr);
-        trace!("lazily mapping heap page {page:?} at 0x{fault_addr:x}");
-        self.virtual_address_space.map_partial_vma(heap,
+        trace!("lazily mapping {:?} page {page:?} at 0x{fault_addr:x}", vma.typ());
+        if vma.typ == VmaType::Code {
+            let image = self.image().expect("Process has code areas, but no executable image");
+            return image.map_page(&self.virtual_address_space, vma, page);
+        }
+
+        self.virtual_address_space.map_partial_vma(vma,
             PageRange {
                 start: page,
                 end: page + 1,
             },
             MemorySpace::User,
-            PageTableFlags::PRESENT | PageTableFlags::WRITABLE | PageTableFlags::USER_ACCESSIBLE,
-        );
+            vma.flags,
+        )
     }
 
 
@@ -11,12 +11,22 @@ use alloc::vec::Vec;
 use log::info;
 
 use crate::memory::vmm;
+use crate::naming::pipe;
 use crate::process::process::Process;
 use crate::scheduler;
+use crate::sync::futex;
+
+/// Exit status of a terminated process, kept until its parent has waited for it
+struct ExitStatus {
+    process_id: usize,
+    parent_id: usize,
+    status: isize,
+}
 
 pub struct ProcessManager {
     active_processes: Vec<Arc<Process>>,
     exited_processes: Vec<Arc<Process>>, // processed by cleanup thread later
+    exit_statuses: Vec<ExitStatus>,      // of terminated processes, whose parent is still active
 }
 
 impl ProcessManager {
@@ -24,6 +34,7 @@ impl ProcessManager {
         Self {
             active_processes: Vec::new(),
             exited_processes: Vec::new(),
+            exit_statuses: Vec::new(),
         }
     }
 
@@ -37,7 +48,10 @@ impl ProcessManager {
             None => vmm::create_kernel_address_space(),
         };
 
-        let process = Arc::new(Process::new(paging));
+        // The first process is the kernel process, which has no parent
+        let parent_id = if self.active_processes.is_empty() { 0 } else { self.current_process().id() };
+
+        let process = Arc::new(Process::new(paging, parent_id));
         self.active_processes.push(Arc::clone(&process));
 
         info!("Process [{}]: created", process.id());
@@ -64,7 +78,54 @@ impl ProcessManager {
         }
     }
 
-    pub fn exit(&mut self, process_id: usize) {
+    /// Return the active process `process_id`
+    pub fn active_process(&self, process_id: usize) -> Option<Arc<Process>> {
+        self.active_processes
+            .iter()
+            .find(|process| process.id == process_id)
+            .map(Arc::clone)
+    }
+
+    /// Return the ids of all foreground processes. These are the children of processes waiting for a child
+    /// (e.g. all commands of a pipeline started by the shell), which receive SIGINT if Ctrl-C is pressed.
+    pub fn foreground_process_ids(&self) -> Vec<usize> {
+        self.active_processes
+            .iter()
+            .filter(|process| {
+                self.active_processes.iter().any(|parent| parent.id() == process.parent_id() && parent.is_waiting_for_children())
+            })
+            .map(|process| process.id())
+            .collect()
+    }
+
+    /// Check if the active process `process_id` is `ancestor_id` or (indirectly) one of its children
+    pub fn is_descendant(&self, mut process_id: usize, ancestor_id: usize) -> bool {
+        while process_id != ancestor_id {
+            match self.active_process(process_id) {
+                Some(process) if process.parent_id() != process_id => process_id = process.parent_id(),
+                _ => return false,
+            }
+        }
+        true
+    }
+
+    /// Return the active process `process_id`, if it is a child of `parent_id`
+    pub fn active_child(&self, parent_id: usize, process_id: usize) -> Option<Arc<Process>> {
+        self.active_processes
+            .iter()
+            .find(|process| process.id == process_id && process.parent_id() == parent_id)
+            .map(Arc::clone)
+    }
+
+    /// Remove and return the exit status of the terminated process `process_id`, if it is a child of `parent_id`
+    pub fn take_exit_status(&mut self, parent_id: usize, process_id: usize) -> Option<isize> {
+        let index = self.exit_statuses
+            .iter()
+            .position(|exit_status| exit_status.process_id == process_id && exit_status.parent_id == parent_id)?;
+        Some(self.exit_statuses.swap_remove(index).status)
+    }
+
+    pub fn exit(&mut self, process_id: usize, status: isize) {
         let index = self
             .active_processes
             .iter()
@@ -73,12 +134,15 @@ impl ProcessManager {
 
         let process = Arc::clone(&self.active_processes[index]);
         process.kill_all_threads_but_current();
+        process.close_all_objects();
 
         self.active_processes.swap_remove(index);
+        self.record_exit_status(&process, status);
         self.exited_processes.push(process);
     }
 
-    pub fn kill(&mut self, process_id: usize) {
+    /// Kill the process `process_id` (must not be the calling process), reporting `status` as exit status to the parent
+    pub fn kill(&mut self, process_id: usize, status: isize) {
         let index = self
             .active_processes
             .iter()
@@ -91,10 +155,29 @@ impl ProcessManager {
         }
 
         self.active_processes.swap_remove(index);
+        self.record_exit_status(&process, status);
         self.exited_processes.push(process);
     }
 
+    /// Helper function, storing the exit status of `process` (already removed from the active processes) for its parent
+    fn record_exit_status(&mut self, process: &Process, status: isize) {
+        // Nobody can wait for the children of an exited process anymore
+        self.exit_statuses.retain(|exit_status| exit_status.parent_id != process.id());
+
+        if self.active_processes.iter().any(|active| active.id() == process.parent_id()) {
+            self.exit_statuses.push(ExitStatus { process_id: process.id(), parent_id: process.parent_id(), status });
+        }
+        process.set_exit_status(status);
+    }
+
     pub fn drop_exited_process(&mut self) {
+        // Killed processes did not close their handles yet and their threads may still be in wait lists
+        self.exited_processes.iter().for_each(|process| {
+            process.close_all_objects();
+            futex::remove_process(process.id());
+            pipe::remove_process(process.id());
+            self.active_processes.iter().for_each(|active| active.remove_waiting_threads(process.id()));
+        });
         self.exited_processes.clear();
     }
 
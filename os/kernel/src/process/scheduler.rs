@@ -1,21 +1,32 @@
 /* ╔═════════════════════════════════════════════════════════════════════════╗
    ║ Module: scheduler                                                       ║
    ╟─────────────────────────────────────────────────────────────────────────╢
-   ║ Descr.: Implementation of the scheduler.                                ║
+   ║ Descr.: Implementation of the scheduler. The order of the ready threads ║
+   ║         is decided by a scheduling policy (see `policy`). Each core has ║
+   ║         its own ready queue and sleep list. Threads stay on their core, ║
+   ║         but the idle thread of a core (see `smp`) steals ready user     ║
+   ║         threads from other cores.                                       ║
    ╟─────────────────────────────────────────────────────────────────────────╢
    ║ Author: Fabian Ruhland, HHU                                             ║
    ╚═════════════════════════════════════════════════════════════════════════╝
 */
+use crate::consts::MAX_CORES;
+use crate::interrupt::interrupt_dispatcher::InterruptVector;
+use crate::process::policy::{MultilevelFeedbackQueue, SchedulingPolicy};
 use crate::process::thread::Thread;
+use crate::smp::{core_id, online_cores};
 use crate::{allocator, apic, scheduler, timer, tss};
-use alloc::collections::VecDeque;
+use alloc::boxed::Box;
 use alloc::sync::Arc;
 use alloc::vec::Vec;
+use core::hint::spin_loop;
+use core::mem;
 use core::ptr;
 use core::sync::atomic::AtomicUsize;
 use core::sync::atomic::Ordering::Relaxed;
 use smallmap::Map;
 use spin::{Mutex, MutexGuard};
+use x86_64::instructions::interrupts;
 
 
 // thread IDs
@@ -25,27 +36,46 @@ pub fn next_thread_id() -> usize {
     THREAD_ID_COUNTER.fetch_add(1, Relaxed)
 }
 
-/// Everything related to the ready state in the scheduler
+/// Everything related to the ready state of a core in the scheduler
 struct ReadyState {
     initialized: bool,
     current_thread: Option<Arc<Thread>>,
-    ready_queue: VecDeque<Arc<Thread>>,
+    ready_queue: Box<dyn SchedulingPolicy>,
+    sleep_list: Vec<(Arc<Thread>, usize)>,
 }
 
 impl ReadyState {
-    pub fn new() -> Self {
+    pub fn new(policy: Box<dyn SchedulingPolicy>) -> Self {
         Self {
             initialized: false,
             current_thread: None,
-            ready_queue: VecDeque::new(),
+            ready_queue: policy,
+            sleep_list: Vec::new(),
         }
     }
+
+    /// Insert all threads, whose wakeup time has been reached, into the ready queue
+    fn check_sleep_list(&mut self) {
+        let time = timer().systime_ms();
+        let ready_queue = &mut self.ready_queue;
+
+        self.sleep_list.retain(|entry| {
+            if time >= entry.1 {
+                ready_queue.insert(Arc::clone(&entry.0));
+                false
+            } else {
+                true
+            }
+        });
+    }
 }
 
-/// Main struct of the scheduler
+/// Main struct of the scheduler. \
+/// A thread never holds the ready states of two cores at the same time. The ready state of another core
+/// is only locked with interrupts disabled (see `with_ready_state`), while the ready state of the own core
+/// may be held with interrupts enabled, since it prevents thread switching on the core.
 pub struct Scheduler {
-    ready_state: Mutex<ReadyState>,
-    sleep_list: Mutex<Vec<(Arc<Thread>, usize)>>,
+    ready_states: Vec<Mutex<ReadyState>>, // one per core (index = core id)
     join_map: Mutex<Map<usize, Vec<Arc<Thread>>>>, // manage which threads are waiting for a thread-id to terminate
 }
 
@@ -55,16 +85,20 @@ unsafe impl Sync for Scheduler {}
 /// Called from assembly code, after the thread has been switched
 #[unsafe(no_mangle)]
 pub unsafe extern "C" fn unlock_scheduler() {
-    unsafe { scheduler().ready_state.force_unlock(); }
+    unsafe { scheduler().ready_states[core_id()].force_unlock(); }
 }
 
 impl Scheduler {
 
-    /// Description: Create and init the scheduler.
+    /// Description: Create and init the scheduler (using a multilevel feedback queue as scheduling policy).
     pub fn new() -> Self {
+        Self::with_policy(|| Box::new(MultilevelFeedbackQueue::new()))
+    }
+
+    /// Description: Create and init the scheduler with the scheduling policy created by `policy` (called once per core).
+    pub fn with_policy(policy: fn() -> Box<dyn SchedulingPolicy>) -> Self {
         Self {
-            ready_state: Mutex::new(ReadyState::new()),
-            sleep_list: Mutex::new(Vec::new()),
+            ready_states: (0..MAX_CORES).map(|_| Mutex::new(ReadyState::new(policy()))).collect(),
             join_map: Mutex::new(Map::new()),
         }
     }
@@ -74,16 +108,22 @@ impl Scheduler {
         self.get_ready_state().initialized = true;
     }
 
+    /// Description: Return the ids of all threads except the calling one (running on other cores, ready or sleeping)
     pub fn active_thread_ids(&self) -> Vec<usize> {
-        let state = self.get_ready_state();
-        let sleep_list = self.sleep_list.lock();
-
-        state.ready_queue.iter()
-            .map(|thread| thread.id())
-            .collect::<Vec<usize>>()
-            .into_iter()
-            .chain(sleep_list.iter().map(|entry| entry.0.id()))
-            .collect()
+        let current_id = self.current_thread().id();
+        let mut ids = Vec::new();
+
+        for core in online_cores() {
+            self.with_ready_state(core, |state| {
+                ids.extend(state.current_thread.iter()
+                    .chain(state.ready_queue.iter())
+                    .chain(state.sleep_list.iter().map(|entry| &entry.0))
+                    .map(|thread| thread.id())
+                    .filter(|&id| id != current_id));
+            });
+        }
+
+        ids
     }
 
     /// Description: Return reference to current thread
@@ -92,53 +132,71 @@ impl Scheduler {
         Scheduler::current(&state)
     }
 
-    /// Description: Return reference to thread for the given `thread_id`
+    /// Description: Return reference to current thread, if the ready state of the calling core is not locked
+    ///              (used by exception handlers, which must not wait for the interrupted code)
+    pub fn try_current_thread(&self) -> Option<Arc<Thread>> {
+        let state = self.ready_states[core_id()].try_lock()?;
+        state.current_thread.as_ref().map(Arc::clone)
+    }
+
+    /// Description: Return reference to thread for the given `thread_id` (running, ready or sleeping)
     pub fn thread(&self, thread_id: usize) -> Option<Arc<Thread>> {
-        self.ready_state.lock().ready_queue
-            .iter()
-            .find(|thread| thread.id() == thread_id)
-            .cloned()
+        online_cores().find_map(|core| {
+            self.with_ready_state(core, |state| {
+                state.current_thread.iter()
+                    .chain(state.ready_queue.iter())
+                    .chain(state.sleep_list.iter().map(|entry| &entry.0))
+                    .find(|thread| thread.id() == thread_id)
+                    .cloned()
+            })
+        })
     }
 
-    /// Description: Start the scheduler, called only once from `boot.rs` 
+    /// Description: Check if there are ready threads on the calling core (besides the current thread)
+    pub fn has_ready_threads(&self) -> bool {
+        self.get_ready_state().ready_queue.iter().next().is_some()
+    }
+
+    /// Description: Start the scheduler on the calling core, called once per core from `boot.rs`
     pub fn start(&self) {
-        // TODO: make sure this is actually called just once
-        let mut state = self.get_ready_state();
-        state.current_thread = state.ready_queue.pop_back();
+        // Wait until a thread has been inserted into the ready queue of this core (the idle thread, see `smp`)
+        let state = loop {
+            let mut state = self.get_ready_state();
+            if let Some(thread) = state.ready_queue.next() {
+                state.current_thread = Some(thread);
+                break state;
+            }
+
+            drop(state);
+            spin_loop();
+        };
 
         unsafe { Thread::start_first(state.current_thread.as_ref().expect("Failed to dequeue first thread!").as_ref()); }
     }
 
     /// 
-    /// Description: Insert a thread into the ready_queue
+    /// Description: Insert a new thread into the ready queue of the core with the fewest ready threads
     /// 
     /// Parameters: `thread` thread to be inserted.
     /// 
     pub fn ready(&self, thread: Arc<Thread>) {
-        let id = thread.id();
-        let mut join_map;
-        let mut state;
-
-        // If we get the lock on 'self.state' but not on 'self.join_map' the system hangs.
-        // The scheduler is not able to switch threads anymore, because of 'self.state' is locked,
-        // and we will never be able to get the lock on 'self.join_map'.
-        // To solve this, we need to release the lock on 'self.state' in case we do not get
-        // the lock on 'self.join_map' and let the scheduler switch threads until we get both locks.
-        loop {
-            let state_mutex = self.get_ready_state();
-            let join_map_option = self.join_map.try_lock();
+        let core = online_cores()
+            .min_by_key(|&core| self.with_ready_state(core, |state| state.ready_queue.iter().count()))
+            .unwrap_or(0);
 
-            if join_map_option.is_some() {
-                state = state_mutex;
-                join_map = join_map_option.unwrap();
-                break;
-            } else {
-                self.switch_thread_no_interrupt();
-            }
-        }
+        self.ready_on_core(thread, core);
+    }
 
-        state.ready_queue.push_front(thread);
-        join_map.insert(id, Vec::new());
+    /// 
+    /// Description: Insert a new thread into the ready queue of the given core
+    /// 
+    /// Parameters: `thread` thread to be inserted \
+    ///             `core` core executing the thread (kernel threads are never moved to another core)
+    /// 
+    pub fn ready_on_core(&self, thread: Arc<Thread>, core: usize) {
+        // No ready state is locked here, so waiting for 'join_map' cannot block the scheduler
+        self.join_map.lock().insert(thread.id(), Vec::new());
+        self.insert(thread, core);
     }
 
     /// Description: Put calling thread to sleep for `ms` milliseconds
@@ -154,14 +212,9 @@ impl Scheduler {
             // Scheduler is initialized, so we can block the calling thread
             let thread = Scheduler::current(&state);
             let wakeup_time = timer().systime_ms() + ms;
-            
-            {
-                // Execute in own block, so that the lock is released automatically (block() does not return)
-                let mut sleep_list = self.sleep_list.lock();
-                sleep_list.push((thread, wakeup_time));
-            }
+            state.sleep_list.push((thread, wakeup_time));
 
-            self.block(&mut state);
+            self.block(state);
         }
     }
 
@@ -172,14 +225,13 @@ impl Scheduler {
     ///                         false = no EOI needed
     /// 
     fn switch_thread(&self, interrupt: bool) {
-        if let Some(mut state) = self.ready_state.try_lock() {
+        // Interrupts are disabled, so that the calling thread cannot be moved to another core, before the ready state is locked
+        if let Some(mut state) = interrupts::without_interrupts(|| self.ready_states[core_id()].try_lock()) {
             if !state.initialized {
                 return;
             }
 
-            if let Some(mut sleep_list) = self.sleep_list.try_lock() {
-                Scheduler::check_sleep_list(&mut state, &mut sleep_list);
-            }
+            state.check_sleep_list();
 
             // Get clone of the current thread
             let current = Scheduler::current(&state);
@@ -189,17 +241,23 @@ impl Scheduler {
                 return;
             }
 
-            // Try to get the next thread from the ready queue
-            let next = match state.ready_queue.pop_back() {
-                Some(thread) => thread,
-                None => return,
+            // Put the current thread back and get the next thread from the ready queue
+            // (Preempted by the timer interrupt -> the current thread has used up its time slice)
+            state.ready_queue.requeue(Arc::clone(&current), interrupt);
+            let Some(next) = state.ready_queue.next() else {
+                return; // Only possible before the idle thread of this core has been created
             };
 
+            // The current thread still has the highest priority
+            if next.id() == current.id() {
+                return;
+            }
+
             let current_ptr = ptr::from_ref(current.as_ref());
             let next_ptr = ptr::from_ref(next.as_ref());
 
             state.current_thread = Some(next);
-            state.ready_queue.push_front(current);
+            drop(current); // Decrease Rc manually, because Thread::switch does not return
 
             if interrupt {
                 apic().end_of_interrupt();
@@ -208,6 +266,10 @@ impl Scheduler {
             unsafe {
                 Thread::switch(current_ptr, next_ptr);
             }
+
+            // The ready state has already been unlocked after switching back to this thread (see `unlock_scheduler`),
+            // and this thread may be running on another core now
+            mem::forget(state);
         }
     }
 
@@ -221,104 +283,173 @@ impl Scheduler {
         self.switch_thread(true);
     }
 
+    ///
+    /// Description: Move a ready user thread from another core to the calling core (kernel threads always stay on their core).
+    ///              Called by the idle thread of a core (see `smp`).
+    ///
+    /// Return: `true`, if a thread has been moved
+    ///
+    pub fn steal(&self) -> bool {
+        let core = core_id();
+
+        // Take a thread from the core with the most ready threads
+        let victim = online_cores()
+            .filter(|&other| other != core)
+            .max_by_key(|&other| self.with_ready_state(other, |state| state.ready_queue.iter().count()));
+        let Some(victim) = victim else {
+            return false;
+        };
+
+        let thread = self.with_ready_state(victim, |state| state.ready_queue.steal(&|thread| !thread.is_kernel_thread()));
+        match thread {
+            Some(thread) => {
+                self.insert(thread, core);
+                true
+            }
+            None => false,
+        }
+    }
+
     /// 
     /// Description: Calling thread wants to wait for another thread to terminate
     /// 
     /// Parameters: `thread_id` thread to wait for
     /// 
     pub fn join(&self, thread_id: usize) {
-        let mut state = self.get_ready_state();
+        let (state, mut join_map) = self.get_ready_state_and_join_map();
         let thread = Scheduler::current(&state);
 
-        {
-            // Execute in own block, so that the lock is released automatically (block() does not return)
-            let mut join_map = self.join_map.lock();
-            if let Some(join_list) = join_map.get_mut(&thread_id) {
-                join_list.push(thread);
-            } else {
-                // Joining on a non-existent thread has no effect (i.e. the thread has already finished running)
-                return;
-            }
+        if let Some(join_list) = join_map.get_mut(&thread_id) {
+            join_list.push(thread);
+        } else {
+            // Joining on a non-existent thread has no effect (i.e. the thread has already finished running)
+            return;
         }
 
-        self.block(&mut state);
+        drop(join_map);
+        self.block(state);
     }
 
-    /// Description: Exit calling thread.
-    pub fn exit(&self) -> ! {
-        let mut ready_state;
-        let current;
-
-        {
-            // Execute in own block, so that join_map is released automatically (block() does not return)
-            let state = self.get_ready_state_and_join_map();
-            ready_state = state.0;
-            let mut join_map = state.1;
-
-            current = Scheduler::current(&ready_state);
-            let join_list = join_map.get_mut(&current.id()).expect("Missing join_map entry!");
+    ///
+    /// Description: Block calling thread, after it has been inserted into a wait list protected by `guard`.
+    ///              The ready state is locked before `guard` is released, so a waking thread cannot
+    ///              insert the calling thread into the ready queue, before it has been switched away.
+    ///
+    /// Parameters: `guard` lock protecting the wait list (released by this function)
+    ///
+    pub fn block_and_unlock<T>(&self, guard: MutexGuard<T>) {
+        let state = self.get_ready_state();
+        drop(guard);
+        self.block(state);
+    }
 
-            for thread in join_list {
-                ready_state.ready_queue.push_front(Arc::clone(thread));
+    ///
+    /// Description: Insert threads taken from a wait list into the ready queue of their cores.
+    ///              Threads of exited processes have been killed while waiting and are dropped.
+    ///
+    /// Parameters: `threads` threads to be woken up
+    ///
+    /// Return: number of threads inserted into a ready queue
+    ///
+    pub fn wake_up(&self, threads: Vec<Arc<Thread>>) -> usize {
+        let mut count = 0;
+        for thread in threads {
+            if !thread.is_killed() && !thread.process().is_exited() {
+                let core = thread.core();
+                self.insert(thread, core);
+                count += 1;
             }
+        }
+
+        count
+    }
 
-            join_map.remove(&current.id());
+    /// Description: Exit calling thread.
+    pub fn exit(&self) -> ! {
+        // Wake up all threads waiting for the calling thread
+        // (The entry may be missing, if the calling thread has been killed by another core meanwhile)
+        let current_id = self.current_thread().id();
+        let join_list = self.join_map.lock().remove(&current_id);
+        if let Some(join_list) = join_list {
+            self.wake_up(join_list);
         }
 
-        drop(current); // Decrease Rc manually, because block() does not return
-        self.block(&mut ready_state);
+        let state = self.get_ready_state();
+        self.block(state);
         unreachable!()
     }
 
+    /// Description: Exit calling thread, if it has been killed by another thread. Called at safe points
+    ///              (before returning to user mode, see `syscall_dispatcher::return_to_user`),
+    ///              where the thread does not hold any kernel locks.
+    pub fn exit_if_killed(&self) {
+        let killed = self.current_thread().is_killed();
+        if killed {
+            self.exit();
+        }
+    }
+
     /// 
-    /// Description: Kill the thread with the  given id
+    /// Description: Kill the thread with the given id. The thread is only marked as killed and exits at its next safe point
+    ///              (see `exit_if_killed`), since it may hold kernel locks. A sleeping thread is woken up and a thread
+    ///              running on another core is interrupted, so that it reaches its safe point as soon as possible.
+    ///              Threads blocked in a wait list are dropped, when they are woken up (see `wake_up`) or removed from
+    ///              futexes, pipes and exit states, when their process is cleaned up (see `drop_exited_process`).
     /// 
     /// Parameters: `thread_id` thread to be killed
     /// 
     pub fn kill(&self, thread_id: usize) {
-        {
-            // Check if current thread tries to kill itself (illegal)
-            let ready_state = self.get_ready_state();
-            let current = Scheduler::current(&ready_state);
-
-            if current.id() == thread_id {
-                panic!("A thread cannot kill itself!");
-            }
+        if self.current_thread().id() == thread_id {
+            panic!("A thread cannot kill itself!");
         }
 
-        let state = self.get_ready_state_and_join_map();
-        let mut ready_state = state.0;
-        let mut join_map = state.1;
-
-        let join_list = join_map.get_mut(&thread_id).expect("Missing join map entry!");
+        // Wake up all threads waiting for the killed thread
+        // (The entry may be missing, if the thread has exited on another core meanwhile)
+        let join_list = self.join_map.lock().remove(&thread_id);
+        if let Some(join_list) = join_list {
+            self.wake_up(join_list);
+        }
 
-        for thread in join_list {
-            ready_state.ready_queue.push_front(Arc::clone(thread));
+        let mut running = false;
+        for core in online_cores() {
+            running |= self.with_ready_state(core, |state| {
+                state.ready_queue.iter()
+                    .filter(|thread| thread.id() == thread_id)
+                    .for_each(|thread| thread.kill());
+
+                if let Some(index) = state.sleep_list.iter().position(|entry| entry.0.id() == thread_id) {
+                    let (thread, _) = state.sleep_list.swap_remove(index);
+                    thread.kill();
+                    state.ready_queue.insert(thread);
+                }
+
+                // The thread may be running on another core (not on the calling one, since a thread cannot kill itself)
+                state.current_thread.as_ref()
+                    .filter(|thread| thread.id() == thread_id)
+                    .inspect(|thread| thread.kill())
+                    .is_some()
+            });
         }
 
-        join_map.remove(&thread_id);
-        ready_state.ready_queue.retain(|thread| thread.id() != thread_id);
+        // The IPI lets a thread running in user mode reach its safe point (see `interrupt_dispatcher::handle_interrupt`)
+        if running {
+            apic().send_ipi_to_others(InterruptVector::ThreadKilled);
+        }
     }
 
     /// 
-    /// Description: Block calling thread
+    /// Description: Block calling thread (switch to the next ready thread of the core)
     /// 
-    /// Parameters: `state` ReadyState of scheduler 
-    /// MS -> why this param?
+    /// Parameters: `state` locked ReadyState of the calling core (unlocked by the next thread)
     /// 
-    fn block(&self, state: &mut ReadyState) {
-        let mut next_thread = state.ready_queue.pop_back();
-
-        {
-            // Execute in own block, so that the lock is released automatically (block() does not return)
-            let mut sleep_list = self.sleep_list.lock();
-            while next_thread.is_none() {
-                Scheduler::check_sleep_list(state, &mut sleep_list);
-                next_thread = state.ready_queue.pop_back();
-            }
+    fn block(&self, mut state: MutexGuard<ReadyState>) {
+        let mut next_thread = state.ready_queue.next();
+        while next_thread.is_none() {
+            state.check_sleep_list();
+            next_thread = state.ready_queue.next();
         }
 
-        let current = Scheduler::current(state);
+        let current = Scheduler::current(&state);
         let next = next_thread.unwrap();
 
         // Thread has enqueued itself into sleep list and waited so long, that it dequeued itself in the meantime
@@ -335,6 +466,9 @@ impl Scheduler {
         unsafe {
             Thread::switch(current_ptr, next_ptr);
         }
+
+        // The ready state has already been unlocked after switching back to this thread (see `unlock_scheduler`)
+        mem::forget(state);
     }
 
     /// Description: Return current running thread
@@ -342,46 +476,60 @@ impl Scheduler {
         Arc::clone(state.current_thread.as_ref().expect("Trying to access current thread before initialization!"))
     }
 
-    fn check_sleep_list(state: &mut ReadyState, sleep_list: &mut Vec<(Arc<Thread>, usize)>) {
-        let time = timer().systime_ms();
-
-        sleep_list.retain(|entry| {
-            if time >= entry.1 {
-                state.ready_queue.push_front(Arc::clone(&entry.0));
-                false
-            } else {
-                true
-            }
-        });
+    /// Description: Insert `thread` into the ready queue of `core`
+    fn insert(&self, thread: Arc<Thread>, core: usize) {
+        thread.set_core(core);
+        self.with_ready_state(core, |state| state.ready_queue.insert(thread));
     }
 
-    /// Description: Helper function returning `ReadyState` of scheduler in a MutexGuard
+    /// Description: Helper function returning `ReadyState` of the calling core in a MutexGuard
     fn get_ready_state(&self) -> MutexGuard<ReadyState> {
-        let state;
-
         // We need to make sure, that both the kernel memory manager and the ready queue are currently not locked.
         // Otherwise, a deadlock may occur: Since we are holding the ready queue lock,
-        // the scheduler won't switch threads anymore, and none of the locks will ever be released
+        // the scheduler won't switch threads anymore, and none of the locks will ever be released.
+        // Interrupts are disabled while locking, so that the calling thread cannot be moved to another core meanwhile
+        // (but enabled between two attempts, so that a thread holding the kernel memory manager can continue).
         loop {
-            let state_tmp = self.ready_state.lock();
-            if allocator().is_locked() {
-                continue;
-            }
+            let state = interrupts::without_interrupts(|| {
+                let state = self.ready_states[core_id()].lock();
+                (!allocator().is_locked()).then_some(state)
+            });
 
-            state = state_tmp;
-            break;
+            if let Some(state) = state {
+                return state;
+            }
         }
+    }
+
+    /// Description: Helper function calling `f` with the locked `ReadyState` of `core` (with interrupts disabled)
+    fn with_ready_state<R>(&self, core: usize, f: impl FnOnce(&mut ReadyState) -> R) -> R {
+        let mut f = Some(f);
 
-        state
+        // See `get_ready_state`
+        loop {
+            let result = interrupts::without_interrupts(|| {
+                let mut state = self.ready_states[core].lock();
+                if allocator().is_locked() {
+                    return None;
+                }
+
+                f.take().map(|f| f(&mut state))
+            });
+
+            if let Some(result) = result {
+                return result;
+            }
+        }
     }
 
-    /// Description: Helper function returning `ReadyState` and `Map` of scheduler, each in a MutexGuard
+    /// Description: Helper function returning `ReadyState` of the calling core and `Map` of scheduler, each in a MutexGuard
     fn get_ready_state_and_join_map(&self) -> (MutexGuard<ReadyState>, MutexGuard<Map<usize, Vec<Arc<Thread>>>>) {
         loop {
             let ready_state = self.get_ready_state();
             if let Some(join_map) = self.join_map.try_lock() {
                 return (ready_state, join_map);
             } else {
+                drop(ready_state);
                 self.switch_thread_no_interrupt();
             }
         }
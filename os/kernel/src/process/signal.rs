@@ -0,0 +1,104 @@
+/* ╔═════════════════════════════════════════════════════════════════════════╗
+   ║ Module: signal                                                          ║
+   ╟─────────────────────────────────────────────────────────────────────────╢
+   ║ Delivery of signals to processes. If a process has registered a handler ║
+   ║ for a signal, the handler is executed in a new user thread of the       ║
+   ║ process (running concurrently to the other threads). Otherwise, the     ║
+   ║ default action is taken, which is terminating the process.              ║
+   ║                                                                         ║
+   ║ Ctrl-C is recorded by the keyboard interrupt handler and SIGINT is sent ║
+   ║ to the foreground processes by the next thread returning to user mode   ║
+   ║ (or by an idle thread), since signals cannot be sent in interrupt       ║
+   ║ context (requires locks and memory allocation).                         ║
+   ║                                                                         ║
+   ║ Public functions:                                                       ║
+   ║  - send               send a signal to a process                        ║
+   ║  - raise_interrupt    record Ctrl-C, called by the keyboard interrupt   ║
+   ║  - interrupt_pending  check if Ctrl-C has not been handled yet          ║
+   ║  - deliver_interrupt  send SIGINT to all foreground processes, if       ║
+   ║                       Ctrl-C has been pressed                           ║
+   ╟─────────────────────────────────────────────────────────────────────────╢
+   ║ Author: agent, 18.10.2026                                               ║
+   ╚═════════════════════════════════════════════════════════════════════════╝
+*/
+use alloc::vec::Vec;
+use core::sync::atomic::AtomicBool;
+use core::sync::atomic::Ordering::Relaxed;
+use log::info;
+use syscall::return_vals::Errno;
+use syscall::signal::Signal;
+use x86_64::VirtAddr;
+use crate::consts::MAX_USER_STACK_SIZE;
+use crate::process::thread::Thread;
+use crate::{process_manager, scheduler};
+
+/// Set, if Ctrl-C has been pressed (see `raise_interrupt`)
+static INTERRUPT_PENDING: AtomicBool = AtomicBool::new(false);
+
+/// User function handling a signal, started like a thread (`kickoff(entry)`, see `library::concurrent::signal`)
+#[derive(Clone, Copy)]
+pub struct SignalHandler {
+    pub kickoff: VirtAddr,
+    pub entry: fn(),
+}
+
+/// Send `signal` to the process `process_id`. \
+/// Does not return, if the calling process terminates itself.
+pub fn send(process_id: usize, signal: Signal) -> Result<(), Errno> {
+    let (process, current, kernel_process) = {
+        let process_manager = process_manager().read();
+        let process = process_manager.active_process(process_id).ok_or(Errno::ESRCH)?;
+        (process, process_manager.current_process(), process_manager.kernel_process())
+    };
+    if kernel_process.is_some_and(|kernel_process| kernel_process.id() == process_id) {
+        return Err(Errno::EACCES);
+    }
+
+    info!("Process [{}]: received signal {:?}", process_id, signal);
+
+    if let Some(handler) = process.signal_handler(signal) {
+        scheduler().ready(Thread::new_user_thread(process, handler.kickoff, handler.entry, 0, MAX_USER_STACK_SIZE));
+        return Ok(());
+    }
+
+    // Default action: terminate the process
+    if process.id() == current.id() {
+        drop(current);
+        process.exit(signal.exit_status());
+        drop(process); // Manually decrease reference count, because exit() will never return
+        scheduler().exit();
+    }
+    process_manager().write().kill(process_id, signal.exit_status());
+    Ok(())
+}
+
+/// Record that Ctrl-C has been pressed (called by the keyboard interrupt handler)
+pub fn raise_interrupt() {
+    INTERRUPT_PENDING.store(true, Relaxed);
+}
+
+/// Check if Ctrl-C has been pressed, but SIGINT has not been sent yet
+pub fn interrupt_pending() -> bool {
+    INTERRUPT_PENDING.load(Relaxed)
+}
+
+/// Send SIGINT to all foreground processes, if Ctrl-C has been pressed. \
+/// Called before returning to user mode (see `syscall_dispatcher::return_to_user`) and by the idle threads.
+/// Does not return, if the calling process is a foreground process without a handler for SIGINT.
+pub fn deliver_interrupt() {
+    if !INTERRUPT_PENDING.swap(false, Relaxed) {
+        return;
+    }
+
+    let (foreground_process_ids, current_id) = {
+        let process_manager = process_manager().read();
+        (process_manager.foreground_process_ids(), process_manager.current_process().id())
+    };
+
+    // The calling process is signalled last, since `send` does not return, if it terminates itself
+    let (current, others): (Vec<usize>, Vec<usize>) = foreground_process_ids.into_iter().partition(|&id| id == current_id);
+    for process_id in others.into_iter().chain(current) {
+        // The process may have exited in the meantime
+        let _ = send(process_id, Signal::SIGINT);
+    }
+}
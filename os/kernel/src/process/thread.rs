@@ -8,60 +8,84 @@
    ║  - new_kernel_thread  create a new kernel-only thread                   ║
    ║  - load_application   load application, create process, and main thread ║
    ║  - new_user_thread    create and additional user thread in a process    ║
+   ║  - new_forked_thread  copy the calling thread into a forked process     ║
    ║  - start_first        start a thread, called once by scheduler          ║
    ║  - switch             switch threads, called by scheduler               ║
    ║  - stacks_locked      check if stacks are locked, called by scheduler   ║
    ║  - grow_user_stack    grow stack as needed, called from page fault      ║
    ║  - user_stack_start   return last usable address of user stack          ║
+   ║  - kernel_stack_overflowed  check if the kernel stack has overflowed    ║
    ║  - is_kernel_thread   check if self is a kernel only thread or not      ║
    ║  - process            return reference to my process                    ║
    ║  - id                 return my thread id                               ║
    ║  - join               calling thread will wait until 'self' terminates  ║
+   ║  - priority           get/set priority of the thread (also effective    ║
+   ║    set_priority       priority used by the scheduling policy)           ║
+   ║  - core               get/set core, on which the thread is scheduled    ║
+   ║    set_core                                                             ║
+   ║  - kill               mark thread as killed, called by scheduler        ║
+   ║  - is_killed          check if thread has been killed                   ║
    ║                                                                         ║
    ║ Thread stack:                                                           ║
    ║  Kernel threads have a stack of 'KERNEL_STACK_PAGES'. User threads have ║
-   ║  an additional stack with a logical size of 'MAX_USER_STACK_SIZE' and   ║
-   ║  an initial phyiscal size of one page. Additional pages are allocated   ║
-   ║  for user stacks as need until 'MAX_USER_STACK_SIZE' is reached.        ║
-   ║  A thread is killed if this limit is exceeded. The stack of a user      ║
-   ║  thread stack within one processes is logically allocated at            ║
-   ║  'MAIN_USER_STACK_START'. The next stack for the next user stack is     ║
-   ║  allocated at 'MAIN_USER_STACK_START' + 'MAX_USER_STACK_SIZE' and so on.║
+   ║  an additional stack with a logical size given, when the thread is      ║
+   ║  created (at most 'MAX_USER_STACK_SIZE'), and an initial phyiscal size  ║
+   ║  of one page. Additional pages are allocated for user stacks as needed  ║
+   ║  until the logical size is reached. The stack of the main thread of a   ║
+   ║  process is allocated at 'MAIN_USER_STACK_START', each further stack    ║
+   ║  directly above the highest stack of the process.                       ║
+   ║  Below each stack lie 'STACK_GUARD_PAGES', which are never mapped. A    ║
+   ║  user thread overflowing its stack is killed (page fault), a kernel     ║
+   ║  stack overflow is reported by the double fault handler.                ║
+   ║                                                                         ║
+   ║ Thread local storage:                                                   ║
+   ║  If the application has a 'PT_TLS' segment, the TLS block of a user     ║
+   ║  thread is placed at the top of its user stack (x86_64 variant II: the  ║
+   ║  data lies below the thread pointer, which points to itself). FS base   ║
+   ║  is set to the thread pointer on each thread switch.                    ║
    ╟─────────────────────────────────────────────────────────────────────────╢
    ║ Author: Fabian Ruhland & Michael Schoettner, 25.5.2025, HHU             ║
    ╚═════════════════════════════════════════════════════════════════════════╝
 */
 
 use crate::consts::MAIN_USER_STACK_START;
-use crate::consts::MAX_USER_STACK_SIZE;
-use crate::consts::USER_SPACE_ENV_START;
+use crate::consts::{MAX_USER_STACK_SIZE, STACK_GUARD_PAGES};
 use crate::memory::stack;
 use crate::memory::stack::StackAllocator;
-use crate::memory::vma::VmaType;
+use crate::memory::vma::{VirtualMemoryArea, VmaType};
 use crate::memory::{MemorySpace, PAGE_SIZE};
+use crate::memory::vmm::VirtualAddressSpace;
+use crate::process::image::ExecutableImage;
 use crate::process::process::Process;
 use crate::process::scheduler;
-use crate::syscall::syscall_dispatcher::CORE_LOCAL_STORAGE_TSS_RSP0_PTR_INDEX;
+use crate::syscall::syscall_dispatcher::{SyscallFrame, CORE_LOCAL_STORAGE_TSS_RSP0_PTR_INDEX};
 use crate::{process_manager, scheduler, tss};
 use alloc::sync::Arc;
+use alloc::vec;
 use alloc::vec::Vec;
 use core::arch::naked_asm;
+use core::cmp::min;
+use core::sync::atomic::{AtomicBool, AtomicUsize};
+use core::sync::atomic::Ordering::Relaxed;
+use core::mem::{offset_of, size_of};
 use core::ptr;
-use goblin::elf::Elf;
-use goblin::elf64;
 use log::info;
 use spin::Mutex;
+use syscall::priority::{DEFAULT_PRIORITY, MAX_PRIORITY};
 use x86_64::PrivilegeLevel::Ring3;
 use x86_64::VirtAddr;
+use x86_64::instructions::interrupts;
+use x86_64::registers::model_specific::FsBase;
 use x86_64::structures::gdt::SegmentSelector;
 use x86_64::structures::paging::page::PageRange;
-use x86_64::structures::paging::{Page, PageTableFlags, Size4KiB};
+use x86_64::structures::paging::{Page, Size4KiB};
 
 /// kernel & user stack of a thread
 struct Stacks {
     kernel_stack: Vec<u64, StackAllocator>,
     user_stack: Vec<u64, StackAllocator>,
     old_rsp0: VirtAddr, // used for thread switching; rsp3 is stored in TSS
+    user_rsp: VirtAddr, // initial stack pointer, when switching to user mode the first time
 }
 
 /// A thread is the unit of execution.
@@ -73,6 +97,7 @@ struct Stacks {
 /// * [`Thread::new_kernel_thread`]: for kernel threads
 /// * [`Thread::load_application`]: for the main thread of an application
 /// * [`Thread::new_user_thread`]: for additional threads of an application
+/// * [`Thread::new_forked_thread`]: for the thread of a forked process
 /// 
 /// This will allocate all required ressources, but will not actually start the
 /// thread. You need to call [`scheduler::Scheduler::ready`] to enqueue it.
@@ -81,10 +106,13 @@ struct Stacks {
 /// [`Thread::kickoff_kernel_thread`]. This sets up the TSS and then:
 /// * for a kernel thread: call the `entry` function,
 ///   and [`scheduler::Scheduler::exit`] afterwards.
-/// * for a user thread: call `user_kickoff(entry)`,
-///   with `user_kickoff` being `library::concurrent::thread::kickoff_user_thread`.
+/// * for a user thread: call `user_kickoff(entry, user_arg)`,
+///   with `user_kickoff` being `library::concurrent::thread::kickoff_user_thread`
+///   (or `kickoff_user_thread_with_arg` for threads created by `spawn`).
 ///   This is needed so that the actual `entry` function of the application
 ///   can safely return.
+/// * for a forked thread: return to user mode like the system call of the
+///   forking thread, with the registers saved in `user_context`.
 pub struct Thread {
     id: usize,
     stacks: Mutex<Stacks>,
@@ -93,8 +121,28 @@ pub struct Thread {
     user_kickoff: VirtAddr,
     /// the actual entry point (eg. for user threads the single parameter to kickoff)
     entry: fn(),
+    /// for user threads: the second parameter to kickoff (eg. the boxed closure of `spawn`)
+    user_arg: usize,
+    /// for forked threads: registers of the forking thread (at its `Fork` system call)
+    user_context: Option<SyscallFrame>,
+    /// priority set for the thread (see `syscall::priority`)
+    priority: AtomicUsize,
+    /// priority currently used by the scheduling policy (may be lower than `priority`, see `policy`)
+    effective_priority: AtomicUsize,
+    /// core, in whose ready queue the thread is inserted (may change, if another core steals the thread)
+    core: AtomicUsize,
+    /// set, if the thread has been killed (it exits at its next safe point, see `Scheduler::kill`)
+    killed: AtomicBool,
+    /// thread pointer of the TLS block (loaded into FS base), zero if the thread has no thread local storage
+    fs_base: VirtAddr,
 }
 
+/// Size of the thread control block following the TLS block (only the self pointer of the thread pointer)
+const TCB_SIZE: u64 = 8;
+
+/// Model specific register holding the FS base (written by `thread_switch`)
+const FS_BASE_MSR: u32 = 0xc000_0100;
+
 impl Stacks {
     const fn new(
         kernel_stack: Vec<u64, StackAllocator>,
@@ -104,6 +152,7 @@ impl Stacks {
             kernel_stack,
             user_stack,
             old_rsp0: VirtAddr::zero(),
+            user_rsp: VirtAddr::zero(),
         }
     }
 }
@@ -113,9 +162,11 @@ impl Thread {
     /// Create a kernel thread. Not started yet, nor registered in the scheduler. \
     /// `entry` is the thread entry function.
     pub fn new_kernel_thread(entry: fn(), tag_str: &str) -> Arc<Thread> {
+        // Kernel threads run in the address space of the kernel process, which therefore contains their stacks
         let process = process_manager()
             .read()
-            .current_process();
+            .kernel_process()
+            .expect("Trying to create a kernel thread before process initialization!");
         let pid = process.id();
         let tid = scheduler::next_thread_id();
         
@@ -125,16 +176,7 @@ impl Thread {
         let kernel_stack = stack::alloc_kernel_stack(pid, tid);
 
         // Allocate virtual memory area for kernel stack
-        let _vma = process
-            .virtual_address_space
-            .alloc_vma(
-                Some( kernel_stack.allocator().get_start_page()),
-                kernel_stack.allocator().get_num_pages(),
-                MemorySpace::Kernel,
-                VmaType::KernelStack,
-                tag_str,
-            )
-            .expect("alloc_vma failed for kernel stack");
+        Thread::alloc_kernel_stack_vmas(&process, kernel_stack.allocator(), tag_str);
 
         // Create empty user stack, so need to add it to the virtual address space
         let user_stack: Vec<u64, StackAllocator> = stack::alloc_user_stack(pid, tid, MAIN_USER_STACK_START, 0);
@@ -143,12 +185,16 @@ impl Thread {
         let thread = Thread {
             id: tid,
             stacks: Mutex::new(Stacks::new(kernel_stack, user_stack)),
-            process: process_manager()
-                .read()
-                .kernel_process()
-                .expect("Trying to create a kernel thread before process initialization!"),
+            process,
             user_kickoff: VirtAddr::zero(),
             entry,
+            user_arg: 0,
+            user_context: None,
+            priority: AtomicUsize::new(DEFAULT_PRIORITY),
+            effective_priority: AtomicUsize::new(DEFAULT_PRIORITY),
+            core: AtomicUsize::new(0),
+            killed: AtomicBool::new(false),
+            fs_base: VirtAddr::zero(),
         };
 
         thread.prepare_kernel_stack();
@@ -156,10 +202,12 @@ impl Thread {
     }
 
 
-    /// Load application code from `elf_buffer`, create a process with a main thread. \
-    /// `name` is the name of the application, `args` are the arguments passed to the application. \
+    /// Create a process with a main thread for the executable `image` (see `ExecutableImage::load`). \
+    /// The segments of the image are registered as code areas, which are populated on demand (see `Process::map_on_demand`). \
+    /// `name` is the name of the application, `argv` (including the program name) and `envp` (`KEY=VALUE` entries)
+    /// are placed on the user stack of the main thread (see `prepare_initial_user_stack`). \
     /// Returns the main thread of the application which is not yet registered in the scheduler.
-    pub fn load_application(elf_buffer: &[u8], name: &str, args: &Vec<&str>) -> Arc<Thread> {
+    pub fn load_application(image: Arc<ExecutableImage>, name: &str, argv: &[&[u8]], envp: &[&[u8]]) -> Arc<Thread> {
         let process = process_manager().write().create_process();
         let pid = process.id();
         let tid = scheduler::next_thread_id();
@@ -168,161 +216,48 @@ impl Thread {
             "load_application: pid = {pid}, tid = {tid}, name = {name}",
         );
 
-        //
-        // Parse elf file headers and map code if successful
-        //
-        let elf = Elf::parse(elf_buffer).expect("Failed to parse application");
-        elf.program_headers
-            .iter()
-            .filter(|header| header.p_type == elf64::program_header::PT_LOAD)
-            .for_each(|header| {
-                let page_count = if header.p_memsz as usize % PAGE_SIZE == 0 {
-                    header.p_memsz as usize / PAGE_SIZE
-                } else {
-                    (header.p_memsz as usize / PAGE_SIZE) + 1
-                };
-
-                let virt_start = Page::from_start_address(VirtAddr::new(header.p_vaddr))
-                    .expect("ELF: Program section not page aligned");
-                /*let pages = PageRange {
-                    start: virt_start,
-                    end: virt_start + page_count as u64,
-                };*/
-
-                // Allocate virtual memory area for the code and add it to the process
-                let vma = process.virtual_address_space.alloc_vma(
-                    Some(virt_start),
-                    page_count as u64,
-                    MemorySpace::User,
-                    VmaType::Code,
-                    name,
-                );
-                if vma.is_none() {
-                    panic!("alloc_vma failed for code section");
-                }
-                let vma = vma.unwrap();
-
-                // Allocate frames for the code section
-                let frames = process.virtual_address_space.alloc_pf_for_vma(&vma);
-                if frames.is_none() {
-                    panic!("alloc_pf_for_vma failed for code section");
-                }
-                let frames = frames.unwrap();
-
-                // Map code section to the process
-                let res = process.virtual_address_space.map_pfr_for_vma(
-                    &vma,
-                    frames,
-                    PageTableFlags::PRESENT
-                        | PageTableFlags::WRITABLE
-                        | PageTableFlags::USER_ACCESSIBLE,
-                );
-                if res.is_err() {
-                    panic!("map_pfr_for_vma failed");
-                }
-
-                // copy code from the ELF file to the allocated frames
-                unsafe {
-                    let code = elf_buffer.as_ptr().offset(header.p_offset as isize);
-                    let target = frames.start.start_address().as_u64() as *mut u8;
-                    target.copy_from(code, header.p_filesz as usize);
-                    target
-                        .offset(header.p_filesz as isize)
-                        .write_bytes(0, (header.p_memsz - header.p_filesz) as usize);
-                }
-            });
-
-        //
-        // Create and init environment for the application
-        //
-
-        // create environment for the application
-        let args_size = args.iter().map(|arg| arg.len()).sum::<usize>();
-        let env_virt_start =
-            Page::from_start_address(VirtAddr::new(USER_SPACE_ENV_START as u64)).unwrap();
-        let env_size = args_size;
-        let env_page_count = if env_size > 0 && env_size % PAGE_SIZE == 0 {
-            env_size / PAGE_SIZE
-        } else {
-            (env_size / PAGE_SIZE) + 1
-        };
+        if let Some(template) = image.tls_template() {
+            process.set_tls_template(template.clone());
+        }
 
-        // Allocate virtual memory area for environment of the application
-        let env_vma = process
-            .virtual_address_space
-            .alloc_vma(
-                Some( env_virt_start ),
-                env_page_count as u64,
+        // Allocate virtual memory areas for the segments (no frames are allocated and mapped yet)
+        for segment in image.segments() {
+            process.virtual_address_space.alloc_vma_with_flags(
+                Some(segment.pages.start),
+                segment.pages.len(),
                 MemorySpace::User,
-                VmaType::Environment,
-                "",
-            )
-            .expect("alloc_vma failed for kernel stack of main thread");
-
-        // Allocate frames for the environment of the application
-        let env_frames = process.virtual_address_space.alloc_pf_for_vma(&env_vma)
-            .expect("alloc_pf_for_vma failed for environment");
-
-        // Map environment of the application
-        let res = process.virtual_address_space.map_pfr_for_vma(
-            &env_vma,
-            env_frames,
-            PageTableFlags::PRESENT | PageTableFlags::WRITABLE | PageTableFlags::USER_ACCESSIBLE,
-        );
-        if res.is_err() {
-            panic!("map_pfr_for_vma failed for environment");
+                VmaType::Code,
+                name,
+                segment.flags,
+            ).expect("alloc_vma failed for code section");
         }
 
-        // create argc and argv in the user space environment
-        let env_addr = VirtAddr::new(env_frames.start.start_address().as_u64()); // Start address of user space environment
-        let argc = env_addr.as_mut_ptr::<usize>(); // First entry in environment is argc (number of arguments)
-        let argv = (env_addr + size_of::<usize>() as u64).as_mut_ptr::<*const u8>(); // Second entry in environment is argv (array of pointers to arguments)
-
-        // copy arguments directly behind argv array and store pointers to them in argv
-        unsafe {
-            argc.write(args.len() + 1);
-
-            let args_begin = argv.add(args.len() + 1) as *mut u8; // Physical start address of arguments (we use this address to copy them)
-            let args_begin_virt = env_virt_start.start_address()
-                + size_of::<usize>() as u64
-                + ((args.len() + 1) * size_of::<usize>()) as u64; // Virtual start address of arguments (they will be visible here in user space)
-
-            // copy program name as first argument
-            args_begin.copy_from(name.as_bytes().as_ptr(), name.len());
-            args_begin.add(name.len()).write(0); // null-terminate the string for C compatibility
-            argv.write(args_begin_virt.as_ptr());
-
-            let mut offset = name.len() + 1;
-
-            // copy remaining arguments
-            for (i, arg) in args.iter().enumerate() {
-                let target = args_begin.add(offset);
-                target.copy_from(arg.as_bytes().as_ptr(), arg.len());
-                target.add(arg.len()).write(0); // null-terminate the string for C compatibility
-
-                argv.add(i + 1)
-                    .write((args_begin_virt + offset as u64).as_ptr());
-                offset += arg.len() + 1;
-            }
-        }
+        let entry = image.entry();
+        process.set_image(image);
 
         // create thread
         // this first thread is special in that there is not really a kickoff;
-        // we just jump to the ELF's entry point
+        // we just jump to the ELF's entry point, which finds argc, argv and envp on the stack
         // TODO: this leaks a kernel address to user space
-        Self::new_user_thread(process, VirtAddr::new(elf.entry), || {})
+        let thread = Self::new_user_thread(process, entry, || {}, 0, MAX_USER_STACK_SIZE);
+        thread.prepare_initial_user_stack(argv, envp);
+        thread
     }
 
 
     /// Create user thread. Not started yet, nor registered in the scheduler. \
     /// `parent` is the process the thread belongs to. \
     /// `kickoff_addr` address of the first function to be called,
-    /// with the `entry` function is the parameter. \
-    /// This indirection ensures that the thread calls exit when it is done, see `library::concurrent::thread`.
+    /// with the `entry` function as first and `arg` as second parameter. \
+    /// This indirection ensures that the thread calls exit when it is done, see `library::concurrent::thread`. \
+    /// `stack_size` is the max. size of the user stack in bytes (page aligned, at most `MAX_USER_STACK_SIZE`),
+    /// which must also hold the TLS block of the thread.
     pub fn new_user_thread(
         parent: Arc<Process>,
         kickoff_addr: VirtAddr,
         entry: fn(),
+        arg: usize,
+        stack_size: usize,
     ) -> Arc<Thread> {
         let pid = parent.id();
         let tid = scheduler::next_thread_id(); // get id for new thread
@@ -335,16 +270,7 @@ impl Thread {
         let kernel_stack = stack::alloc_kernel_stack(pid, tid);
 
         // Allocate virtual memory area for kernel stack
-        let _kernel_stack_vma = parent
-            .virtual_address_space
-            .alloc_vma(
-                Some( kernel_stack.allocator().get_start_page()),
-                kernel_stack.allocator().get_num_pages(),
-                MemorySpace::Kernel,
-                VmaType::KernelStack,
-                "user",
-            )
-            .expect("alloc_vma failed for kernel stack of user thread");
+        Thread::alloc_kernel_stack_vmas(&parent, kernel_stack.allocator(), "user");
 
         //
         // Create user stack for the application
@@ -355,24 +281,19 @@ impl Thread {
             .iter_vmas()
             .filter(|vma| vma.typ == VmaType::UserStack)
             .max_by(|a, b| a.range.end.cmp(&b.range.end));
-        let stack_start = if let Some(vma) = highest_stack_vma {
-            // from there allocate new user stack
-            let user_stack_start : Page<Size4KiB> = Page::from_start_address(
+        let guard_start = if let Some(vma) = highest_stack_vma {
+            // from there allocate new user stack (above its guard pages)
+            let guard_start : Page<Size4KiB> = Page::from_start_address(
                 vma.end(),
             ).unwrap();
-            let user_stack_end = user_stack_start + (MAX_USER_STACK_SIZE / PAGE_SIZE) as u64;
-
-            let user_stack_pages = PageRange {
-                start: user_stack_start,
-                end: user_stack_end,
-            };
-            user_stack_pages.start.start_address().as_u64() as usize
+            guard_start.start_address().as_u64() as usize
         } else {
             MAIN_USER_STACK_START
         };
+        let stack_start = guard_start + STACK_GUARD_PAGES * PAGE_SIZE;
 
         // Alloc user stack for the main thread
-        let user_stack: Vec<u64, StackAllocator> = stack::alloc_user_stack(pid, tid, stack_start, MAX_USER_STACK_SIZE);
+        let user_stack: Vec<u64, StackAllocator> = stack::alloc_user_stack(pid, tid, stack_start, stack_size);
 
         // Allocate virtual memory area for user stack
         let user_stack_vma = parent
@@ -380,26 +301,41 @@ impl Thread {
             .alloc_vma(
                 Some( user_stack.allocator().get_start_page() ),
                 user_stack.allocator().get_num_pages(),
-                MemorySpace::Kernel,
+                MemorySpace::User,
                 VmaType::UserStack,
                 "user"
             )
             .expect("alloc_vma failed for user stack of user thread");
+        parent.virtual_address_space
+            .alloc_guard_vma(user_stack.allocator().get_guard_pages(), MemorySpace::User, "guard")
+            .expect("alloc_vma failed for guard pages of user stack");
 
         parent.virtual_address_space.map_partial_vma(
             &user_stack_vma,
             PageRange {start: user_stack_vma.range.end - 1, end: user_stack_vma.range.end},
             MemorySpace::User,
-            PageTableFlags::PRESENT | PageTableFlags::WRITABLE | PageTableFlags::USER_ACCESSIBLE,
-        );
+            user_stack_vma.flags,
+        ).expect("Failed to map user stack");
         
+        // The TLS block is placed at the top of the user stack
+        let (fs_base, stack_top) = Thread::prepare_tls_block(&parent, &user_stack_vma);
+
         // create user thread and prepare the stack for starting it later
+        let mut stacks = Stacks::new(kernel_stack, user_stack);
+        stacks.user_rsp = stack_top - 8u64;
         let thread = Thread {
             id: tid,
-            stacks: Mutex::new(Stacks::new(kernel_stack, user_stack)),
+            stacks: Mutex::new(stacks),
             process: parent,
             user_kickoff: kickoff_addr,
             entry,
+            user_arg: arg,
+            user_context: None,
+            priority: AtomicUsize::new(DEFAULT_PRIORITY),
+            effective_priority: AtomicUsize::new(DEFAULT_PRIORITY),
+            core: AtomicUsize::new(0),
+            killed: AtomicBool::new(false),
+            fs_base,
         };
 
         info!("Created user stack for thread at 0x{stack_start:x?}");
@@ -408,13 +344,168 @@ impl Thread {
         Arc::new(thread)
     }
 
+    /// Create a copy of the calling user thread for `process`, which has been forked from the calling process
+    /// (see `VirtualAddressSpace::clone_user_space`). Not started yet, nor registered in the scheduler. \
+    /// The new thread uses the copy of the user stack and continues after the system call
+    /// of the calling thread with the same registers, but 0 as return value.
+    pub fn new_forked_thread(process: Arc<Process>) -> Arc<Thread> {
+        let current = scheduler().current_thread();
+        let pid = process.id();
+        let tid = scheduler::next_thread_id();
+
+        // Allocate kernel stack for the new thread
+        let kernel_stack = stack::alloc_kernel_stack(pid, tid);
+        Thread::alloc_kernel_stack_vmas(&process, kernel_stack.allocator(), "fork");
+
+        // The calling thread is executing a system call, so its registers are on top of its kernel stack
+        let (user_stack_start, user_stack_size, user_context) = {
+            let stacks = current.stacks.lock();
+            let frame_addr = stacks.kernel_stack.as_ptr() as usize + stacks.kernel_stack.capacity() * 8 - size_of::<SyscallFrame>();
+            let user_context = unsafe { (frame_addr as *const SyscallFrame).read() };
+            (stacks.user_stack.as_ptr() as usize, stacks.user_stack.capacity() * 8, user_context)
+        };
+
+        // The user stack vma has already been copied with the address space
+        let user_stack = stack::alloc_user_stack(pid, tid, user_stack_start, user_stack_size);
+
+        let mut stacks = Stacks::new(kernel_stack, user_stack);
+        stacks.user_rsp = VirtAddr::new(user_context.rsp);
+        let thread = Thread {
+            id: tid,
+            stacks: Mutex::new(stacks),
+            process,
+            user_kickoff: VirtAddr::new(user_context.rcx),
+            entry: || {},
+            user_arg: 0,
+            user_context: Some(user_context),
+            priority: AtomicUsize::new(current.priority()),
+            effective_priority: AtomicUsize::new(current.priority()),
+            core: AtomicUsize::new(0),
+            killed: AtomicBool::new(false),
+            fs_base: current.fs_base, // the TLS block has been copied with the user stack
+        };
+
+        info!("new_forked_thread: pid = {pid}, tid = {tid}, parent tid = {}", current.id());
+
+        thread.prepare_kernel_stack();
+        Arc::new(thread)
+    }
+
+    /// Place `argv` and `envp` on the user stack of the main thread, as expected by the System V ABI. \
+    /// Layout (from the initial stack pointer upwards, which is 16 byte aligned): \
+    /// `argc`, `argv[0..argc]`, `NULL`, `envp[0..]`, `NULL`, auxiliary vector (only `AT_NULL`), strings. \
+    /// Everything is placed below the TLS block (if any), which lies at the top of the user stack.
+    fn prepare_initial_user_stack(&self, argv: &[&[u8]], envp: &[&[u8]]) {
+        let mut stacks = self.stacks.lock();
+        let stack_end = stacks.user_stack.as_ptr() as u64 + stacks.user_stack.capacity() as u64 * 8;
+        let stack_top = stacks.user_rsp.as_u64() + 8;
+
+        // Build the vectors (argc, argv, envp, auxv) with pointers to the strings, which are placed at the top
+        let strings_size = argv.iter().chain(envp.iter()).map(|s| s.len() + 1).sum::<usize>() as u64;
+        let strings_start = stack_top - 8 - strings_size; // keep the topmost slot unused (like for other threads)
+        let mut vectors = vec![argv.len() as u64];
+        let mut strings = Vec::with_capacity(strings_size as usize);
+        for list in [argv, envp] {
+            for string in list {
+                vectors.push(strings_start + strings.len() as u64);
+                strings.extend_from_slice(string);
+                strings.push(0); // null-terminate the string for C compatibility
+            }
+            vectors.push(0);
+        }
+        vectors.extend([0, 0]); // auxiliary vector, only AT_NULL
+        let rsp = (strings_start - (vectors.len() * 8) as u64) & !0xf;
+
+        let mut image = vec![0u8; (stack_top - rsp) as usize];
+        for (i, word) in vectors.iter().enumerate() {
+            image[i * 8..(i + 1) * 8].copy_from_slice(&word.to_le_bytes());
+        }
+        let strings_offset = (strings_start - rsp) as usize;
+        image[strings_offset..strings_offset + strings.len()].copy_from_slice(&strings);
+
+        // Map all required pages (the page below `stack_top` is already mapped) and copy the content
+        let address_space = &self.process.virtual_address_space;
+        let stack_vma = address_space
+            .iter_vmas()
+            .find(|vma| vma.typ == VmaType::UserStack && vma.end() == VirtAddr::new(stack_end))
+            .expect("User stack of main thread not found");
+        let first_page = Page::containing_address(VirtAddr::new(rsp));
+        let last_page = Page::containing_address(VirtAddr::new(stack_top - 1));
+        if first_page < last_page {
+            address_space.map_partial_vma(
+                &stack_vma,
+                PageRange { start: first_page, end: last_page },
+                MemorySpace::User,
+                stack_vma.flags,
+            ).expect("Failed to map user stack");
+        }
+        copy_to_user_space(address_space, VirtAddr::new(rsp), &image);
+
+        stacks.user_rsp = VirtAddr::new(rsp);
+    }
+
+    /// Create the TLS block of a new thread of `process` at the top of the user stack `stack_vma`
+    /// (only the topmost page is mapped yet), initialized from the TLS template of the process. \
+    /// Returns the thread pointer (zero, if the process has no TLS template) and the new top of the user stack below the block.
+    fn prepare_tls_block(process: &Process, stack_vma: &VirtualMemoryArea) -> (VirtAddr, VirtAddr) {
+        let stack_end = stack_vma.end();
+        let Some(template) = process.tls_template() else {
+            return (VirtAddr::zero(), stack_end);
+        };
+
+        // The thread pointer is aligned like the TLS block and the stack (the TCB lies above it).
+        // The block ends at the thread pointer and its size is rounded like the linker does for TLS offsets.
+        let thread_pointer = (stack_end - TCB_SIZE).align_down(template.align.max(16) as u64);
+        let block_start = thread_pointer - (template.size as u64).next_multiple_of(template.align as u64);
+
+        // Image of the TLS block and the TCB: data of the template, zeroed rest, self pointer
+        let mut image = vec![0u8; (thread_pointer + TCB_SIZE - block_start) as usize];
+        image[..template.data.len()].copy_from_slice(&template.data);
+        let tcb_offset = (thread_pointer - block_start) as usize;
+        image[tcb_offset..].copy_from_slice(&thread_pointer.as_u64().to_le_bytes());
+
+        // Map all pages of the block and the page below, used by the stack (the topmost page is already mapped)
+        let address_space = &process.virtual_address_space;
+        let first_page = Page::containing_address(block_start - 1u64);
+        let last_page = stack_vma.range.end - 1;
+        if first_page < last_page {
+            address_space.map_partial_vma(
+                stack_vma,
+                PageRange { start: first_page, end: last_page },
+                MemorySpace::User,
+                stack_vma.flags,
+            ).expect("Failed to map user stack");
+        }
+        copy_to_user_space(address_space, block_start, &image);
+
+        (thread_pointer, block_start)
+    }
+
+    /// Allocate the virtual memory areas for the kernel stack managed by `allocator` and its guard pages in the address space of `process`,
+    /// in which the stack is used (the guard pages are unmapped there, see `VirtualAddressSpace::alloc_guard_vma`)
+    fn alloc_kernel_stack_vmas(process: &Process, allocator: &StackAllocator, tag_str: &str) {
+        process.virtual_address_space
+            .alloc_vma(
+                Some(allocator.get_start_page()),
+                allocator.get_num_pages(),
+                MemorySpace::Kernel,
+                VmaType::KernelStack,
+                tag_str,
+            )
+            .expect("alloc_vma failed for kernel stack");
+        process.virtual_address_space
+            .alloc_guard_vma(allocator.get_guard_pages(), MemorySpace::Kernel, tag_str)
+            .expect("alloc_vma failed for guard pages of kernel stack");
+    }
+
     /// Called first for both a new kernel and a new user thread
     fn kickoff_kernel_thread() -> ! {
         let scheduler = scheduler();
         scheduler.set_init(); // scheduler initialized
 
         let thread = scheduler.current_thread();
-        tss().lock().privilege_stack_table[0] = thread.kernel_stack_addr(); // get stack pointer for kernel stack
+        // get stack pointer for kernel stack (with interrupts disabled, so that the thread cannot be moved to another core meanwhile)
+        interrupts::without_interrupts(|| tss().lock().privilege_stack_table[0] = thread.kernel_stack_addr());
 
         if thread.is_kernel_thread() {
             assert!(thread.user_kickoff.is_null());
@@ -426,6 +517,9 @@ impl Thread {
             let thread_ptr = ptr::from_ref(thread.as_ref());
             drop(thread); // Manually decrease reference count, because switch_to_user_mode() will never return
 
+            // The thread may have been killed, before it has been started
+            scheduler.exit_if_killed();
+
             let thread_ref = unsafe { thread_ptr.as_ref().unwrap() };
             thread_ref.switch_to_user_mode(); // call kickoff function of user thread
             // exit is in the entry function -> runtime::lib.rs
@@ -436,6 +530,7 @@ impl Thread {
     pub unsafe fn start_first(thread_ptr: *const Thread) {
         let thread = unsafe { thread_ptr.as_ref().unwrap() };
         let old_rsp0 = thread.stacks.lock().old_rsp0;
+        FsBase::write(thread.fs_base);
 
         unsafe {
             thread_kernel_start(old_rsp0.as_u64());
@@ -454,9 +549,10 @@ impl Thread {
             .virtual_address_space
             .page_table_address()
             .as_u64();
+        let next_fs_base = next.fs_base.as_u64();
 
         unsafe {
-            thread_switch(current_rsp0, next_rsp0, next_rsp0_end, next_address_space);
+            thread_switch(current_rsp0, next_rsp0, next_rsp0_end, next_address_space, next_fs_base);
         }
     }
 
@@ -476,6 +572,17 @@ impl Thread {
         VirtAddr::new(stacks.user_stack.as_ptr() as u64)
     }
 
+    /// Check if `stack_pointer` lies in the guard pages below the kernel stack (or at their end),
+    /// which means that the kernel stack has overflowed. Called by the double fault handler.
+    pub fn kernel_stack_overflowed(&self, stack_pointer: VirtAddr) -> bool {
+        // The stacks may be locked by the interrupted code on this core
+        let Some(stacks) = self.stacks.try_lock() else {
+            return false;
+        };
+        let guard = stacks.kernel_stack.allocator().get_guard_pages();
+        guard.start.start_address() <= stack_pointer && stack_pointer <= guard.end.start_address()
+    }
+
     /// Return reference to my process
     pub fn process(&self) -> Arc<Process> {
         Arc::clone(&self.process)
@@ -492,6 +599,47 @@ impl Thread {
         self.id
     }
 
+    /// Return the priority of the thread
+    pub fn priority(&self) -> usize {
+        self.priority.load(Relaxed)
+    }
+
+    /// Set the priority of the thread (`MIN_PRIORITY..=MAX_PRIORITY`). \
+    /// If the thread is ready, the new priority is used the next time it is inserted into the ready queue.
+    pub fn set_priority(&self, priority: usize) {
+        self.priority.store(min(priority, MAX_PRIORITY), Relaxed);
+    }
+
+    /// Return the priority currently used by the scheduling policy
+    pub fn effective_priority(&self) -> usize {
+        self.effective_priority.load(Relaxed)
+    }
+
+    /// Set the priority currently used by the scheduling policy
+    pub fn set_effective_priority(&self, priority: usize) {
+        self.effective_priority.store(min(priority, MAX_PRIORITY), Relaxed);
+    }
+
+    /// Return the core, on which the thread is scheduled
+    pub fn core(&self) -> usize {
+        self.core.load(Relaxed)
+    }
+
+    /// Set the core, on which the thread is scheduled (called by the scheduler, when inserting the thread into a ready queue)
+    pub fn set_core(&self, core: usize) {
+        self.core.store(core, Relaxed);
+    }
+
+    /// Mark the thread as killed, so that it exits at its next safe point (called by the scheduler)
+    pub fn kill(&self) {
+        self.killed.store(true, Relaxed);
+    }
+
+    /// Check if the thread has been killed
+    pub fn is_killed(&self) -> bool {
+        self.killed.load(Relaxed)
+    }
+
     /// Helper function, returns highest useable stack address of kernel stack  of 'self'
     fn kernel_stack_addr(&self) -> VirtAddr {
         let stacks = self.stacks.lock();
@@ -547,15 +695,14 @@ impl Thread {
             // Separate block to make sure that the lock is released, before calling `thread_user_start()`.
             let mut stacks = self.stacks.lock();
             let kernel_stack_addr = stacks.kernel_stack.as_ptr() as u64;
-            let user_stack_addr = stacks.user_stack.as_ptr() as u64;
             let capacity = stacks.kernel_stack.capacity();
 
             stacks.kernel_stack[capacity - 6] = self.user_kickoff.as_u64(); // Address of entry point for user thread
 
             stacks.kernel_stack[capacity - 5] = SegmentSelector::new(4, Ring3).0 as u64; // cs = user code segment
-            stacks.kernel_stack[capacity - 4] = 0x202; // rflags (Interrupts enabled)
-            stacks.kernel_stack[capacity - 3] =
-                user_stack_addr + (stacks.user_stack.capacity() - 1) as u64 * 8; // rsp for user stack
+            // rflags (Interrupts enabled, forked threads continue with the flags of the forking thread)
+            stacks.kernel_stack[capacity - 4] = self.user_context.as_ref().map_or(0x202, |context| context.r11);
+            stacks.kernel_stack[capacity - 3] = stacks.user_rsp.as_u64(); // rsp for user stack
             stacks.kernel_stack[capacity - 2] = SegmentSelector::new(3, Ring3).0 as u64; // ss = user data segment
 
             stacks.kernel_stack[capacity - 1] = 0x00DEAD00u64; // Dummy return address
@@ -565,11 +712,27 @@ impl Thread {
         }
 
         unsafe {
-            thread_user_start(old_rsp0, self.entry);
+            match &self.user_context {
+                Some(context) => thread_fork_start(old_rsp0, context),
+                None => thread_user_start(old_rsp0, self.entry, self.user_arg),
+            }
         }
     }
 }
 
+/// Copy `data` to the user space address `addr` of `address_space` (all pages must be mapped). \
+/// The data is copied page by page, because the frames are not necessarily contiguous.
+fn copy_to_user_space(address_space: &VirtualAddressSpace, mut addr: VirtAddr, data: &[u8]) {
+    let mut remaining = data;
+    while !remaining.is_empty() {
+        let count = remaining.len().min(PAGE_SIZE - (addr.as_u64() as usize % PAGE_SIZE));
+        let phys_addr = address_space.translate(addr).expect("User space page not mapped");
+        unsafe { (phys_addr.as_u64() as *mut u8).copy_from(remaining.as_ptr(), count) };
+        remaining = &remaining[count..];
+        addr += count as u64;
+    }
+}
+
 /// Low-level function for starting a thread in kernel mode
 #[unsafe(naked)]
 unsafe extern "C" fn thread_kernel_start(old_rsp0: u64) {
@@ -599,14 +762,39 @@ unsafe extern "C" fn thread_kernel_start(old_rsp0: u64) {
 /// Low-level function for starting a thread in user mode
 #[unsafe(naked)]
 #[allow(improper_ctypes_definitions)] // 'entry' takes no arguments and has no return value, so we just assume that the "C" and "Rust" ABIs act the same way in this case
-unsafe extern "C" fn thread_user_start(old_rsp0: u64, entry: fn()) -> ! {
+unsafe extern "C" fn thread_user_start(old_rsp0: u64, entry: fn(), arg: usize) -> ! {
     naked_asm!(
         "mov rsp, rdi", // Load 'old_rsp' (first parameter)
         "mov rdi, rsi", // Second parameter becomes first parameter for 'kickoff_user_thread()'
+        "mov rsi, rdx", // Third parameter becomes second parameter for 'kickoff_user_thread()'
         "iretq"         // Switch to user-mode
     )
 }
 
+/// Low-level function for starting a forked thread in user mode. \
+/// The callee-saved registers are restored from `context` (the caller-saved ones are clobbered by a system call anyway)
+/// and 0 is returned as result of the `Fork` system call.
+#[unsafe(naked)]
+unsafe extern "C" fn thread_fork_start(old_rsp0: u64, context: *const SyscallFrame) -> ! {
+    naked_asm!(
+        "mov rbp, [rsi + {RBP}]",
+        "mov rbx, [rsi + {RBX}]",
+        "mov r12, [rsi + {R12}]",
+        "mov r13, [rsi + {R13}]",
+        "mov r14, [rsi + {R14}]",
+        "mov r15, [rsi + {R15}]",
+        "mov rsp, rdi", // Load 'old_rsp' (first parameter)
+        "xor eax, eax", // Return value of the system call (0 -> child)
+        "iretq",        // Switch to user-mode
+        RBP = const offset_of!(SyscallFrame, rbp),
+        RBX = const offset_of!(SyscallFrame, rbx),
+        R12 = const offset_of!(SyscallFrame, r12),
+        R13 = const offset_of!(SyscallFrame, r13),
+        R14 = const offset_of!(SyscallFrame, r14),
+        R15 = const offset_of!(SyscallFrame, r15),
+    )
+}
+
 /// Low-level thread switching function
 #[unsafe(naked)]
 unsafe extern "C" fn thread_switch(
@@ -614,6 +802,7 @@ unsafe extern "C" fn thread_switch(
     next_rsp0: u64,
     next_rsp0_end: u64,
     next_cr3: u64,
+    next_fs_base: u64,
 ) {
     naked_asm!(
     // Save registers of current thread
@@ -638,6 +827,8 @@ unsafe extern "C" fn thread_switch(
     "mov [rdi], rsp",
 
     // Set rsp0 of kernel stack in tss (third parameter 'next_rsp0_end')
+    // Interrupts must be disabled while gs base is swapped (flags of the next thread are restored below)
+    "cli",
     "swapgs", // Setup core local storage access via gs base
     "mov rax,gs:[{CORE_LOCAL_STORAGE_TSS_RSP0_PTR_INDEX}]", // Load pointer to rsp0 entry of tss into rax
     "mov [rax],rdx", // Set rsp0 entry in tss to 'next_rsp0_end' (third parameter)
@@ -646,6 +837,14 @@ unsafe extern "C" fn thread_switch(
     // Switch address space (fourth parameter 'next_cr3')
     "mov cr3, rcx",
 
+    // Set fs base for the thread local storage of the next thread (fifth parameter 'next_fs_base')
+    // (rax, rcx, rdx and r8 are restored from the stack of the next thread below)
+    "mov ecx, {FS_BASE_MSR}",
+    "mov rax, r8",
+    "mov rdx, r8",
+    "shr rdx, 32",
+    "wrmsr",
+
     // Load registers of next thread by using 'next_rsp0' (second parameter)
     "mov rsp, rsi",
     "pop rbp",
@@ -667,6 +866,7 @@ unsafe extern "C" fn thread_switch(
 
     "call unlock_scheduler", // force unlock, thread_switch locks Scheduler but returns later
     "ret", // Return to next thread
-    CORE_LOCAL_STORAGE_TSS_RSP0_PTR_INDEX = const CORE_LOCAL_STORAGE_TSS_RSP0_PTR_INDEX
+    CORE_LOCAL_STORAGE_TSS_RSP0_PTR_INDEX = const CORE_LOCAL_STORAGE_TSS_RSP0_PTR_INDEX,
+    FS_BASE_MSR = const FS_BASE_MSR,
     )
 }
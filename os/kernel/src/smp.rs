@@ -0,0 +1,253 @@
+/* ╔═════════════════════════════════════════════════════════════════════════╗
+   ║ Module: smp                                                             ║
+   ╟─────────────────────────────────────────────────────────────────────────╢
+   ║ Symmetric multiprocessing. The application processors (APs) listed in   ║
+   ║ the MADT are started with INIT-SIPI-SIPI, executing the startup code in ║
+   ║ 'boot.asm' and then 'boot::start_application_processor()'. Each core    ║
+   ║ has its own GDT, TSS, core local storage and ready queue.               ║
+   ║                                                                         ║
+   ║ Public functions:                                                       ║
+   ║  - core_id                       id of the calling core (BSP = 0)       ║
+   ║  - online_cores                  ids of all running cores               ║
+   ║  - set_online                    mark calling core as running           ║
+   ║  - start_application_processors  start all APs, called by 'boot.rs'     ║
+   ║  - tlb_shootdown                 flush the TLBs of all cores            ║
+   ╟─────────────────────────────────────────────────────────────────────────╢
+   ║ Author: agent, 18.10.2026                                               ║
+   ╚═════════════════════════════════════════════════════════════════════════╝
+*/
+use crate::boot::start_application_processor;
+use crate::consts::{KERNEL_STACK_PAGES, MAX_CORES};
+use crate::interrupt::interrupt_dispatcher::InterruptVector;
+use crate::interrupt::interrupt_handler::InterruptHandler;
+use crate::memory::{frames, PAGE_SIZE};
+use crate::process::signal;
+use crate::process::thread::Thread;
+use crate::syscall::syscall_dispatcher::CoreLocalStorage;
+use crate::{apic, interrupt_dispatcher, scheduler, timer};
+use alloc::boxed::Box;
+use alloc::sync::Arc;
+use core::hint::spin_loop;
+use core::ptr;
+use core::sync::atomic::AtomicUsize;
+use core::sync::atomic::Ordering::{AcqRel, Acquire, Release};
+use log::{info, warn};
+use syscall::priority::MIN_PRIORITY;
+use x86_64::instructions::{hlt, interrupts, tlb};
+use x86_64::registers::control::{Cr0, Cr3, Cr4, Cr4Flags};
+use x86_64::registers::model_specific::{Efer, EferFlags, KernelGsBase};
+
+/// Physical address (below 1 MiB), to which the startup code of the application processors is copied
+/// (must match 'AP_TRAMPOLINE_ADDR' in 'boot.asm')
+pub const AP_TRAMPOLINE_ADDR: u64 = 0x8000;
+
+/// Time (in ms) to wait for an application processor to come online
+const AP_STARTUP_TIMEOUT_MS: usize = 100;
+
+// import labels from 'boot.asm'
+unsafe extern "C" {
+    static ap_trampoline: u8; // start of the startup code
+    static ap_trampoline_data: u8; // data block for the startup code (see `TrampolineData`)
+    static ap_trampoline_end: u8; // end of the startup code
+}
+
+/// Data block read by the startup code of an application processor (layout must match 'ap_trampoline_data' in 'boot.asm')
+#[repr(C)]
+struct TrampolineData {
+    cr0: u64,
+    cr3: u64,
+    cr4: u64,
+    efer: u64,
+    stack: u64, // top of the stack, used until the scheduler is started on the core
+    entry: u64, // address of `start_application_processor()`
+    core_id: u64,
+}
+
+/// Bit mask of the running cores (the bootstrap processor is core 0 and always running)
+static ONLINE_CORES: AtomicUsize = AtomicUsize::new(1);
+
+/// Number of TLB flushes requested from each core (see `tlb_shootdown()`)
+static TLB_FLUSH_REQUESTS: [AtomicUsize; MAX_CORES] = [const { AtomicUsize::new(0) }; MAX_CORES];
+
+/// Number of TLB flush requests handled by each core
+static TLB_FLUSHES: [AtomicUsize; MAX_CORES] = [const { AtomicUsize::new(0) }; MAX_CORES];
+
+/// Interrupt handler for TLB shootdown IPIs
+#[derive(Default)]
+struct TlbShootdownHandler {}
+
+impl InterruptHandler for TlbShootdownHandler {
+    fn trigger(&self) {
+        flush_requested_tlb(core_id());
+    }
+}
+
+/// Interrupt handler for IPIs sent after a running thread has been killed (see `Scheduler::kill`).
+/// Nothing to do here: the killed thread exits, when the interrupt returns to user mode.
+#[derive(Default)]
+struct ThreadKilledHandler {}
+
+impl InterruptHandler for ThreadKilledHandler {
+    fn trigger(&self) {}
+}
+
+/// Return the id of the calling core (index into all per core structures, the bootstrap processor is core 0)
+pub fn core_id() -> usize {
+    let core_local_storage = KernelGsBase::read();
+    if core_local_storage.is_null() {
+        // Core local storage has not been initialized yet (early boot of the bootstrap processor)
+        return 0;
+    }
+
+    unsafe { core_local_storage.as_ptr::<CoreLocalStorage>().as_ref().unwrap().core_id() }
+}
+
+/// Return the ids of all running cores
+pub fn online_cores() -> impl Iterator<Item = usize> {
+    let online = ONLINE_CORES.load(Acquire);
+    (0..MAX_CORES).filter(move |core| online & (1 << core) != 0)
+}
+
+/// Mark the calling core as running, called by `start_application_processor()` when the core is ready to schedule threads
+pub fn set_online() {
+    ONLINE_CORES.fetch_or(1 << core_id(), AcqRel);
+}
+
+/// Start the application processors listed in the MADT (at most `MAX_CORES - 1`), called once by 'boot.rs'. \
+/// Each core (including the bootstrap processor) gets an idle thread, which takes over ready threads from other cores,
+/// if there is nothing else to do on its own core.
+pub fn start_application_processors() {
+    interrupt_dispatcher().assign(InterruptVector::TlbShootdown, Box::new(TlbShootdownHandler::default()));
+    interrupt_dispatcher().assign(InterruptVector::ThreadKilled, Box::new(ThreadKilledHandler::default()));
+    scheduler().ready_on_core(new_idle_thread(), 0);
+
+    // The startup code loads cr3 in real mode, so only 32 bits are available
+    let page_table = Cr3::read().0.start_address().as_u64();
+    if page_table > u32::MAX as u64 {
+        warn!("Page tables are located above 4 GiB -> Not starting application processors");
+        return;
+    }
+
+    // Copy the startup code below 1 MiB (the page frame has been reserved during boot)
+    let (trampoline, trampoline_len, data_offset) = unsafe {
+        let start = ptr::from_ref(&ap_trampoline);
+        let data = ptr::from_ref(&ap_trampoline_data);
+        let end = ptr::from_ref(&ap_trampoline_end);
+        (start, end.offset_from(start) as usize, data.offset_from(start) as u64)
+    };
+    assert!(trampoline_len <= PAGE_SIZE, "Startup code of application processors does not fit into a page frame!");
+    unsafe { (AP_TRAMPOLINE_ADDR as *mut u8).copy_from_nonoverlapping(trampoline, trampoline_len); }
+    let data = (AP_TRAMPOLINE_ADDR + data_offset) as *mut TrampolineData;
+
+    for (index, &apic_id) in apic().application_processors().iter().take(MAX_CORES - 1).enumerate() {
+        let core = index + 1;
+        info!("Starting application processor [{apic_id}] as core [{core}]");
+
+        // Stack used during the initialization of the core (afterward, the core uses the stacks of its threads),
+        // which must be reachable by the startup code in 32 bit mode
+        let stack = frames::alloc_dma32(KERNEL_STACK_PAGES);
+        unsafe {
+            data.write_volatile(TrampolineData {
+                cr0: Cr0::read_raw(),
+                cr3: page_table,
+                cr4: Cr4::read_raw() & !Cr4Flags::PCID.bits(), // PCIDs cannot be enabled outside long mode
+                efer: Efer::read_raw() & !EferFlags::LONG_MODE_ACTIVE.bits(),
+                stack: stack.end.start_address().as_u64(),
+                entry: start_application_processor as *const () as u64,
+                core_id: core as u64,
+            });
+        }
+
+        // INIT-SIPI-SIPI (the second startup IPI is only needed, if the first one has been missed)
+        apic().send_init_ipi(apic_id);
+        timer().wait(10);
+        apic().send_startup_ipi(apic_id, (AP_TRAMPOLINE_ADDR / PAGE_SIZE as u64) as u8);
+        if !wait_until_online(core, 1) {
+            apic().send_startup_ipi(apic_id, (AP_TRAMPOLINE_ADDR / PAGE_SIZE as u64) as u8);
+        }
+
+        if !wait_until_online(core, AP_STARTUP_TIMEOUT_MS) {
+            // The core might still read the data block later on, so we cannot reuse it for the next core
+            warn!("Application processor [{apic_id}] did not start -> Not starting remaining application processors");
+            break;
+        }
+
+        scheduler().ready_on_core(new_idle_thread(), core);
+    }
+
+    info!("[{}] cores online", online_cores().count());
+}
+
+/// Flush the TLBs of all cores (after page table entries have been changed or removed). \
+/// The other cores are notified by an IPI and the calling core waits until all of them have flushed their TLB.
+pub fn tlb_shootdown() {
+    interrupts::without_interrupts(|| {
+        let core = core_id();
+        tlb::flush_all();
+
+        let mut targets = [0; MAX_CORES];
+        let mut others = false;
+        for other in online_cores().filter(|&other| other != core) {
+            targets[other] = TLB_FLUSH_REQUESTS[other].fetch_add(1, AcqRel) + 1;
+            others = true;
+        }
+        if !others {
+            return;
+        }
+
+        apic().send_ipi_to_others(InterruptVector::TlbShootdown);
+
+        // Another core may wait for this core to flush its TLB at the same time (with interrupts disabled)
+        for other in online_cores().filter(|&other| other != core) {
+            while TLB_FLUSHES[other].load(Acquire) < targets[other] {
+                flush_requested_tlb(core);
+                spin_loop();
+            }
+        }
+    });
+}
+
+/// Flush the TLB of `core` (the calling core), if requested by another core
+fn flush_requested_tlb(core: usize) {
+    let requests = TLB_FLUSH_REQUESTS[core].load(Acquire);
+    if TLB_FLUSHES[core].load(Acquire) < requests {
+        tlb::flush_all();
+        TLB_FLUSHES[core].store(requests, Release);
+    }
+}
+
+/// Wait up to `timeout_ms` milliseconds for `core` to come online
+fn wait_until_online(core: usize, timeout_ms: usize) -> bool {
+    let end = timer().systime_ms() + timeout_ms;
+    while ONLINE_CORES.load(Acquire) & (1 << core) == 0 {
+        if timer().systime_ms() > end {
+            return false;
+        }
+        spin_loop();
+    }
+
+    true
+}
+
+/// Create the idle thread of a core, which runs, if there is no other ready thread on the core.
+/// It takes over ready threads from other cores or halts until the next interrupt.
+fn new_idle_thread() -> Arc<Thread> {
+    let thread = Thread::new_kernel_thread(
+        || {
+            loop {
+                // Ctrl-C may have been pressed, while all user threads are waiting (see `signal`)
+                signal::deliver_interrupt();
+
+                if scheduler().steal() || scheduler().has_ready_threads() {
+                    scheduler().switch_thread_no_interrupt();
+                } else {
+                    hlt();
+                }
+            }
+        },
+        "idle",
+    );
+
+    thread.set_priority(MIN_PRIORITY);
+    thread
+}
@@ -1,6 +1,7 @@
 use alloc::format;
 use alloc::string::{String, ToString};
 use alloc::sync::Arc;
+use alloc::vec::Vec;
 use log::info;
 use smallmap::Map;
 use spin::{Mutex, Once, RwLock};
@@ -45,4 +46,9 @@ pub fn block_device(name: &str) -> Option<Arc<dyn BlockDevice + Send + Sync>> {
         None => None,
         Some(device) => Some(Arc::clone(device))
     }
-}
\ No newline at end of file
+}
+
+/// Get the names of all registered block devices and partitions
+pub fn block_device_names() -> Vec<String> {
+    BLOCK_DEVICES.call_once(|| RwLock::new(Map::new())).read().keys().cloned().collect()
+}
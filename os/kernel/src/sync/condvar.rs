@@ -0,0 +1,56 @@
+/* ╔═════════════════════════════════════════════════════════════════════════╗
+   ║ Module: condvar                                                         ║
+   ╟─────────────────────────────────────────────────────────────────────────╢
+   ║ Condition variable, used together with 'sync::Mutex'. As usual, a       ║
+   ║ thread may wake up, although the condition is not true (e.g. because    ║
+   ║ another thread changed it back), so it has to be checked in a loop      ║
+   ║ (see 'wait_while').                                                     ║
+   ╟─────────────────────────────────────────────────────────────────────────╢
+   ║ Author: agent, 18.10.2026                                               ║
+   ╚═════════════════════════════════════════════════════════════════════════╝
+*/
+use super::mutex::MutexGuard;
+use super::wait_queue::WaitQueue;
+
+pub struct Condvar {
+    waiting: WaitQueue,
+}
+
+impl Condvar {
+    pub const fn new() -> Self {
+        Self { waiting: WaitQueue::new() }
+    }
+
+    /// Unlock the mutex of `guard` and block the calling thread until it is notified. \
+    /// The mutex is locked again, before this function returns.
+    pub fn wait<'a, T: ?Sized>(&self, guard: MutexGuard<'a, T>) -> MutexGuard<'a, T> {
+        let mutex = guard.mutex();
+
+        // The mutex is unlocked with the wait queue locked, so a notification cannot get lost
+        self.waiting.wait_if(|| {
+            drop(guard);
+            true
+        });
+
+        mutex.lock()
+    }
+
+    /// Block the calling thread (see `wait`), as long as `condition` returns true for the data protected by the mutex
+    pub fn wait_while<'a, T: ?Sized>(&self, mut guard: MutexGuard<'a, T>, mut condition: impl FnMut(&mut T) -> bool) -> MutexGuard<'a, T> {
+        while condition(&mut *guard) {
+            guard = self.wait(guard);
+        }
+
+        guard
+    }
+
+    /// Wake up one waiting thread
+    pub fn notify_one(&self) {
+        self.waiting.wake_one();
+    }
+
+    /// Wake up all waiting threads
+    pub fn notify_all(&self) {
+        self.waiting.wake_all();
+    }
+}
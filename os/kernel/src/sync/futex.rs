@@ -0,0 +1,74 @@
+/* ╔═════════════════════════════════════════════════════════════════════════╗
+   ║ Module: futex                                                           ║
+   ╟─────────────────────────────────────────────────────────────────────────╢
+   ║ Fast user space mutexes. User space synchronization primitives use an   ║
+   ║ atomic 32 bit word and only enter the kernel to block on the word or    ║
+   ║ to wake up threads blocked on it ('FutexWait' and 'FutexWake').         ║
+   ║ A futex is identified by the process id and the virtual address of the  ║
+   ║ word, so it cannot be shared between processes. Wait queues are only   ║
+   ║ kept, while they are used.                                              ║
+   ║                                                                         ║
+   ║ Public functions:                                                       ║
+   ║  - wait            block, if the word has the expected value            ║
+   ║  - wake            wake up threads blocked on a word                    ║
+   ║  - remove_process  drop the wait queues of an exited process            ║
+   ╟─────────────────────────────────────────────────────────────────────────╢
+   ║ Author: agent, 18.10.2026                                               ║
+   ╚═════════════════════════════════════════════════════════════════════════╝
+*/
+use alloc::collections::BTreeMap;
+use alloc::sync::Arc;
+use spin::Mutex;
+use syscall::return_vals::Errno;
+use super::wait_queue::WaitQueue;
+
+/// Wait queues of all futexes currently used (key: process id and virtual address of the word)
+static FUTEXES: Mutex<BTreeMap<(usize, usize), Arc<WaitQueue>>> = Mutex::new(BTreeMap::new());
+
+/// Block the calling thread of process `process_id` until it is woken up by `wake`, if the word at `address` contains `expected`.
+/// The word is read by `load` with the wait queue locked, so a wake-up after changing the word cannot get lost. \
+/// Returns `Ok(false)`, if the word does not contain `expected` (the thread has not been blocked),
+/// or the error returned by `load`.
+pub fn wait(process_id: usize, address: usize, expected: u32, load: impl FnOnce() -> Result<u32, Errno>) -> Result<bool, Errno> {
+    let key = (process_id, address);
+    let queue = Arc::clone(FUTEXES.lock().entry(key).or_insert_with(|| Arc::new(WaitQueue::new())));
+
+    let mut value = Ok(expected);
+    let blocked = queue.wait_if(|| {
+        value = load();
+        value == Ok(expected)
+    });
+    release(key, queue);
+    value.map(|_| blocked)
+}
+
+/// Wake up to `count` threads of process `process_id` blocked on the word at `address` and return their number
+pub fn wake(process_id: usize, address: usize, count: usize) -> usize {
+    let key = (process_id, address);
+    let Some(queue) = FUTEXES.lock().get(&key).cloned() else {
+        return 0;
+    };
+
+    let mut woken = 0;
+    while woken < count && queue.wake_one() {
+        woken += 1;
+    }
+
+    release(key, queue);
+    woken
+}
+
+/// Drop the wait queues of the exited process `process_id` (threads blocked on them have been killed)
+pub fn remove_process(process_id: usize) {
+    FUTEXES.lock().retain(|&(id, _), _| id != process_id);
+}
+
+/// Drop the reference `queue` of the futex `key` and remove the wait queue, if nobody uses it anymore
+fn release(key: (usize, usize), queue: Arc<WaitQueue>) {
+    let mut futexes = FUTEXES.lock();
+    drop(queue);
+
+    if futexes.get(&key).is_some_and(|queue| Arc::strong_count(queue) == 1 && queue.is_empty()) {
+        futexes.remove(&key);
+    }
+}
@@ -0,0 +1,27 @@
+/* ╔═════════════════════════════════════════════════════════════════════════╗
+   ║ Module: sync                                                            ║
+   ╟─────────────────────────────────────────────────────────────────────────╢
+   ║ Synchronization primitives, which block the calling thread instead of  ║
+   ║ spinning (built on 'Scheduler::block_and_unlock' and 'wake_up').        ║
+   ║ They must not be used in interrupt handlers. Short critical sections    ║
+   ║ and data used by interrupt handlers are still protected by spin locks.  ║
+   ║                                                                         ║
+   ║   - WaitQueue  threads waiting for a condition                          ║
+   ║   - Mutex      mutual exclusion                                         ║
+   ║   - Semaphore  counting semaphore                                       ║
+   ║   - Condvar    condition variable, used together with a Mutex           ║
+   ║   - futex      wait queues for user space addresses (FutexWait/Wake)    ║
+   ╟─────────────────────────────────────────────────────────────────────────╢
+   ║ Author: agent, 18.10.2026                                               ║
+   ╚═════════════════════════════════════════════════════════════════════════╝
+*/
+pub mod wait_queue;
+pub mod mutex;
+pub mod semaphore;
+pub mod condvar;
+pub mod futex;
+
+pub use condvar::Condvar;
+pub use mutex::{Mutex, MutexGuard};
+pub use semaphore::Semaphore;
+pub use wait_queue::WaitQueue;
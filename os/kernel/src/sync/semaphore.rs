@@ -0,0 +1,46 @@
+/* ╔═════════════════════════════════════════════════════════════════════════╗
+   ║ Module: semaphore                                                       ║
+   ╟─────────────────────────────────────────────────────────────────────────╢
+   ║ Counting semaphore, 'acquire' blocks the calling thread while the       ║
+   ║ counter is zero. Must not be used in interrupt handlers ('release' may  ║
+   ║ be called anywhere, where the scheduler may be used).                   ║
+   ╟─────────────────────────────────────────────────────────────────────────╢
+   ║ Author: agent, 18.10.2026                                               ║
+   ╚═════════════════════════════════════════════════════════════════════════╝
+*/
+use core::sync::atomic::AtomicUsize;
+use core::sync::atomic::Ordering::{Acquire, Relaxed, Release};
+use super::wait_queue::WaitQueue;
+
+pub struct Semaphore {
+    count: AtomicUsize,
+    waiting: WaitQueue,
+}
+
+impl Semaphore {
+    pub const fn new(count: usize) -> Self {
+        Self { count: AtomicUsize::new(count), waiting: WaitQueue::new() }
+    }
+
+    /// Decrement the counter, blocking the calling thread while it is zero
+    pub fn acquire(&self) {
+        if !self.try_acquire() {
+            self.waiting.wait_while(|| !self.try_acquire());
+        }
+    }
+
+    /// Decrement the counter, if it is not zero. Returns `false`, if the counter is zero.
+    pub fn try_acquire(&self) -> bool {
+        self.count.fetch_update(Acquire, Relaxed, |count| count.checked_sub(1)).is_ok()
+    }
+
+    /// Increment the counter and wake up a waiting thread
+    pub fn release(&self) {
+        self.count.fetch_add(1, Release);
+        self.waiting.wake_one();
+    }
+
+    pub fn count(&self) -> usize {
+        self.count.load(Relaxed)
+    }
+}
@@ -0,0 +1,71 @@
+/* ╔═════════════════════════════════════════════════════════════════════════╗
+   ║ Module: wait_queue                                                      ║
+   ╟─────────────────────────────────────────────────────────────────────────╢
+   ║ Queue of threads blocked until a condition becomes true. The condition  ║
+   ║ is checked with the queue locked, so a thread cannot miss a wake-up     ║
+   ║ issued after the condition has been changed.                            ║
+   ╟─────────────────────────────────────────────────────────────────────────╢
+   ║ Author: agent, 18.10.2026                                               ║
+   ╚═════════════════════════════════════════════════════════════════════════╝
+*/
+use alloc::collections::VecDeque;
+use alloc::sync::Arc;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::mem;
+use spin::Mutex;
+use crate::process::thread::Thread;
+use crate::scheduler;
+
+pub struct WaitQueue {
+    threads: Mutex<VecDeque<Arc<Thread>>>,
+}
+
+impl WaitQueue {
+    pub const fn new() -> Self {
+        Self { threads: Mutex::new(VecDeque::new()) }
+    }
+
+    /// Block the calling thread once, if `condition` returns true (checked with the queue locked). \
+    /// Returns `true`, if the thread has been blocked and woken up again.
+    pub fn wait_if(&self, condition: impl FnOnce() -> bool) -> bool {
+        let mut threads = self.threads.lock();
+        if !condition() {
+            return false;
+        }
+
+        threads.push_back(scheduler().current_thread());
+        scheduler().block_and_unlock(threads);
+        true
+    }
+
+    /// Block the calling thread, as long as `condition` returns true
+    pub fn wait_while(&self, mut condition: impl FnMut() -> bool) {
+        while self.wait_if(&mut condition) {}
+    }
+
+    /// Wake up the thread waiting the longest. Returns `false`, if no thread has been woken up.
+    pub fn wake_one(&self) -> bool {
+        loop {
+            let Some(thread) = self.threads.lock().pop_front() else {
+                return false;
+            };
+
+            // Killed threads are dropped by the scheduler, so we try the next one
+            if scheduler().wake_up(vec![thread]) > 0 {
+                return true;
+            }
+        }
+    }
+
+    /// Wake up all waiting threads and return their number
+    pub fn wake_all(&self) -> usize {
+        let threads = mem::take(&mut *self.threads.lock());
+        scheduler().wake_up(Vec::from(threads))
+    }
+
+    /// Check if no thread is waiting
+    pub fn is_empty(&self) -> bool {
+        self.threads.lock().is_empty()
+    }
+}
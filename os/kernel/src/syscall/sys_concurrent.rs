@@ -6,28 +6,158 @@
    ║ Author: Fabian Ruhland, 30.8.2024, HHU                                  ║
    ╚═════════════════════════════════════════════════════════════════════════╝
 */
+use alloc::format;
 use alloc::vec::Vec;
 use alloc::sync::Arc;
-use core::ptr::slice_from_raw_parts;
-use core::str::from_utf8;
+use core::mem::{align_of, size_of};
 use x86_64::VirtAddr;
 use syscall::return_vals::Errno;
-use crate::{initrd, process_manager, scheduler};
+use syscall::priority::MAX_PRIORITY;
+use syscall::signal::Signal;
+use crate::{naming, process_manager, scheduler};
+use crate::consts::{MAX_USER_STACK_SIZE, MIN_USER_STACK_SIZE};
+use crate::memory::PAGE_SIZE;
+use crate::memory::user_access::{self, UserSlice};
+use crate::naming::open_objects::OpenObjectTable;
+use crate::process::image::ExecutableImage;
+use crate::process::signal::{self, SignalHandler};
+use crate::process::process::Process;
 use crate::process::thread::Thread;
+use crate::sync::futex;
+
+/// Standard handles inherited by new processes (stdin, stdout, stderr)
+const STDIN: usize = 0;
+const STDOUT: usize = 1;
+const STDERR: usize = 2;
 
 
 pub fn sys_process_id() -> isize {
     process_manager().read().current_process().id() as isize
 }
 
-pub fn sys_process_exit() -> isize {
-    scheduler().current_thread().process().exit();
+pub fn sys_process_exit(status: isize) -> isize {
+    scheduler().current_thread().process().exit(status);
     scheduler().exit();
     0
 }
 
-pub fn sys_thread_create(kickoff_addr: u64, entry: fn()) -> isize {
-    let thread = Thread::new_user_thread(process_manager().read().current_process(), VirtAddr::new(kickoff_addr), entry);
+/// Wait until the child process `pid` has exited, write its exit status to `status` (if not null) and return `pid`
+pub fn sys_wait_pid(pid: usize, status: *mut isize) -> isize {
+    let parent = process_manager().read().current_process();
+    let parent_id = parent.id();
+    let child = process_manager().read().active_child(parent_id, pid);
+
+    let exit_status = match child {
+        Some(child) => {
+            let exit_status = parent.wait_for_child(&child);
+            process_manager().write().take_exit_status(parent_id, pid);
+            exit_status
+        }
+        None => match process_manager().write().take_exit_status(parent_id, pid) {
+            Some(exit_status) => exit_status,
+            None => return Errno::ECHILD.into(),
+        },
+    };
+
+    if !status.is_null() && let Err(e) = user_access::write_to_user(status, &exit_status) {
+        return e.into();
+    }
+    pid as isize
+}
+
+/// Send the signal `signal` to the process `pid`, which must be the calling process or one of its descendants
+/// (`EACCES` otherwise).
+pub fn sys_kill(pid: usize, signal: usize) -> isize {
+    let Ok(signal) = Signal::try_from(signal) else {
+        return Errno::EINVAL.into();
+    };
+    {
+        let process_manager = process_manager().read();
+        if process_manager.active_process(pid).is_none() {
+            return Errno::ESRCH.into();
+        }
+        if !process_manager.is_descendant(pid, process_manager.current_process().id()) {
+            return Errno::EACCES.into();
+        }
+    }
+
+    match signal::send(pid, signal) {
+        Ok(()) => 0,
+        Err(e) => e.into(),
+    }
+}
+
+/// Set the handler for `signal` of the calling process. The handler is started like a thread,
+/// by calling `kickoff_addr` with `entry` as parameter. If `entry` is `None`, the default action is restored. \
+/// Returns `EINVAL`, if `kickoff_addr` or `entry` is not executable code of the calling process.
+pub fn sys_signal_handler(signal: usize, kickoff_addr: u64, entry: Option<fn()>) -> isize {
+    let signal = match Signal::try_from(signal) {
+        Ok(Signal::SIGKILL) | Err(_) => return Errno::EINVAL.into(),
+        Ok(signal) => signal,
+    };
+
+    let process = process_manager().read().current_process();
+    let handler = match entry {
+        Some(entry) => match (user_code_address(&process, kickoff_addr), user_code_address(&process, entry as u64)) {
+            (Ok(kickoff), Ok(_)) => Some(SignalHandler { kickoff, entry }),
+            (Err(e), _) | (_, Err(e)) => return e.into(),
+        },
+        None => None,
+    };
+    process.set_signal_handler(signal, handler);
+    0
+}
+
+/// Check that `addr` (passed by the calling `process`) is a canonical address of executable user code
+fn user_code_address(process: &Process, addr: u64) -> Result<VirtAddr, Errno> {
+    let addr = VirtAddr::try_new(addr).map_err(|_| Errno::EINVAL)?;
+    if !process.virtual_address_space.is_user_code(addr) {
+        return Err(Errno::EINVAL);
+    }
+    Ok(addr)
+}
+
+/// Create a child process as copy of the calling process and return its id. \
+/// The address space is shared copy-on-write and the child gets the same open objects, signal handlers, TLS template
+/// and executable image.
+/// Only the calling thread (and its user stack) is copied, which continues in the child after this system call, returning 0.
+pub fn sys_fork() -> isize {
+    let parent = process_manager().read().current_process();
+    let child = process_manager().write().create_process();
+
+    let user_stack = scheduler().current_thread().user_stack_start();
+    child.virtual_address_space.clone_user_space(&parent.virtual_address_space, user_stack);
+    *child.open_objects.lock() = parent.open_objects.lock().duplicate();
+    child.inherit_signal_handlers(&parent);
+    child.inherit_tls_template(&parent);
+    child.inherit_image(&parent);
+
+    let thread = Thread::new_forked_thread(Arc::clone(&child));
+    scheduler().ready(thread);
+    child.id() as isize
+}
+
+/// Create a user thread in the calling process, starting with `kickoff_addr(entry, arg)`, and return its id. \
+/// `stack_size` is the max. size of its user stack in bytes (rounded up to pages, 0 -> `MAX_USER_STACK_SIZE`).
+/// Returns `EINVAL`, if the stack would be larger than `MAX_USER_STACK_SIZE` or smaller than `MIN_USER_STACK_SIZE`
+/// plus the TLS block of the thread.
+pub fn sys_thread_create(kickoff_addr: u64, entry: fn(), arg: usize, stack_size: usize) -> isize {
+    let process = process_manager().read().current_process();
+    let kickoff_addr = match user_code_address(&process, kickoff_addr) {
+        Ok(kickoff_addr) => kickoff_addr,
+        Err(e) => return e.into(),
+    };
+    let stack_size = match stack_size {
+        0 => MAX_USER_STACK_SIZE,
+        size if size <= MAX_USER_STACK_SIZE => size.next_multiple_of(PAGE_SIZE),
+        _ => return Errno::EINVAL.into(),
+    };
+    let tls_size = process.tls_template().map_or(0, |template| template.size + template.align);
+    if stack_size < MIN_USER_STACK_SIZE + tls_size {
+        return Errno::EINVAL.into();
+    }
+
+    let thread = Thread::new_user_thread(process, kickoff_addr, entry, arg, stack_size);
     let id = thread.id();
 
     scheduler().ready(thread);
@@ -58,14 +188,163 @@ pub fn sys_thread_exit() -> isize {
     0
 }
 
-pub unsafe fn sys_process_execute_binary(name_buffer: *const u8, name_length: usize, args: *const Vec<&str>) -> isize {
-    let app_name = from_utf8(unsafe { slice_from_raw_parts(name_buffer, name_length).as_ref().unwrap() }).unwrap();
-    match initrd().entries().find(|entry| entry.filename().as_str().unwrap() == app_name) {
-        Some(app) => {
-            let thread = Thread::load_application(app.data(), app_name, unsafe { args.as_ref().unwrap() });
-            scheduler().ready(Arc::clone(&thread));
-            thread.id() as isize
+/// Set the priority of the thread `id` (must belong to the calling process) to `priority`
+pub fn sys_set_priority(id: usize, priority: usize) -> isize {
+    if priority > MAX_PRIORITY {
+        return Errno::EINVAL.into();
+    }
+
+    match own_thread(id) {
+        Ok(thread) => {
+            thread.set_priority(priority);
+            0
+        }
+        Err(e) => e.into(),
+    }
+}
+
+/// Return the priority of the thread `id` (must belong to the calling process)
+pub fn sys_get_priority(id: usize) -> isize {
+    match own_thread(id) {
+        Ok(thread) => thread.priority() as isize,
+        Err(e) => e.into(),
+    }
+}
+
+/// Block the calling thread until it is woken up by `sys_futex_wake`, if the word at `address` contains `expected`. \
+/// Returns `Err(EAGAIN)`, if the word contains another value.
+pub fn sys_futex_wait(address: *const u32, expected: u32) -> isize {
+    let process_id = process_manager().read().current_process().id();
+    if let Err(e) = check_futex_address(address) {
+        return e.into();
+    }
+
+    // The word is copied (not accessed directly), since another thread may unmap it at any time
+    match futex::wait(process_id, address as usize, expected, || unsafe { user_access::read_from_user(address) }) {
+        Ok(true) => 0,
+        Ok(false) => Errno::EAGAIN.into(),
+        Err(e) => e.into(),
+    }
+}
+
+/// Wake up to `count` threads blocked on the word at `address` and return their number
+pub fn sys_futex_wake(address: *const u32, count: usize) -> isize {
+    let process_id = process_manager().read().current_process().id();
+    match check_futex_address(address) {
+        Ok(()) => futex::wake(process_id, address as usize, count) as isize,
+        Err(e) => e.into(),
+    }
+}
+
+/// Check if `address` is an aligned user space address of the calling process, which may be used as futex word
+fn check_futex_address(address: *const u32) -> Result<(), Errno> {
+    if address as usize % align_of::<u32>() != 0 {
+        return Err(Errno::EINVAL);
+    }
+    UserSlice::readable(address.cast::<u8>(), size_of::<u32>()).map(|_| ())
+}
+
+/// Find the thread `id` of the calling process (running, ready or sleeping). \
+/// Threads blocked in a wait list (e.g. of a pipe) cannot be found.
+fn own_thread(id: usize) -> Result<Arc<Thread>, Errno> {
+    let current = scheduler().current_thread();
+    if current.id() == id {
+        return Ok(current);
+    }
+
+    let thread = scheduler().thread(id).ok_or(Errno::ESRCH)?;
+    if thread.process().id() != current.process().id() {
+        return Err(Errno::EACCES);
+    }
+    Ok(thread)
+}
+
+/// Directories searched for binaries given by name only, if the environment of the new process has no `PATH` variable
+const DEFAULT_EXEC_SEARCH_PATH: &str = "/initrd";
+
+/// Max. size of the serialized arguments and environment variables passed to a new process
+const MAX_EXEC_ARGS_SIZE: usize = 0x20000;
+
+/// Start the binary `name_buffer` as new (child) process and return its id. \
+/// `exec_args` contains `argc` null-terminated arguments (including the program name as first argument),
+/// followed by null-terminated environment variables (`KEY=VALUE`). \
+/// `handles` refer to the objects of the calling process, used as stdin, stdout and stderr of the new process.
+/// If `handles` is null, the new process inherits the standard handles 0, 1 and 2 of the calling process.
+pub fn sys_process_execute_binary(name_buffer: *const u8, name_length: usize, exec_args: *const u8, exec_args_length: usize, argc: usize, handles: *const [usize; 3]) -> isize {
+    let app_name = match UserSlice::readable(name_buffer, name_length).and_then(|name| name.read_to_string()) {
+        Ok(app_name) => app_name,
+        Err(e) => return e.into(),
+    };
+    if exec_args.is_null() || exec_args_length > MAX_EXEC_ARGS_SIZE {
+        return Errno::EINVAL.into();
+    }
+    let exec_args = match UserSlice::readable(exec_args, exec_args_length).and_then(|exec_args| exec_args.read_to_vec()) {
+        Ok(exec_args) => exec_args,
+        Err(e) => return e.into(),
+    };
+    let (argv, envp) = match parse_exec_args(&exec_args, argc) {
+        Ok(vectors) => vectors,
+        Err(e) => return e.into(),
+    };
+
+    let handles = if handles.is_null() {
+        [STDIN, STDOUT, STDERR]
+    } else {
+        match unsafe { user_access::read_from_user(handles) } {
+            Ok(handles) => handles,
+            Err(e) => return e.into(),
+        }
+    };
+    let open_objects = {
+        let parent = process_manager().read().current_process();
+        let parent_objects = parent.open_objects.lock();
+        match OpenObjectTable::with_handles_from(&parent_objects, &handles) {
+            Ok(open_objects) => open_objects,
+            Err(e) => return e.into(),
+        }
+    };
+
+    let image = match load_executable(&app_name, &envp).and_then(ExecutableImage::load) {
+        Ok(image) => image,
+        Err(e) => return e.into(),
+    };
+
+    let thread = Thread::load_application(image, &app_name, &argv, &envp);
+    *thread.process().open_objects.lock() = open_objects;
+    scheduler().ready(Arc::clone(&thread));
+    thread.process().id() as isize
+}
+
+/// Split the serialized `exec_args` into the first `argc` strings (arguments) and the remaining strings (environment)
+fn parse_exec_args(exec_args: &[u8], argc: usize) -> Result<(Vec<&[u8]>, Vec<&[u8]>), Errno> {
+    let mut strings = match exec_args.split_last() {
+        Some((0, strings)) => strings.split(|byte| *byte == 0).collect::<Vec<&[u8]>>(),
+        None => Vec::new(),
+        Some(_) => return Err(Errno::EINVAL), // last string is not null-terminated
+    };
+    if argc > strings.len() {
+        return Err(Errno::EINVAL);
+    }
+
+    let envp = strings.split_off(argc);
+    Ok((strings, envp))
+}
+
+/// Read the binary `name` via the naming service. \
+/// Names containing a '/' are used as path, all others are searched in the directories listed
+/// in the `PATH` variable of `envp` (separated by ':', `DEFAULT_EXEC_SEARCH_PATH` if not set).
+fn load_executable(name: &str, envp: &[&[u8]]) -> Result<Vec<u8>, Errno> {
+    if name.contains('/') {
+        return naming::api::read_file(name);
+    }
+
+    let search_path = envp.iter()
+        .find_map(|var| var.strip_prefix(b"PATH="))
+        .map_or(Ok(DEFAULT_EXEC_SEARCH_PATH), |path| core::str::from_utf8(path).map_err(|_| Errno::EINVAL))?;
+    for dir in search_path.split(':').filter(|dir| !dir.is_empty()) {
+        if let Ok(elf_buffer) = naming::api::read_file(&format!("{}/{name}", dir.trim_end_matches('/'))) {
+            return Ok(elf_buffer);
         }
-        None => Errno::ENOENT.into(),
     }
+    Err(Errno::ENOENT)
 }
@@ -6,41 +6,72 @@
    ║ Author: Michael Schoettner, 28.12.2024, HHU                             ║
    ╚═════════════════════════════════════════════════════════════════════════╝
 */
-use alloc::slice;
-use alloc::string::{String, ToString};
-use core::ptr::slice_from_raw_parts;
-use core::str::from_utf8;
+use alloc::vec;
 use core::mem;
 use naming::shared_types::{OpenOptions, SeekOrigin, RawDirent};
 use syscall::return_vals::{self, Errno};
 use num_enum::FromPrimitive;
 
+use crate::memory::PAGE_SIZE;
+use crate::memory::user_access::{self, UserSlice};
 use crate::naming::api;
 
-pub unsafe fn sys_open(path: *const u8, flags: OpenOptions) -> isize {
-    return_vals::convert_syscall_result_to_ret_code(api::open(&unsafe { ptr_to_string(path).unwrap() }, flags))
+/// Size of the kernel buffer, through which `sys_read` and `sys_write` copy the user buffer in chunks
+/// (the length of the user buffer must not determine the size of a kernel allocation)
+const BOUNCE_BUFFER_SIZE: usize = PAGE_SIZE;
+
+pub fn sys_open(path: *const u8, flags: OpenOptions) -> isize {
+    return_vals::convert_syscall_result_to_ret_code(user_access::read_user_str(path).and_then(|path| api::open(&path, flags)))
 }
 
-pub unsafe fn sys_read(fh: usize, buffer: *mut u8, buffer_length: usize) -> isize {
+pub fn sys_read(fh: usize, buffer: *mut u8, buffer_length: usize) -> isize {
     if buffer.is_null() || buffer_length == 0 {
         return Errno::EINVAL as isize;
     }
-    let buf: &mut[u8];
-    unsafe {
-        buf = slice::from_raw_parts_mut(buffer, buffer_length);
-    }
-    return_vals::convert_syscall_result_to_ret_code(api::read(fh, buf))
+    let result = UserSlice::writable(buffer, buffer_length).and_then(|user_buffer| {
+        let mut buf = vec![0; BOUNCE_BUFFER_SIZE];
+        let mut total = 0;
+        for chunk in user_buffer.chunks(BOUNCE_BUFFER_SIZE) {
+            let count = match api::read(fh, &mut buf[..chunk.len()]) {
+                Ok(count) => count,
+                Err(error) if total == 0 => return Err(error),
+                Err(_) => break, // Report the bytes read so far
+            };
+            chunk.write(&buf[..count])?;
+            total += count;
+
+            if count < chunk.len() {
+                break; // End of file or no more data available at the moment (e.g. terminal or pipe)
+            }
+        }
+        Ok(total)
+    });
+    return_vals::convert_syscall_result_to_ret_code(result)
 }
 
-pub unsafe fn sys_write(fh: usize, buffer: *const u8, buffer_length: usize) -> isize {
+pub fn sys_write(fh: usize, buffer: *const u8, buffer_length: usize) -> isize {
     if buffer.is_null() || buffer_length == 0 {
         return Errno::EINVAL as isize;
     }
-    let buf: &[u8];
-    unsafe {
-        buf = slice::from_raw_parts(buffer, buffer_length);
-    }
-    return_vals::convert_syscall_result_to_ret_code(api::write(fh, buf))
+    let result = UserSlice::readable(buffer, buffer_length).and_then(|user_buffer| {
+        let mut buf = vec![0; BOUNCE_BUFFER_SIZE];
+        let mut total = 0;
+        for chunk in user_buffer.chunks(BOUNCE_BUFFER_SIZE) {
+            chunk.read(&mut buf)?;
+            let count = match api::write(fh, &buf[..chunk.len()]) {
+                Ok(count) => count,
+                Err(error) if total == 0 => return Err(error),
+                Err(_) => break, // Report the bytes written so far
+            };
+            total += count;
+
+            if count < chunk.len() {
+                break;
+            }
+        }
+        Ok(total)
+    });
+    return_vals::convert_syscall_result_to_ret_code(result)
 }
 
 pub fn sys_seek(fh: usize, offset: usize, origin: usize) -> isize {
@@ -51,56 +82,77 @@ pub fn sys_close(fh: usize) -> isize {
     return_vals::convert_syscall_result_to_ret_code(api::close(fh))
 }
 
-pub unsafe fn sys_mkdir(path: *const u8) -> isize {
-    return_vals::convert_syscall_result_to_ret_code(api::mkdir(&unsafe { ptr_to_string(path).unwrap() }))
+pub fn sys_mkdir(path: *const u8) -> isize {
+    return_vals::convert_syscall_result_to_ret_code(user_access::read_user_str(path).and_then(|path| api::mkdir(&path)))
 }
 
-pub unsafe fn sys_touch(path: *const u8) -> isize {
-    return_vals::convert_syscall_result_to_ret_code(api::touch(&unsafe { ptr_to_string(path).unwrap() }))
+pub fn sys_touch(path: *const u8) -> isize {
+    return_vals::convert_syscall_result_to_ret_code(user_access::read_user_str(path).and_then(|path| api::touch(&path)))
 }
 
-/// Convert a raw pointer resulting from a CString to a UTF-8 String
-unsafe fn ptr_to_string(ptr: *const u8) -> Result<String, Errno> {
-    if ptr.is_null() {
-        return Err(Errno::EBADSTR);
+pub fn sys_readdir(fh: usize, buffer: *mut u8, buffer_length: usize) -> isize {
+    if buffer.is_null() || buffer_length == 0 || buffer_length <  mem::size_of::<RawDirent>() {
+        return Errno::EINVAL as isize;
     }
-
-    let mut len = 0;
-    // Find the null terminator to determine the length
-    unsafe {
-        while *ptr.add(len) != 0 {
-            len += 1;
+    let mut dentry = RawDirent::new();
+    let result = api::readdir(fh, Some(&mut dentry)).and_then(|count| {
+        if count > 0 {
+            user_access::write_to_user(buffer as *mut RawDirent, &dentry)?;
         }
-    }
-
-    let path = from_utf8(unsafe { slice_from_raw_parts(ptr, len).as_ref().unwrap() });
-    match path {
-        Ok(path_str) => Ok(path_str.to_string()),
-        Err(_) => Err(Errno::EBADSTR),
-    }   
+        Ok(count)
+    });
+    return_vals::convert_syscall_result_to_ret_code(result)
 }
 
-pub unsafe fn sys_readdir(fh: usize, buffer: *mut u8, buffer_length: usize) -> isize {
-    if buffer.is_null() || buffer_length == 0 || buffer_length <  mem::size_of::<RawDirent>() {
+
+pub fn sys_cwd(buffer: *mut u8, buffer_length: usize) -> isize {
+    if buffer.is_null() || buffer_length == 0 {
         return Errno::EINVAL as isize;
     }
-    let dentry_ptr = buffer as *mut RawDirent;
-    let dentry = unsafe { dentry_ptr.as_mut() };
-    return_vals::convert_syscall_result_to_ret_code(api::readdir(fh, dentry))
+    let result = UserSlice::writable(buffer, buffer_length).and_then(|user_buffer| {
+        let mut buf = vec![0; user_buffer.len()];
+        let len = api::cwd(&mut buf)?;
+        user_buffer.write(&buf[..len])?;
+        Ok(len)
+    });
+    return_vals::convert_syscall_result_to_ret_code(result)
 }
 
+pub fn sys_cd(path: *const u8) -> isize {
+    return_vals::convert_syscall_result_to_ret_code(user_access::read_user_str(path).and_then(|path| api::cd(&path)))
+}
 
-pub unsafe fn sys_cwd(buffer: *mut u8, buffer_length: usize) -> isize {
-    if buffer.is_null() || buffer_length == 0 {
-        return Errno::EINVAL as isize;
+pub fn sys_mount(source: *const u8, target: *const u8, fs_type: *const u8) -> isize {
+    let source = user_access::read_user_str(source);
+    let target = user_access::read_user_str(target);
+    let fs_type = user_access::read_user_str(fs_type);
+    match (source, target, fs_type) {
+        (Ok(source), Ok(target), Ok(fs_type)) => return_vals::convert_syscall_result_to_ret_code(api::mount(&source, &target, &fs_type)),
+        (Err(e), _, _) | (_, Err(e), _) | (_, _, Err(e)) => e as isize,
     }
-    let buf: &mut[u8];
-    unsafe {
-        buf = slice::from_raw_parts_mut(buffer, buffer_length);
+}
+
+pub fn sys_umount(target: *const u8) -> isize {
+    match user_access::read_user_str(target) {
+        Ok(target) => return_vals::convert_syscall_result_to_ret_code(api::umount(&target)),
+        Err(e) => e as isize,
     }
-    return_vals::convert_syscall_result_to_ret_code(api::cwd(buf))
 }
 
-pub unsafe fn sys_cd(path: *const u8) -> isize {
-    return_vals::convert_syscall_result_to_ret_code(api::cd(&unsafe {ptr_to_string(path)}.unwrap()))
+pub fn sys_pipe(handles: *mut usize) -> isize {
+    if handles.is_null() {
+        return Errno::EINVAL as isize;
+    }
+    match api::pipe() {
+        Ok((read_handle, write_handle)) => {
+            if let Err(e) = user_access::write_to_user(handles as *mut [usize; 2], &[read_handle, write_handle]) {
+                // The application will never know the handles
+                let _ = api::close(read_handle);
+                let _ = api::close(write_handle);
+                return e as isize;
+            }
+            0
+        }
+        Err(e) => e as isize,
+    }
 }
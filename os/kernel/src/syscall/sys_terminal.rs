@@ -6,21 +6,24 @@
    ║ Author: Fabian Ruhland, 30.8.2024, HHU                                  ║
    ╚═════════════════════════════════════════════════════════════════════════╝
 */
-use core::ptr::slice_from_raw_parts;
-use core::str::from_utf8;
+use syscall::return_vals::Errno;
+use crate::memory::user_access::UserSlice;
 use crate::terminal;
 
 pub fn sys_terminal_read() -> isize {
     let terminal = terminal();
     match terminal.read_byte() {
-        -1 => panic!("Input stream closed!"),
+        -1 => Errno::EINTR as isize, // Waiting has been interrupted (by Ctrl-C or because the calling thread has been killed)
         c => c as isize
     }
 }
 
-pub unsafe fn sys_terminal_write(buffer: *const u8, length: usize) -> isize {
-    let string = from_utf8(unsafe { slice_from_raw_parts(buffer, length).as_ref().unwrap() }).unwrap();
+pub fn sys_terminal_write(buffer: *const u8, length: usize) -> isize {
+    let string = match UserSlice::readable(buffer, length).and_then(|buffer| buffer.read_to_string()) {
+        Ok(string) => string,
+        Err(e) => return e as isize,
+    };
     let terminal = terminal();
-    terminal.write_str(string);
+    terminal.write_str(&string);
     0
 }
@@ -11,6 +11,7 @@ use alloc::format;
 use alloc::string::ToString;
 use chrono::{DateTime, Datelike, TimeDelta, Timelike};
 use uefi::runtime::{Time, TimeParams};
+use syscall::return_vals::Errno;
 use crate::{efi_services_available, timer};
 
 
@@ -49,10 +50,17 @@ pub fn sys_get_date() -> isize {
     }
 }
 
+/// Set the date of the EFI runtime clock to `date_ms` milliseconds since the Unix epoch.
+/// Returns `EINVAL`, if the date cannot be represented by the EFI clock.
 pub fn sys_set_date(date_ms: usize) -> isize {
-    let date = DateTime::from_timestamp_millis(date_ms as i64).expect("Failed to parse date from milliseconds");
+    let Some(date) = DateTime::from_timestamp_millis(date_ms as i64) else {
+        return Errno::EINVAL as isize;
+    };
+    let Ok(year) = u16::try_from(date.year()) else {
+        return Errno::EINVAL as isize;
+    };
     let uefi_date = Time::new(TimeParams {
-        year: date.year() as u16,
+        year,
         month: date.month() as u8,
         day: date.day() as u8,
         hour: date.hour() as u8,
@@ -61,7 +69,10 @@ pub fn sys_set_date(date_ms: usize) -> isize {
         nanosecond: date.nanosecond(),
         time_zone: None,
         daylight: Default::default(),
-    }).expect("Failed to create EFI date");
+    });
+    let Ok(uefi_date) = uefi_date else {
+        return Errno::EINVAL as isize;
+    };
 
     match unsafe { uefi::runtime::set_time(&uefi_date) } {
         Ok(_) => true as isize,
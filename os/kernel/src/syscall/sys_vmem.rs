@@ -7,35 +7,189 @@
    ╚═════════════════════════════════════════════════════════════════════════╝
 */
 
+use core::slice;
 use x86_64::VirtAddr;
-use x86_64::structures::paging::Page;
+use x86_64::structures::paging::page::PageRange;
+use x86_64::structures::paging::{Page, PageTableFlags};
 
-use crate::memory::vma::VmaType;
+use crate::memory::frames;
+use crate::memory::user_access;
+use crate::memory::vma::{VirtualMemoryArea, VmaType};
+use crate::memory::vmm::VirtualAddressSpace;
 use crate::memory::{MemorySpace, PAGE_SIZE};
+use crate::naming::api;
 use crate::process_manager;
-use syscall::return_vals::Errno;
+use syscall::meminfo::MemInfo;
+use syscall::mman::{MapFlags, Protection};
+use syscall::return_vals::{self, Errno};
 
 /// Map memory to a process.
 ///
 /// This just sets up the VMA, no page tables are created yet.
 /// This happens later on on page faults.
+/// Returns `EINVAL`, if the range is not page aligned, does not lie in user space or is already in use.
 pub fn sys_map_memory(start: usize, size: usize) -> isize {
     let process = process_manager().read().current_process();
-
-    let start_addr = VirtAddr::new(start.try_into().unwrap());
-    let start_page = Page::containing_address(start_addr);
-    let num_pages = size.div_ceil(PAGE_SIZE);
+    let pages = match user_page_range(start, size) {
+        Ok(pages) => pages,
+        Err(errno) => return errno as isize,
+    };
 
     let vma = process.virtual_address_space.alloc_vma(
-        Some(start_page),
-        num_pages as u64,
+        Some(pages.start),
+        pages.len(),
         MemorySpace::User,
         VmaType::Heap,
         "heap",
     );
     if vma.is_none() {
-        Errno::EUNKN as isize
+        Errno::EINVAL as isize
     } else {
         0
     }
 }
+
+/// Map `length` bytes into the address space of the calling process and return the start address. \
+/// The memory is zero filled (`MapFlags::ANONYMOUS`) or a private copy of the file `handle` from `offset` (page aligned).
+/// Anonymous memory is mapped on demand, whereas files are read completely by this system call. \
+/// `address` is a hint for the placement of the mapping (0 -> chosen by the kernel),
+/// unless `MapFlags::FIXED` is given (then the range must be free, otherwise `EEXIST` is returned).
+/// Memory cannot be mapped writable and executable at the same time (`EACCES`).
+pub fn sys_mmap(address: usize, length: usize, prot: usize, flags: usize, handle: usize, offset: usize) -> isize {
+    let (Some(prot), Some(flags)) = (Protection::from_bits(prot), MapFlags::from_bits(flags)) else {
+        return Errno::EINVAL as isize;
+    };
+    if length == 0 || offset % PAGE_SIZE != 0 || (flags.contains(MapFlags::FIXED) && address % PAGE_SIZE != 0) {
+        return Errno::EINVAL as isize;
+    }
+    let hint = match (address, VirtAddr::try_new(address as u64)) {
+        (0, _) => None,
+        (_, Ok(addr)) => Some(Page::containing_address(addr)),
+        (_, Err(_)) => return Errno::EINVAL as isize,
+    };
+    if flags.contains(MapFlags::FIXED) && hint.is_none() {
+        return Errno::EINVAL as isize;
+    }
+
+    let (typ, tag) = if flags.contains(MapFlags::ANONYMOUS) { (VmaType::Anonymous, "anon") } else { (VmaType::File, "file") };
+    let page_flags = match protection_flags(prot) {
+        Ok(page_flags) => page_flags,
+        Err(errno) => return errno as isize,
+    };
+    let num_pages = length.div_ceil(PAGE_SIZE) as u64;
+
+    let process = process_manager().read().current_process();
+    let address_space = &process.virtual_address_space;
+    let vma = hint
+        .and_then(|page| address_space.alloc_vma_with_flags(Some(page), num_pages, MemorySpace::User, typ, tag, page_flags))
+        .or_else(|| {
+            if flags.contains(MapFlags::FIXED) {
+                return None;
+            }
+            address_space.alloc_vma_with_flags(None, num_pages, MemorySpace::User, typ, tag, page_flags)
+        });
+    let Some(vma) = vma else {
+        return if flags.contains(MapFlags::FIXED) { Errno::EEXIST as isize } else { Errno::ENOMEM as isize };
+    };
+
+    if typ == VmaType::File {
+        if let Err(errno) = map_file(address_space, &vma, handle, offset) {
+            address_space.unmap_pages(vma.range()).expect("Failed to remove file mapping");
+            return errno as isize;
+        }
+    }
+    vma.start().as_u64() as isize
+}
+
+/// Unmap the memory range of `length` bytes from `address` (page aligned), splitting mappings if necessary. \
+/// Pages in the range, which are not mapped, are ignored.
+pub fn sys_munmap(address: usize, length: usize) -> isize {
+    let process = process_manager().read().current_process();
+    match user_page_range(address, length) {
+        Ok(pages) => return_vals::convert_syscall_result_to_ret_code(process.virtual_address_space.unmap_pages(pages).map(|_| 0)),
+        Err(errno) => errno as isize,
+    }
+}
+
+/// Change the access rights of the memory range of `length` bytes from `address` (page aligned) to `prot`. \
+/// The whole range must be mapped (otherwise `ENOMEM` is returned) and must not become writable and executable (`EACCES`).
+pub fn sys_mprotect(address: usize, length: usize, prot: usize) -> isize {
+    let Some(prot) = Protection::from_bits(prot) else {
+        return Errno::EINVAL as isize;
+    };
+
+    let process = process_manager().read().current_process();
+    let result = user_page_range(address, length)
+        .and_then(|pages| process.virtual_address_space.protect(pages, protection_flags(prot)?))
+        .map(|_| 0);
+    return_vals::convert_syscall_result_to_ret_code(result)
+}
+
+/// Write the statistics of the page frame allocator (total, free and largest free block per zone) to `info`.
+pub fn sys_mem_info(info: *mut MemInfo) -> isize {
+    let mem_info = frames::mem_info();
+    return_vals::convert_syscall_result_to_ret_code(user_access::write_to_user(info, &mem_info).map(|_| 0))
+}
+
+/// Helper function returning the page table flags for user pages with the access rights `prot`. \
+/// Memory must not be writable and executable at the same time (W^X) -> `Err(EACCES)`.
+fn protection_flags(prot: Protection) -> Result<PageTableFlags, Errno> {
+    if prot.contains(Protection::WRITE | Protection::EXEC) {
+        return Err(Errno::EACCES);
+    }
+
+    let mut flags = PageTableFlags::USER_ACCESSIBLE;
+    if !prot.is_empty() {
+        flags |= PageTableFlags::PRESENT;
+    }
+    if prot.contains(Protection::WRITE) {
+        flags |= PageTableFlags::WRITABLE;
+    }
+    if !prot.contains(Protection::EXEC) {
+        flags |= PageTableFlags::NO_EXECUTE;
+    }
+    Ok(flags)
+}
+
+/// Helper function returning the pages for the memory range of `length` bytes from the page aligned `address`
+fn user_page_range(address: usize, length: usize) -> Result<PageRange, Errno> {
+    if length == 0 || address % PAGE_SIZE != 0 {
+        return Err(Errno::EINVAL);
+    }
+    let end = address.checked_add(length.div_ceil(PAGE_SIZE) * PAGE_SIZE).ok_or(Errno::EINVAL)?;
+    let start = VirtAddr::try_new(address as u64).map_err(|_| Errno::EINVAL)?;
+    let end = VirtAddr::try_new(end as u64).map_err(|_| Errno::EINVAL)?;
+
+    Ok(PageRange { start: Page::containing_address(start), end: Page::containing_address(end) })
+}
+
+/// Helper function allocating frames for the file mapping `vma` page by page, filling them with the content of the file
+/// `handle` (from `offset`, the rest is zeroed) and mapping them with the flags of `vma`. \
+/// Returns `Err(ENOMEM)`, if there are not enough free frames. The caller removes `vma` (and the frames mapped so far)
+/// on errors.
+fn map_file(address_space: &VirtualAddressSpace, vma: &VirtualMemoryArea, handle: usize, offset: usize) -> Result<(), Errno> {
+    let mut end_of_file = false;
+    for (index, page) in vma.range().enumerate() {
+        let frame_range = frames::try_alloc(1).ok_or(Errno::ENOMEM)?;
+        let buffer = unsafe { slice::from_raw_parts_mut(frame_range.start.start_address().as_u64() as *mut u8, PAGE_SIZE) };
+        buffer.fill(0);
+
+        // The frame is mapped before it is filled, so that it is freed with the mapping on errors
+        address_space.map_pfr_for_partial_vma(vma, frame_range, PageRange { start: page, end: page + 1 }, vma.flags)
+            .inspect_err(|_| unsafe { frames::free(frame_range) })?;
+
+        let mut count = 0;
+        while !end_of_file && count < buffer.len() {
+            match api::read_at(handle, &mut buffer[count..], offset.saturating_add(index * PAGE_SIZE + count))? {
+                0 => end_of_file = true,
+                bytes_read => count += bytes_read,
+            }
+        }
+    }
+
+    if !vma.flags.contains(PageTableFlags::PRESENT) {
+        // `map_pfr_for_partial_vma` always creates present mappings
+        address_space.protect(vma.range(), vma.flags)?;
+    }
+    Ok(())
+}
@@ -12,17 +12,19 @@ use core::ops::Deref;
 use core::ptr;
 use syscall::NUM_SYSCALLS;
 use x86_64::registers::control::{Efer, EferFlags};
-use x86_64::registers::model_specific::{KernelGsBase, LStar, Star};
+use x86_64::registers::model_specific::{LStar, Star};
 use x86_64::structures::gdt::SegmentSelector;
 use x86_64::{PrivilegeLevel, VirtAddr};
-use crate::syscall::sys_vmem::sys_map_memory;
+use crate::syscall::sys_vmem::{sys_map_memory, sys_mmap, sys_munmap, sys_mprotect, sys_mem_info};
 use crate::syscall::sys_time::{sys_get_date, sys_get_system_time, sys_set_date, };
 use crate::syscall::sys_concurrent::{sys_process_execute_binary, sys_process_exit, sys_process_id, sys_thread_create, sys_thread_exit,
-    sys_thread_id, sys_thread_join, sys_thread_sleep, sys_thread_switch};
+    sys_thread_id, sys_thread_join, sys_thread_sleep, sys_thread_switch, sys_wait_pid, sys_kill, sys_signal_handler, sys_fork,
+    sys_set_priority, sys_get_priority, sys_futex_wait, sys_futex_wake};
 use crate::syscall::sys_terminal::{sys_terminal_read, sys_terminal_write};
 use crate::syscall::sys_naming::*;
 
-use crate::{core_local_storage, tss};
+use crate::process::signal;
+use crate::{core_local_storage, scheduler, tss};
 
 pub const CORE_LOCAL_STORAGE_TSS_RSP0_PTR_INDEX: u64 = 0x00;
 pub const CORE_LOCAL_STORAGE_USER_RSP_INDEX: u64 = 0x08;
@@ -31,6 +33,7 @@ pub const CORE_LOCAL_STORAGE_USER_RSP_INDEX: u64 = 0x08;
 pub struct CoreLocalStorage {
     tss_rsp0_ptr: VirtAddr,
     user_rsp: VirtAddr,
+    core_id: usize,
 }
 
 impl CoreLocalStorage {
@@ -38,8 +41,39 @@ impl CoreLocalStorage {
         Self {
             tss_rsp0_ptr: VirtAddr::zero(),
             user_rsp: VirtAddr::zero(),
+            core_id: 0,
         }
     }
+
+    pub fn core_id(&self) -> usize {
+        self.core_id
+    }
+
+    pub fn set_core_id(&mut self, core_id: usize) {
+        self.core_id = core_id;
+    }
+}
+
+/// Registers of a user thread, saved on its kernel stack by `syscall_handler` (lowest address first). \
+/// The frame lies at the top of the kernel stack, while the thread executes a system call.
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub struct SyscallFrame {
+    pub rbp: u64,
+    pub r15: u64,
+    pub r14: u64,
+    pub r13: u64,
+    pub r12: u64,
+    pub r11: u64, // rflags for returning to ring 3
+    pub r10: u64,
+    pub r9: u64,
+    pub r8: u64,
+    pub rsi: u64,
+    pub rdi: u64,
+    pub rdx: u64,
+    pub rcx: u64, // rip for returning to ring 3
+    pub rbx: u64,
+    pub rsp: u64, // user stack pointer
 }
 
 pub fn init() {
@@ -62,13 +96,10 @@ pub fn init() {
     // Set rip for syscall
     LStar::write(VirtAddr::new(syscall_handler as u64));
 
-    // Initialize core local storage (accessible via 'swapgs')
+    // Initialize core local storage of the calling core (accessible via 'swapgs', see 'init_core_local_storage()')
     let mut core_local_storage = core_local_storage().lock();
     core_local_storage.tss_rsp0_ptr =
         VirtAddr::new(ptr::from_ref(tss().lock().deref()) as u64 + size_of::<u32>() as u64);
-    KernelGsBase::write(VirtAddr::new(
-        ptr::from_ref(core_local_storage.deref()) as u64
-    ));
 }
 
 #[unsafe(no_mangle)]
@@ -107,7 +138,22 @@ impl SyscallTable {
                 sys_touch as *const _,
                 sys_readdir as *const _,
                 sys_cwd as *const _,
-                sys_cd as *const _,                
+                sys_cd as *const _,
+                sys_mount as *const _,
+                sys_umount as *const _,
+                sys_pipe as *const _,
+                sys_wait_pid as *const _,
+                sys_kill as *const _,
+                sys_signal_handler as *const _,
+                sys_fork as *const _,
+                sys_set_priority as *const _,
+                sys_get_priority as *const _,
+                sys_futex_wait as *const _,
+                sys_futex_wake as *const _,
+                sys_mmap as *const _,
+                sys_munmap as *const _,
+                sys_mprotect as *const _,
+                sys_mem_info as *const _,
             ],
         }
     }
@@ -154,6 +200,7 @@ unsafe extern "C" fn syscall_handler() {
     "push r13",
     "push r14",
     "push r15",
+    "push rbp", // Needed to start a forked thread with the same registers (see `SyscallFrame`)
 
     // copy 4th argument to rcx to adhere x86_64 ABI
     "mov rcx, r10",
@@ -169,6 +216,7 @@ unsafe extern "C" fn syscall_handler() {
     "call syscall_disp",
 
     // Restore registers
+    "pop rbp",
     "pop r15",
     "pop r14",
     "pop r13",
@@ -201,11 +249,27 @@ unsafe extern "C" fn syscall_handler() {
 unsafe extern "C" fn syscall_disp() {
     naked_asm!(
     "call [{SYSCALL_TABLE} + 8 * rax]",
+
+    // Safe point before returning to user mode (preserving the return values in rax and rdx)
+    "push rax",
+    "push rdx",
+    "call return_to_user",
+    "pop rdx",
+    "pop rax",
     "ret",
     SYSCALL_TABLE = sym SYSCALL_TABLE
     );
 }
 
+/// Called before a thread returns to user mode (at the end of a system call and of an interrupt in user mode).
+/// The thread does not hold any kernel locks here, so it sends SIGINT, if Ctrl-C has been pressed (see `signal`),
+/// and exits, if it has been killed (see `Scheduler::kill`).
+#[unsafe(no_mangle)]
+pub extern "C" fn return_to_user() {
+    signal::deliver_interrupt();
+    scheduler().exit_if_killed();
+}
+
 #[unsafe(no_mangle)]
 unsafe extern "C" fn syscall_abort() {
     let syscall_number: u64;
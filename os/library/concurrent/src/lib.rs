@@ -1,6 +1,10 @@
 #![no_std]
+#![feature(allow_internal_unstable)]
+#![allow(internal_features)]
 
 extern crate alloc;
 
 pub mod process;
-pub mod thread;
\ No newline at end of file
+pub mod signal;
+pub mod sync;
+pub mod thread;
@@ -6,20 +6,39 @@
    ║ Author: Fabian Ruhland, Michael Schoettner, 31.8.2024, HHU              ║
    ╚═════════════════════════════════════════════════════════════════════════╝
 */
+use core::ptr;
+use syscall::return_vals::Errno;
 use syscall::{syscall, SystemCall};
+use crate::signal::{self, Signal};
 
 pub struct Process {
     id: usize,
 }
 
 impl Process {
-    const fn new(id: usize) -> Self {
+    pub(crate) const fn new(id: usize) -> Self {
         Self { id }
     }
 
     pub fn id(&self) -> usize {
         self.id
     }
+
+    /// Wait until the (child) process has exited and return its exit status
+    pub fn wait(&self) -> Result<isize, Errno> {
+        wait_pid(self.id)
+    }
+
+    /// Send `signal` to the process
+    pub fn kill(&self, signal: Signal) -> Result<(), Errno> {
+        signal::kill(self.id, signal)
+    }
+}
+
+/// Result of `fork()`, seen by the calling process and by the forked child
+pub enum ForkResult {
+    Parent(Process),
+    Child,
 }
 
 pub fn current() -> Option<Process> {
@@ -30,6 +49,24 @@ pub fn current() -> Option<Process> {
     }    
 }
 
-pub fn exit() {
-    syscall(SystemCall::ProcessExit, &[]).expect("Failed to exit process");
+pub fn exit(status: isize) -> ! {
+    let _ = syscall(SystemCall::ProcessExit, &[status as usize]);
+    panic!("System call 'ProcessExit' has returned!")
+}
+
+/// Wait until the child process `pid` has exited and return its exit status
+pub fn wait_pid(pid: usize) -> Result<isize, Errno> {
+    let mut status: isize = 0;
+    syscall(SystemCall::WaitPid, &[pid, ptr::from_mut(&mut status) as usize])?;
+    Ok(status)
+}
+
+/// Create a child process as copy of the calling process (memory is copied lazily on write). \
+/// Returns `ForkResult::Parent` with the child in the calling process and `ForkResult::Child` in the child. \
+/// Only the calling thread is copied. Locks held by other threads at the time of the fork remain locked in the child.
+pub fn fork() -> Result<ForkResult, Errno> {
+    match syscall(SystemCall::Fork, &[])? {
+        0 => Ok(ForkResult::Child),
+        id => Ok(ForkResult::Parent(Process::new(id))),
+    }
 }
@@ -0,0 +1,34 @@
+/* ╔═════════════════════════════════════════════════════════════════════════╗
+   ║ Module: signal                                                          ║
+   ╟─────────────────────────────────────────────────────────────────────────╢
+   ║ Descr.: Syscalls for sending and handling signals. A handler is run in  ║
+   ║         a new thread of the process, when the signal is received.       ║
+   ╟─────────────────────────────────────────────────────────────────────────╢
+   ║ Author: agent, 18.10.2026                                               ║
+   ╚═════════════════════════════════════════════════════════════════════════╝
+*/
+use syscall::return_vals::Errno;
+use syscall::{syscall, SystemCall};
+use crate::thread::kickoff_user_thread;
+
+pub use syscall::signal::Signal;
+
+/// Send `signal` to the process `pid` (the calling process or one of its descendants, otherwise `EACCES`)
+pub fn kill(pid: usize, signal: Signal) -> Result<(), Errno> {
+    syscall(SystemCall::Kill, &[pid, usize::from(signal)])?;
+    Ok(())
+}
+
+/// Run `handler` in a new thread, whenever the calling process receives `signal` (`SIGKILL` cannot be handled)
+pub fn set_handler(signal: Signal, handler: fn()) -> Result<(), Errno> {
+    syscall(SystemCall::SignalHandler, &[usize::from(signal),
+        kickoff_user_thread as usize,
+        handler as usize,])?;
+    Ok(())
+}
+
+/// Restore the default action for `signal` (terminating the process)
+pub fn reset_handler(signal: Signal) -> Result<(), Errno> {
+    syscall(SystemCall::SignalHandler, &[usize::from(signal), 0, 0])?;
+    Ok(())
+}
@@ -0,0 +1,60 @@
+/* ╔═════════════════════════════════════════════════════════════════════════╗
+   ║ Module: barrier                                                         ║
+   ╟─────────────────────────────────────────────────────────────────────────╢
+   ║ Descr.: Barrier blocking threads until a given number of threads has    ║
+   ║         reached it. The barrier can be reused afterward.                ║
+   ╟─────────────────────────────────────────────────────────────────────────╢
+   ║ Author: agent, 18.10.2026                                               ║
+   ╚═════════════════════════════════════════════════════════════════════════╝
+*/
+use super::condvar::Condvar;
+use super::mutex::Mutex;
+
+struct BarrierState {
+    count: usize,      // threads waiting in the current generation
+    generation: usize, // incremented, each time all threads have reached the barrier
+}
+
+pub struct Barrier {
+    state: Mutex<BarrierState>,
+    all_arrived: Condvar,
+    threads: usize,
+}
+
+/// Returned by `Barrier::wait`, exactly one thread of each generation is the leader
+pub struct BarrierWaitResult(bool);
+
+impl BarrierWaitResult {
+    pub fn is_leader(&self) -> bool {
+        self.0
+    }
+}
+
+impl Barrier {
+    /// Create a barrier for `threads` threads
+    pub const fn new(threads: usize) -> Self {
+        Self {
+            state: Mutex::new(BarrierState { count: 0, generation: 0 }),
+            all_arrived: Condvar::new(),
+            threads,
+        }
+    }
+
+    /// Block the calling thread until `threads` threads have called `wait`. \
+    /// The last arriving thread is the leader and wakes up all others.
+    pub fn wait(&self) -> BarrierWaitResult {
+        let mut state = self.state.lock();
+        state.count += 1;
+
+        if state.count < self.threads {
+            let generation = state.generation;
+            let _state = self.all_arrived.wait_while(state, |state| state.generation == generation);
+            return BarrierWaitResult(false);
+        }
+
+        state.count = 0;
+        state.generation = state.generation.wrapping_add(1);
+        self.all_arrived.notify_all();
+        BarrierWaitResult(true)
+    }
+}
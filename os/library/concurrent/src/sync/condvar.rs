@@ -0,0 +1,64 @@
+/* ╔═════════════════════════════════════════════════════════════════════════╗
+   ║ Module: condvar                                                         ║
+   ╟─────────────────────────────────────────────────────────────────────────╢
+   ║ Descr.: Futex based condition variable, used together with 'Mutex'.    ║
+   ║         Every notification increments a sequence number, so a thread   ║
+   ║         does not block, if it has been notified after unlocking the     ║
+   ║         mutex. Waiting threads may wake up spuriously, so the           ║
+   ║         condition has to be checked in a loop (see 'wait_while').       ║
+   ╟─────────────────────────────────────────────────────────────────────────╢
+   ║ Author: agent, 18.10.2026                                               ║
+   ╚═════════════════════════════════════════════════════════════════════════╝
+*/
+use core::sync::atomic::AtomicU32;
+use core::sync::atomic::Ordering::Relaxed;
+use super::futex;
+use super::mutex::MutexGuard;
+
+pub struct Condvar {
+    sequence: AtomicU32,
+}
+
+impl Condvar {
+    pub const fn new() -> Self {
+        Self { sequence: AtomicU32::new(0) }
+    }
+
+    /// Unlock the mutex of `guard` and block the calling thread until it is notified. \
+    /// The mutex is locked again, before this function returns.
+    pub fn wait<'a, T: ?Sized>(&self, guard: MutexGuard<'a, T>) -> MutexGuard<'a, T> {
+        let sequence = self.sequence.load(Relaxed);
+        let mutex = guard.mutex();
+        drop(guard);
+
+        futex::wait(&self.sequence, sequence);
+        mutex.lock()
+    }
+
+    /// Block the calling thread (see `wait`), as long as `condition` returns true for the data protected by the mutex
+    pub fn wait_while<'a, T: ?Sized>(&self, mut guard: MutexGuard<'a, T>, mut condition: impl FnMut(&mut T) -> bool) -> MutexGuard<'a, T> {
+        while condition(&mut *guard) {
+            guard = self.wait(guard);
+        }
+
+        guard
+    }
+
+    /// Wake up one waiting thread
+    pub fn notify_one(&self) {
+        self.sequence.fetch_add(1, Relaxed);
+        futex::wake_one(&self.sequence);
+    }
+
+    /// Wake up all waiting threads
+    pub fn notify_all(&self) {
+        self.sequence.fetch_add(1, Relaxed);
+        futex::wake_all(&self.sequence);
+    }
+}
+
+impl Default for Condvar {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,35 @@
+/* ╔═════════════════════════════════════════════════════════════════════════╗
+   ║ Module: futex                                                           ║
+   ╟─────────────────────────────────────────────────────────────────────────╢
+   ║ Descr.: Syscalls for blocking on a 32 bit word and for waking up        ║
+   ║         threads blocked on it ('FutexWait' and 'FutexWake').            ║
+   ║         Futexes are private to a process.                               ║
+   ╟─────────────────────────────────────────────────────────────────────────╢
+   ║ Author: agent, 18.10.2026                                               ║
+   ╚═════════════════════════════════════════════════════════════════════════╝
+*/
+use core::ptr;
+use core::sync::atomic::AtomicU32;
+use syscall::{syscall, SystemCall};
+
+/// Block the calling thread, if `word` contains `expected`, until another thread calls `wake` for `word`. \
+/// Returns immediately, if `word` contains another value. As with all futexes, the thread may also wake up
+/// without a call to `wake`, so the caller has to check its condition again.
+pub fn wait(word: &AtomicU32, expected: u32) {
+    let _ = syscall(SystemCall::FutexWait, &[ptr::from_ref(word) as usize, expected as usize]);
+}
+
+/// Wake up to `count` threads blocked on `word` and return their number
+pub fn wake(word: &AtomicU32, count: usize) -> usize {
+    syscall(SystemCall::FutexWake, &[ptr::from_ref(word) as usize, count]).unwrap_or(0)
+}
+
+/// Wake up one thread blocked on `word`
+pub fn wake_one(word: &AtomicU32) -> bool {
+    wake(word, 1) > 0
+}
+
+/// Wake up all threads blocked on `word`
+pub fn wake_all(word: &AtomicU32) -> usize {
+    wake(word, usize::MAX)
+}
@@ -0,0 +1,30 @@
+/* ╔═════════════════════════════════════════════════════════════════════════╗
+   ║ Module: sync                                                            ║
+   ╟─────────────────────────────────────────────────────────────────────────╢
+   ║ Descr.: Synchronization primitives for the threads of a process, with  ║
+   ║         APIs similar to 'std::sync'. Uncontended operations only use   ║
+   ║         atomics, waiting threads are blocked in the kernel by futexes  ║
+   ║         (see 'futex'). Locks are not poisoned, if a thread panics       ║
+   ║         (a panic terminates the whole process anyway).                  ║
+   ║                                                                         ║
+   ║           - Mutex, RwLock, Condvar, Barrier, Once                       ║
+   ║           - mpmc::sync_channel  bounded multi-producer multi-consumer   ║
+   ║                                 channel                                 ║
+   ╟─────────────────────────────────────────────────────────────────────────╢
+   ║ Author: agent, 18.10.2026                                               ║
+   ╚═════════════════════════════════════════════════════════════════════════╝
+*/
+pub mod futex;
+pub mod mpmc;
+
+mod barrier;
+mod condvar;
+mod mutex;
+mod once;
+mod rwlock;
+
+pub use barrier::{Barrier, BarrierWaitResult};
+pub use condvar::Condvar;
+pub use mutex::{Mutex, MutexGuard};
+pub use once::Once;
+pub use rwlock::{RwLock, RwLockReadGuard, RwLockWriteGuard};
@@ -0,0 +1,197 @@
+/* ╔═════════════════════════════════════════════════════════════════════════╗
+   ║ Module: mpmc                                                            ║
+   ╟─────────────────────────────────────────────────────────────────────────╢
+   ║ Descr.: Bounded multi-producer multi-consumer channel. Senders block    ║
+   ║         while the channel is full, receivers while it is empty. Both    ║
+   ║         ends can be cloned. The channel is disconnected, when all       ║
+   ║         senders or all receivers have been dropped.                     ║
+   ╟─────────────────────────────────────────────────────────────────────────╢
+   ║ Author: agent, 18.10.2026                                               ║
+   ╚═════════════════════════════════════════════════════════════════════════╝
+*/
+use alloc::collections::VecDeque;
+use alloc::sync::Arc;
+use core::fmt;
+use super::condvar::Condvar;
+use super::mutex::Mutex;
+
+/// Error returned by `Sender::send`, if all receivers have been dropped (contains the message)
+#[derive(PartialEq, Eq, Clone, Copy)]
+pub struct SendError<T>(pub T);
+
+/// Error returned by `Receiver::recv`, if the channel is empty and all senders have been dropped
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct RecvError;
+
+#[derive(PartialEq, Eq, Clone, Copy)]
+pub enum TrySendError<T> {
+    Full(T),
+    Disconnected(T),
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum TryRecvError {
+    Empty,
+    Disconnected,
+}
+
+impl<T> fmt::Debug for SendError<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("SendError { .. }")
+    }
+}
+
+impl<T> fmt::Debug for TrySendError<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TrySendError::Full(_) => f.write_str("Full(..)"),
+            TrySendError::Disconnected(_) => f.write_str("Disconnected(..)"),
+        }
+    }
+}
+
+struct State<T> {
+    queue: VecDeque<T>,
+    senders: usize,
+    receivers: usize,
+}
+
+struct Channel<T> {
+    state: Mutex<State<T>>,
+    capacity: usize,
+    not_empty: Condvar,
+    not_full: Condvar,
+}
+
+pub struct Sender<T> {
+    channel: Arc<Channel<T>>,
+}
+
+pub struct Receiver<T> {
+    channel: Arc<Channel<T>>,
+}
+
+/// Create a channel, which can hold up to `capacity` messages (must be at least 1)
+pub fn sync_channel<T>(capacity: usize) -> (Sender<T>, Receiver<T>) {
+    assert!(capacity > 0, "Capacity of a channel must be at least 1!");
+
+    let channel = Arc::new(Channel {
+        state: Mutex::new(State { queue: VecDeque::with_capacity(capacity), senders: 1, receivers: 1 }),
+        capacity,
+        not_empty: Condvar::new(),
+        not_full: Condvar::new(),
+    });
+
+    (Sender { channel: Arc::clone(&channel) }, Receiver { channel })
+}
+
+impl<T> Sender<T> {
+    /// Send `message`, blocking the calling thread while the channel is full. \
+    /// Returns the message as error, if all receivers have been dropped.
+    pub fn send(&self, message: T) -> Result<(), SendError<T>> {
+        let channel = &self.channel;
+        let mut state = channel.not_full.wait_while(channel.state.lock(), |state| {
+            state.receivers > 0 && state.queue.len() >= channel.capacity
+        });
+
+        if state.receivers == 0 {
+            return Err(SendError(message));
+        }
+
+        state.queue.push_back(message);
+        drop(state);
+        channel.not_empty.notify_one();
+        Ok(())
+    }
+
+    /// Send `message`, if the channel is not full
+    pub fn try_send(&self, message: T) -> Result<(), TrySendError<T>> {
+        let mut state = self.channel.state.lock();
+        if state.receivers == 0 {
+            return Err(TrySendError::Disconnected(message));
+        }
+        if state.queue.len() >= self.channel.capacity {
+            return Err(TrySendError::Full(message));
+        }
+
+        state.queue.push_back(message);
+        drop(state);
+        self.channel.not_empty.notify_one();
+        Ok(())
+    }
+}
+
+impl<T> Receiver<T> {
+    /// Receive a message, blocking the calling thread while the channel is empty. \
+    /// Returns an error, if the channel is empty and all senders have been dropped.
+    pub fn recv(&self) -> Result<T, RecvError> {
+        let channel = &self.channel;
+        let mut state = channel.not_empty.wait_while(channel.state.lock(), |state| {
+            state.senders > 0 && state.queue.is_empty()
+        });
+
+        let message = state.queue.pop_front().ok_or(RecvError)?;
+        drop(state);
+        channel.not_full.notify_one();
+        Ok(message)
+    }
+
+    /// Receive a message, if the channel is not empty
+    pub fn try_recv(&self) -> Result<T, TryRecvError> {
+        let mut state = self.channel.state.lock();
+        let Some(message) = state.queue.pop_front() else {
+            return Err(if state.senders == 0 { TryRecvError::Disconnected } else { TryRecvError::Empty });
+        };
+
+        drop(state);
+        self.channel.not_full.notify_one();
+        Ok(message)
+    }
+
+    /// Iterate over received messages, until the channel is disconnected
+    pub fn iter(&self) -> impl Iterator<Item = T> + '_ {
+        core::iter::from_fn(|| self.recv().ok())
+    }
+}
+
+impl<T> Clone for Sender<T> {
+    fn clone(&self) -> Self {
+        self.channel.state.lock().senders += 1;
+        Self { channel: Arc::clone(&self.channel) }
+    }
+}
+
+impl<T> Clone for Receiver<T> {
+    fn clone(&self) -> Self {
+        self.channel.state.lock().receivers += 1;
+        Self { channel: Arc::clone(&self.channel) }
+    }
+}
+
+impl<T> Drop for Sender<T> {
+    fn drop(&mut self) {
+        let mut state = self.channel.state.lock();
+        state.senders -= 1;
+        let disconnected = state.senders == 0;
+        drop(state);
+
+        // Blocked receivers must notice, that no more messages will arrive
+        if disconnected {
+            self.channel.not_empty.notify_all();
+        }
+    }
+}
+
+impl<T> Drop for Receiver<T> {
+    fn drop(&mut self) {
+        let mut state = self.channel.state.lock();
+        state.receivers -= 1;
+        let disconnected = state.receivers == 0;
+        drop(state);
+
+        // Blocked senders must notice, that nobody will receive their messages
+        if disconnected {
+            self.channel.not_full.notify_all();
+        }
+    }
+}
@@ -0,0 +1,56 @@
+/* ╔═════════════════════════════════════════════════════════════════════════╗
+   ║ Module: once                                                            ║
+   ╟─────────────────────────────────────────────────────────────────────────╢
+   ║ Descr.: One-time initialization. Threads calling 'call_once' while the  ║
+   ║         initialization is running are blocked until it has finished.    ║
+   ╟─────────────────────────────────────────────────────────────────────────╢
+   ║ Author: agent, 18.10.2026                                               ║
+   ╚═════════════════════════════════════════════════════════════════════════╝
+*/
+use core::sync::atomic::AtomicU32;
+use core::sync::atomic::Ordering::{Acquire, Relaxed, Release};
+use super::futex;
+
+const INCOMPLETE: u32 = 0;
+const RUNNING: u32 = 1;
+const COMPLETE: u32 = 2;
+
+pub struct Once {
+    state: AtomicU32,
+}
+
+impl Once {
+    pub const fn new() -> Self {
+        Self { state: AtomicU32::new(INCOMPLETE) }
+    }
+
+    /// Execute `f`, if this is the first call of `call_once`. \
+    /// Otherwise, wait until the first call has finished (`f` is not executed).
+    pub fn call_once(&self, f: impl FnOnce()) {
+        if self.is_completed() {
+            return;
+        }
+
+        if self.state.compare_exchange(INCOMPLETE, RUNNING, Acquire, Relaxed).is_ok() {
+            f();
+            self.state.store(COMPLETE, Release);
+            futex::wake_all(&self.state);
+            return;
+        }
+
+        while self.state.load(Acquire) == RUNNING {
+            futex::wait(&self.state, RUNNING);
+        }
+    }
+
+    /// Check if `call_once` has finished executing its function
+    pub fn is_completed(&self) -> bool {
+        self.state.load(Acquire) == COMPLETE
+    }
+}
+
+impl Default for Once {
+    fn default() -> Self {
+        Self::new()
+    }
+}
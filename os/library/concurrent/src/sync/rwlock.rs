@@ -0,0 +1,168 @@
+/* ╔═════════════════════════════════════════════════════════════════════════╗
+   ║ Module: rwlock                                                          ║
+   ╟─────────────────────────────────────────────────────────────────────────╢
+   ║ Descr.: Futex based reader-writer lock. The state is the number of      ║
+   ║         readers or 'WRITE_LOCKED'. Waiting threads are counted, so      ║
+   ║         unlocking only enters the kernel, if a thread is waiting.       ║
+   ║         Writers are not preferred, so they may wait as long as there    ║
+   ║         are readers.                                                    ║
+   ╟─────────────────────────────────────────────────────────────────────────╢
+   ║ Author: agent, 18.10.2026                                               ║
+   ╚═════════════════════════════════════════════════════════════════════════╝
+*/
+use core::cell::UnsafeCell;
+use core::fmt;
+use core::ops::{Deref, DerefMut};
+use core::sync::atomic::AtomicU32;
+use core::sync::atomic::Ordering::{Acquire, Relaxed, SeqCst};
+use super::futex;
+
+const WRITE_LOCKED: u32 = u32::MAX;
+const MAX_READERS: u32 = u32::MAX - 1;
+
+pub struct RwLock<T: ?Sized> {
+    state: AtomicU32,
+    waiting: AtomicU32, // number of threads blocked (or about to block) on `state`
+    data: UnsafeCell<T>,
+}
+
+unsafe impl<T: ?Sized + Send> Send for RwLock<T> {}
+unsafe impl<T: ?Sized + Send + Sync> Sync for RwLock<T> {}
+
+pub struct RwLockReadGuard<'a, T: ?Sized> {
+    lock: &'a RwLock<T>,
+}
+
+pub struct RwLockWriteGuard<'a, T: ?Sized> {
+    lock: &'a RwLock<T>,
+}
+
+unsafe impl<T: ?Sized + Sync> Sync for RwLockReadGuard<'_, T> {}
+unsafe impl<T: ?Sized + Sync> Sync for RwLockWriteGuard<'_, T> {}
+
+impl<T> RwLock<T> {
+    pub const fn new(data: T) -> Self {
+        Self { state: AtomicU32::new(0), waiting: AtomicU32::new(0), data: UnsafeCell::new(data) }
+    }
+
+    pub fn into_inner(self) -> T {
+        self.data.into_inner()
+    }
+}
+
+impl<T: ?Sized> RwLock<T> {
+    /// Lock for reading, blocking the calling thread while the lock is held by a writer
+    pub fn read(&self) -> RwLockReadGuard<'_, T> {
+        loop {
+            if let Some(guard) = self.try_read() {
+                return guard;
+            }
+
+            let state = self.state.load(Relaxed);
+            if state >= MAX_READERS {
+                self.wait(state);
+            }
+        }
+    }
+
+    /// Lock for reading, if the lock is not held by a writer
+    pub fn try_read(&self) -> Option<RwLockReadGuard<'_, T>> {
+        self.state.fetch_update(Acquire, Relaxed, |state| (state < MAX_READERS).then_some(state + 1)).ok()
+            .map(|_| RwLockReadGuard { lock: self })
+    }
+
+    /// Lock for writing, blocking the calling thread while the lock is held by readers or another writer
+    pub fn write(&self) -> RwLockWriteGuard<'_, T> {
+        loop {
+            if let Some(guard) = self.try_write() {
+                return guard;
+            }
+
+            let state = self.state.load(Relaxed);
+            if state != 0 {
+                self.wait(state);
+            }
+        }
+    }
+
+    /// Lock for writing, if the lock is not held by anybody
+    pub fn try_write(&self) -> Option<RwLockWriteGuard<'_, T>> {
+        self.state.compare_exchange(0, WRITE_LOCKED, Acquire, Relaxed).ok()
+            .map(|_| RwLockWriteGuard { lock: self })
+    }
+
+    /// Access the data without locking (the mutable reference guarantees, that nobody else holds the lock)
+    pub fn get_mut(&mut self) -> &mut T {
+        self.data.get_mut()
+    }
+
+    /// Block the calling thread, while the lock still has `state`
+    fn wait(&self, state: u32) {
+        self.waiting.fetch_add(1, SeqCst);
+        futex::wait(&self.state, state);
+        self.waiting.fetch_sub(1, SeqCst);
+    }
+
+    fn read_unlock(&self) {
+        // Only writers wait for readers
+        if self.state.fetch_sub(1, SeqCst) == 1 && self.waiting.load(SeqCst) > 0 {
+            futex::wake_all(&self.state);
+        }
+    }
+
+    fn write_unlock(&self) {
+        self.state.store(0, SeqCst);
+        if self.waiting.load(SeqCst) > 0 {
+            futex::wake_all(&self.state);
+        }
+    }
+}
+
+impl<T: Default> Default for RwLock<T> {
+    fn default() -> Self {
+        Self::new(T::default())
+    }
+}
+
+impl<T: ?Sized + fmt::Debug> fmt::Debug for RwLock<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.try_read() {
+            Some(guard) => f.debug_struct("RwLock").field("data", &&*guard).finish(),
+            None => f.write_str("RwLock { <locked> }"),
+        }
+    }
+}
+
+impl<T: ?Sized> Deref for RwLockReadGuard<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        unsafe { &*self.lock.data.get() }
+    }
+}
+
+impl<T: ?Sized> Drop for RwLockReadGuard<'_, T> {
+    fn drop(&mut self) {
+        self.lock.read_unlock();
+    }
+}
+
+impl<T: ?Sized> Deref for RwLockWriteGuard<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        unsafe { &*self.lock.data.get() }
+    }
+}
+
+impl<T: ?Sized> DerefMut for RwLockWriteGuard<'_, T> {
+    fn deref_mut(&mut self) -> &mut T {
+        unsafe { &mut *self.lock.data.get() }
+    }
+}
+
+impl<T: ?Sized> Drop for RwLockWriteGuard<'_, T> {
+    fn drop(&mut self) {
+        self.lock.write_unlock();
+    }
+}
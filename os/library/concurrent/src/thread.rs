@@ -6,9 +6,16 @@
    ║ Author: Fabian Ruhland, Michael Schoettner, 31.8.2024, HHU              ║
    ╚═════════════════════════════════════════════════════════════════════════╝
 */
+use alloc::boxed::Box;
+use alloc::sync::Arc;
 use alloc::vec::Vec;
 use core::ptr;
+use syscall::return_vals::Errno;
 use syscall::{syscall, SystemCall};
+use crate::process::Process;
+use crate::sync::Mutex;
+
+pub use syscall::priority::{DEFAULT_PRIORITY, MAX_PRIORITY, MIN_PRIORITY};
 
 pub struct Thread {
     id: usize,
@@ -26,22 +33,106 @@ impl Thread {
     pub fn join(&self) {
         let _ = syscall(SystemCall::ThreadJoin, &[self.id]);
     }
+
+    /// Return the priority of the thread (must belong to the calling process)
+    pub fn priority(&self) -> Result<usize, Errno> {
+        syscall(SystemCall::GetPriority, &[self.id])
+    }
+
+    /// Set the priority of the thread (`MIN_PRIORITY..=MAX_PRIORITY`, higher value means higher priority). \
+    /// The thread must belong to the calling process.
+    pub fn set_priority(&self, priority: usize) -> Result<(), Errno> {
+        syscall(SystemCall::SetPriority, &[self.id, priority])?;
+        Ok(())
+    }
+}
+
+/// Handle for a thread created by `spawn`, which allows to retrieve the return value of its closure
+pub struct JoinHandle<T> {
+    thread: Thread,
+    result: Arc<Mutex<Option<T>>>,
 }
 
-fn kickoff_user_thread(entry: fn()) {
+impl<T> JoinHandle<T> {
+    pub fn thread(&self) -> &Thread {
+        &self.thread
+    }
+
+    /// Wait for the thread to terminate and return the value returned by its closure
+    /// (`None`, if the thread has been killed before its closure has returned)
+    pub fn join(self) -> Option<T> {
+        self.thread.join();
+        self.result.lock().take()
+    }
+}
+
+/// Closure of a thread created by `spawn` (boxed twice, so it can be passed as thin pointer in a register)
+type ThreadClosure = Box<dyn FnOnce() + Send>;
+
+pub(crate) fn kickoff_user_thread(entry: fn()) {
     entry();
     exit();
 }
 
+/// Like `kickoff_user_thread`, but passing `arg` (second parameter of `ThreadCreate`) to `entry`
+fn kickoff_user_thread_with_arg(entry: fn(usize), arg: usize) {
+    entry(arg);
+    exit();
+}
+
+/// Entry function for threads created by `spawn`, with `closure` being a pointer to a boxed `ThreadClosure`
+fn run_closure(closure: usize) {
+    let closure = unsafe { Box::from_raw(closure as *mut ThreadClosure) };
+    closure();
+}
+
 pub fn create(entry: fn()) -> Option<Thread> {
     let res = syscall(SystemCall::ThreadCreate, &[kickoff_user_thread as usize,
-        entry as usize,]);
+        entry as usize,
+        0,]);
     match res {
         Ok(id) => Some(Thread::new(id)),
         Err(_) => None,
     }    
 }
 
+/// Create a thread executing the closure `f`. The value returned by `f` can be retrieved with `JoinHandle::join`.
+pub fn spawn<F, T>(f: F) -> Option<JoinHandle<T>>
+where
+    F: FnOnce() -> T + Send + 'static,
+    T: Send + 'static,
+{
+    spawn_with_stack_size(0, f)
+}
+
+/// Like `spawn`, but the stack of the new thread may grow to at most `stack_size` bytes
+/// (0 -> default size, see `ThreadCreate`). A thread overflowing its stack terminates the process.
+pub fn spawn_with_stack_size<F, T>(stack_size: usize, f: F) -> Option<JoinHandle<T>>
+where
+    F: FnOnce() -> T + Send + 'static,
+    T: Send + 'static,
+{
+    let result = Arc::new(Mutex::new(None));
+    let thread_result = Arc::clone(&result);
+    let closure: ThreadClosure = Box::new(move || {
+        *thread_result.lock() = Some(f());
+    });
+    let closure = Box::into_raw(Box::new(closure));
+
+    let res = syscall(SystemCall::ThreadCreate, &[kickoff_user_thread_with_arg as usize,
+        run_closure as usize,
+        closure as usize,
+        stack_size,]);
+    match res {
+        Ok(id) => Some(JoinHandle { thread: Thread::new(id), result }),
+        Err(_) => {
+            // The thread has not been created, so the closure is still owned by us
+            drop(unsafe { Box::from_raw(closure) });
+            None
+        }
+    }
+}
+
 pub fn current() -> Option<Thread> {
     let res = syscall(SystemCall::ThreadId, &[]);
     match res {
@@ -65,12 +156,100 @@ pub fn exit() -> ! {
     panic!("System call 'ThreadExit' has returned!")
 }
 
-pub fn start_application(name: &str, args: Vec<&str>) -> Option<Thread> {
+/// Serialize the arguments (`name` followed by `args`) and environment variables (`KEY=VALUE`)
+/// into null-terminated strings, as expected by `ProcessExecuteBinary`
+fn serialize_exec_args(name: &str, args: &[&str], env: &[&str]) -> Vec<u8> {
+    let mut exec_args = Vec::new();
+    for string in [name].iter().chain(args.iter()).chain(env.iter()) {
+        exec_args.extend_from_slice(string.as_bytes());
+        exec_args.push(0);
+    }
+    exec_args
+}
+
+/// Start the application `name` with `args` and the environment variables `env` (`KEY=VALUE`) as child process. \
+/// The new process inherits stdin, stdout and stderr of the calling process.
+pub fn start_application(name: &str, args: Vec<&str>, env: Vec<&str>) -> Option<Process> {
+    let exec_args = serialize_exec_args(name, &args, &env);
     let res = syscall(SystemCall::ProcessExecuteBinary, &[name.as_bytes().as_ptr() as usize,
     name.len(),
-    ptr::from_ref(&args) as usize,]);
+    exec_args.as_ptr() as usize,
+    exec_args.len(),
+    args.len() + 1,
+    ptr::null::<[usize; 3]>() as usize,]);
     match res {
-        Ok(id) => Some(Thread::new(id)),
+        Ok(id) => Some(Process::new(id)),
+        Err(_) => None,
+    }    
+}
+
+/// Start the application `name` like `start_application`, but using the objects referred by `handles`
+/// (of the calling process) as stdin, stdout and stderr
+pub fn start_application_with_handles(name: &str, args: Vec<&str>, env: Vec<&str>, handles: [usize; 3]) -> Option<Process> {
+    let exec_args = serialize_exec_args(name, &args, &env);
+    let res = syscall(SystemCall::ProcessExecuteBinary, &[name.as_bytes().as_ptr() as usize,
+    name.len(),
+    exec_args.as_ptr() as usize,
+    exec_args.len(),
+    args.len() + 1,
+    ptr::from_ref(&handles) as usize,]);
+    match res {
+        Ok(id) => Some(Process::new(id)),
         Err(_) => None,
     }    
 }
+
+/// Key for accessing a thread local value, declared with `thread_local!`. \
+/// Each thread has its own copy of the value, which is initialized from the TLS template of the application,
+/// when the thread is created (so the initializer must be a constant expression). Values are not dropped,
+/// when a thread exits.
+pub struct LocalKey<T: 'static> {
+    value: fn() -> *const T,
+}
+
+impl<T: 'static> LocalKey<T> {
+    #[doc(hidden)]
+    pub const fn new(value: fn() -> *const T) -> Self {
+        Self { value }
+    }
+
+    /// Call `f` with a reference to the value of the calling thread
+    pub fn with<R>(&'static self, f: impl FnOnce(&T) -> R) -> R {
+        // The value lives as long as the calling thread, which cannot exit while executing `f`
+        let value = unsafe { &*(self.value)() };
+        f(value)
+    }
+}
+
+/// Declare thread local statics with a `LocalKey` for each of them (like `std::thread_local!`).
+///
+/// ```ignore
+/// thread_local! {
+///     static COUNTER: Cell<usize> = Cell::new(0);
+/// }
+///
+/// COUNTER.with(|counter| counter.set(counter.get() + 1));
+/// ```
+#[macro_export]
+#[allow_internal_unstable(thread_local)]
+macro_rules! thread_local {
+    () => {};
+
+    ($(#[$attr:meta])* $vis:vis static $name:ident: $t:ty = $init:expr; $($rest:tt)*) => {
+        $crate::thread_local!($(#[$attr])* $vis static $name: $t = $init);
+        $crate::thread_local!($($rest)*);
+    };
+
+    ($(#[$attr:meta])* $vis:vis static $name:ident: $t:ty = $init:expr) => {
+        $(#[$attr])* $vis static $name: $crate::thread::LocalKey<$t> = {
+            #[thread_local]
+            static VALUE: $t = $init;
+
+            fn value() -> *const $t {
+                &raw const VALUE
+            }
+
+            $crate::thread::LocalKey::new(value)
+        };
+    };
+}
@@ -10,10 +10,16 @@
 
 use stream::strlen;
 use syscall::{syscall, SystemCall};
+use terminal::STDOUT;
 
 #[unsafe(no_mangle)]
 pub unsafe extern "C" fn terminal_write(buffer: *const u8) {
-    let res = syscall(SystemCall::TerminalWrite, &[buffer as usize, unsafe { strlen(buffer) }]);
+    let length = unsafe { strlen(buffer) };
+    if length == 0 {
+        return; // 'Write' syscall rejects empty buffers
+    }
+
+    let res = syscall(SystemCall::Write, &[STDOUT, buffer as usize, length]);
     if res.is_err() {
         panic!("Error while writing to the terminal!");
     }
@@ -131,4 +131,30 @@ pub fn cd(path: &str) -> Result<usize, Errno> {
         Ok(c_path) => syscall(SystemCall::Cd, &[c_path.as_bytes().as_ptr() as usize]),
         Err(_) => Err(Errno::EBADSTR),
     }
-}
\ No newline at end of file
+}
+
+/// Mount a file system of type `fs_type` (e.g. `fat32` or `tmpfs`) from device `source` on directory `target`
+pub fn mount(source: &str, target: &str, fs_type: &str) -> Result<usize, Errno> {
+    match (CString::new(source), CString::new(target), CString::new(fs_type)) {
+        (Ok(c_source), Ok(c_target), Ok(c_fs_type)) => syscall(SystemCall::Mount, &[
+            c_source.as_bytes().as_ptr() as usize,
+            c_target.as_bytes().as_ptr() as usize,
+            c_fs_type.as_bytes().as_ptr() as usize,
+        ]),
+        _ => Err(Errno::EBADSTR),
+    }
+}
+
+/// Unmount the file system mounted on directory `target`
+pub fn umount(target: &str) -> Result<usize, Errno> {
+    match CString::new(target) {
+        Ok(c_target) => syscall(SystemCall::Umount, &[c_target.as_bytes().as_ptr() as usize]),
+        Err(_) => Err(Errno::EBADSTR),
+    }
+}
+
+/// Create a pipe and return the handles `(read end, write end)`
+pub fn pipe() -> Result<(usize, usize), Errno> {
+    let mut handles = [0usize; 2];
+    syscall(SystemCall::Pipe, &[handles.as_mut_ptr() as usize]).map(|_| (handles[0], handles[1]))
+}
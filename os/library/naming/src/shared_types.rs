@@ -17,6 +17,7 @@ bitflags! {
         const CREATE    = 3;
         const EXCLUSIVE = 4;
         const DIRECTORY = 5;
+        const TRUNCATE  = 8;
     }
 }
 
@@ -1,17 +1,11 @@
 use alloc::string::{String, ToString};
+use alloc::vec::{self, Vec};
 use core::ffi::CStr;
-use core::ptr::slice_from_raw_parts;
+use core::ptr::{self, slice_from_raw_parts};
+use core::sync::atomic::{AtomicPtr, AtomicUsize, Ordering};
+use spin::Mutex;
 use stream::strlen;
 
-// Duplicated from 'kernel/src/consts.rs'
-const USER_SPACE_START: usize = 0x10000000000;
-const USER_SPACE_CODE_START: usize = USER_SPACE_START;
-const USER_SPACE_ENV_START: usize = USER_SPACE_CODE_START + 0x40000000;
-const USER_SPACE_ARG_START: usize = USER_SPACE_ENV_START;
-
-pub(crate) const ARGC_PTR: *const usize = USER_SPACE_ARG_START as *const usize;
-pub(crate) const ARGV_PTR: *const *const u8 = (USER_SPACE_ARG_START + size_of::<*const usize>()) as *const *const u8;
-
 /// The heap can be as large as 1 TB, but only a tiny fraction (1 MB) is mapped
 /// at the beginning. Additional chunks will be mapped as needed, but userspace
 /// doesn't really notice.
@@ -20,6 +14,46 @@ pub(crate) const ARGV_PTR: *const *const u8 = (USER_SPACE_ARG_START + size_of::<
 pub(crate) const HEAP_START: usize = 63 * 1024 * 1024 * 1024 * 1024;
 pub(crate) const HEAP_SIZE: usize = 1024 * 1024 * 1024 * 1024;
 
+static ARGC: AtomicUsize = AtomicUsize::new(0);
+static ARGV: AtomicPtr<*const u8> = AtomicPtr::new(ptr::null_mut());
+
+/// Environment variables as (key, value) pairs, in the order they have been defined
+static VARS: Mutex<Vec<(String, String)>> = Mutex::new(Vec::new());
+
+/// Remember `argv` and copy the environment variables from `envp` (called once at startup, the heap must be ready)
+pub(crate) unsafe fn init(argc: usize, argv: *const *const u8, envp: *const *const u8) {
+    ARGC.store(argc, Ordering::Relaxed);
+    ARGV.store(argv as *mut *const u8, Ordering::Relaxed);
+
+    let mut vars = VARS.lock();
+    let mut index = 0;
+    loop {
+        let entry = unsafe { *envp.add(index) };
+        if entry.is_null() {
+            break;
+        }
+        index += 1;
+
+        // Entries without '=' are ignored, later definitions replace earlier ones
+        let Some(entry) = (unsafe { to_string(entry) }) else { continue };
+        if let Some((key, value)) = entry.split_once('=') {
+            vars.retain(|(k, _)| k != key);
+            vars.push((key.to_string(), value.to_string()));
+        }
+    }
+}
+
+/// Helper function converting a null-terminated string into a `String`
+unsafe fn to_string(string: *const u8) -> Option<String> {
+    unsafe {
+        let len = strlen(string);
+        CStr::from_bytes_with_nul(slice_from_raw_parts(string, len + 1).as_ref()?)
+            .ok()
+            .and_then(|cstr| cstr.to_str().ok())
+            .map(|str| str.to_string())
+    }
+}
+
 pub fn args() -> Args {
     Args::new()
 }
@@ -38,19 +72,50 @@ impl Iterator for Args {
     type Item = String;
 
     fn next(&mut self) -> Option<Self::Item> {
-        unsafe {
-            let argc = *ARGC_PTR;
-            if self.index >= argc {
-                return None;
-            }
-
-            let arg = *ARGV_PTR.add(self.index);
-            let len = strlen(arg);
-            self.index += 1;
-
-            CStr::from_bytes_with_nul(slice_from_raw_parts(arg, len + 1).as_ref()?)
-                .map(|cstr| cstr.to_str().expect("Invalid UTF-8 in argument").to_string())
-                .ok()
+        if self.index >= ARGC.load(Ordering::Relaxed) {
+            return None;
         }
+
+        let arg = unsafe { *ARGV.load(Ordering::Relaxed).add(self.index) };
+        self.index += 1;
+        Some(unsafe { to_string(arg) }.expect("Invalid UTF-8 in argument"))
     }
-}
\ No newline at end of file
+}
+
+/// Return a snapshot of all environment variables as (key, value) pairs
+pub fn vars() -> Vars {
+    Vars { inner: VARS.lock().clone().into_iter() }
+}
+
+pub struct Vars {
+    inner: vec::IntoIter<(String, String)>,
+}
+
+impl Iterator for Vars {
+    type Item = (String, String);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner.next()
+    }
+}
+
+/// Return the value of the environment variable `key`
+pub fn var(key: &str) -> Option<String> {
+    VARS.lock()
+        .iter()
+        .find(|(k, _)| k == key)
+        .map(|(_, value)| value.clone())
+}
+
+/// Set the environment variable `key` to `value` (it is created, if it does not exist). \
+/// Keys must not be empty and must not contain '=' or '\0', values must not contain '\0'.
+pub fn set_var(key: &str, value: &str) {
+    assert!(!key.is_empty() && !key.contains(['=', '\0']), "Invalid environment variable name '{}'", key);
+    assert!(!value.contains('\0'), "Invalid value for environment variable '{}'", key);
+
+    let mut vars = VARS.lock();
+    match vars.iter_mut().find(|(k, _)| k == key) {
+        Some((_, v)) => *v = value.to_string(),
+        None => vars.push((key.to_string(), value.to_string())),
+    }
+}
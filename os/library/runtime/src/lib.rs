@@ -10,28 +10,50 @@
 extern crate alloc;
 
 pub mod env;
+pub mod mman;
+pub mod meminfo;
 
-use concurrent::{process, thread};
+use concurrent::process;
+use core::arch::naked_asm;
+use core::ffi::c_int;
 use core::panic::PanicInfo;
 use terminal::{print, println};
 use linked_list_allocator::LockedHeap;
 use syscall::{syscall, SystemCall};
 
 unsafe extern "C" {
-    fn main(argc: isize, argv: *const *const u8) -> isize;
+    /// Main function of the application. C applications define `int main(int argc, char *argv[])`,
+    /// so only the lower 32 bits of the return value are defined (exit statuses of Rust applications must fit as well).
+    fn main(argc: c_int, argv: *const *const u8) -> c_int;
 }
 
 #[global_allocator]
 static ALLOCATOR: LockedHeap = LockedHeap::empty();
 
+/// Exit status of a process terminated by a panic (same as in Rust std)
+const PANIC_EXIT_STATUS: isize = 101;
+
 #[panic_handler]
 fn panic(info: &PanicInfo) -> ! {
     println!("Panic: {}!", info);
-    thread::exit();
+    process::exit(PANIC_EXIT_STATUS);
 }
 
+/// Entry point of an application (see `link.ld`). \
+/// The kernel places argc, argv and envp on the stack (System V ABI), so the initial stack pointer is passed to `start()`.
+#[unsafe(naked)]
 #[unsafe(no_mangle)]
-extern "C" fn entry() {
+unsafe extern "C" fn entry() {
+    naked_asm!(
+        "mov rdi, rsp", // First parameter for 'start()' -> pointer to argc
+        "and rsp, -16", // Make sure the stack is aligned (it should already be)
+        "call {start}",
+        "ud2",          // 'start()' never returns
+        start = sym start,
+    )
+}
+
+extern "C" fn start(initial_stack: *const usize) {
     syscall(SystemCall::MapMemory, &[env::HEAP_START, env::HEAP_SIZE])
         .expect("Could not create user heap.");
 
@@ -39,8 +61,12 @@ extern "C" fn entry() {
         ALLOCATOR.lock().init(env::HEAP_START as *mut u8, env::HEAP_SIZE);
     }
 
-    unsafe {
-        main(*env::ARGC_PTR as isize, env::ARGV_PTR);
-    }
-    process::exit();
+    let argc = unsafe { *initial_stack };
+    let argv = unsafe { initial_stack.add(1) } as *const *const u8;
+    let envp = unsafe { argv.add(argc + 1) };
+    let status = unsafe {
+        env::init(argc, argv, envp);
+        main(argc as c_int, argv)
+    };
+    process::exit(status as isize); // sign-extend the 32 bit status
 }
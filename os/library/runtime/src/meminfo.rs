@@ -0,0 +1,20 @@
+/* ╔═════════════════════════════════════════════════════════════════════════╗
+   ║ Module: meminfo                                                         ║
+   ╟─────────────────────────────────────────────────────────────────────────╢
+   ║ Descr.: Querying the usage of physical memory.                          ║
+   ╟─────────────────────────────────────────────────────────────────────────╢
+   ║ Author: agent, 18.10.2026                                               ║
+   ╚═════════════════════════════════════════════════════════════════════════╝
+*/
+use core::ptr;
+use syscall::return_vals::Errno;
+use syscall::{syscall, SystemCall};
+
+pub use syscall::meminfo::{MemInfo, Zone, ZoneInfo, NUM_ORDERS, NUM_ZONES};
+
+/// Return the statistics of the page frame allocator (total, free and largest free block per zone)
+pub fn mem_info() -> Result<MemInfo, Errno> {
+    let mut info = MemInfo::default();
+    syscall(SystemCall::MemInfo, &[ptr::from_mut(&mut info) as usize])?;
+    Ok(info)
+}
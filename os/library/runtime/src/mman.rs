@@ -0,0 +1,34 @@
+/* ╔═════════════════════════════════════════════════════════════════════════╗
+   ║ Module: mman                                                            ║
+   ╟─────────────────────────────────────────────────────────────────────────╢
+   ║ Descr.: Mapping memory into the address space of the application and    ║
+   ║         changing its access rights.                                     ║
+   ╟─────────────────────────────────────────────────────────────────────────╢
+   ║ Author: agent, 18.10.2026                                               ║
+   ╚═════════════════════════════════════════════════════════════════════════╝
+*/
+use syscall::return_vals::Errno;
+use syscall::{syscall, SystemCall};
+
+pub use syscall::mman::{MapFlags, Protection};
+
+/// Map `length` bytes with the access rights `prot` and return the start address. \
+/// The memory is zero filled (`MapFlags::ANONYMOUS`, `handle` and `offset` are ignored)
+/// or a private copy of the file `handle` (opened with the naming service) from `offset` (page aligned). \
+/// `address` is a hint for the placement (0 -> chosen by the kernel), unless `MapFlags::FIXED` is given.
+pub fn mmap(address: usize, length: usize, prot: Protection, flags: MapFlags, handle: usize, offset: usize) -> Result<*mut u8, Errno> {
+    let address = syscall(SystemCall::Mmap, &[address, length, prot.bits(), flags.bits(), handle, offset])?;
+    Ok(address as *mut u8)
+}
+
+/// Unmap `length` bytes from the page aligned `address` (pages, which are not mapped, are ignored)
+pub fn munmap(address: *mut u8, length: usize) -> Result<(), Errno> {
+    syscall(SystemCall::Munmap, &[address as usize, length])?;
+    Ok(())
+}
+
+/// Change the access rights of `length` bytes from the page aligned `address` to `prot` (the whole range must be mapped)
+pub fn mprotect(address: *mut u8, length: usize, prot: Protection) -> Result<(), Errno> {
+    syscall(SystemCall::Mprotect, &[address as usize, length, prot.bits()])?;
+    Ok(())
+}
@@ -9,6 +9,10 @@
 #![no_std]
 
 pub mod return_vals;
+pub mod signal;
+pub mod priority;
+pub mod mman;
+pub mod meminfo;
 
 use core::arch::asm;
 use return_vals::{SyscallResult, convert_ret_code_to_syscall_result};
@@ -42,6 +46,21 @@ pub enum SystemCall {
     Readdir,
     Cwd,
     Cd,
+    Mount,
+    Umount,
+    Pipe,
+    WaitPid,
+    Kill,
+    SignalHandler,
+    Fork,
+    SetPriority,
+    GetPriority,
+    FutexWait,
+    FutexWake,
+    Mmap,
+    Munmap,
+    Mprotect,
+    MemInfo,
     // no syscall, just marking last number, see NUM_SYSCALLS
     // insert any new system calls before this marker
     LastEntryMarker,
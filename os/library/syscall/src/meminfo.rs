@@ -0,0 +1,63 @@
+/* ╔═════════════════════════════════════════════════════════════════════════╗
+   ║ Module: meminfo                                                         ║
+   ╟─────────────────────────────────────────────────────────────────────────╢
+   ║ Descr.: Statistics of the page frame allocator, returned by the system  ║
+   ║         call `MemInfo`.                                                 ║
+   ╟─────────────────────────────────────────────────────────────────────────╢
+   ║ Author: agent, 18.10.2026                                               ║
+   ╚═════════════════════════════════════════════════════════════════════════╝
+*/
+
+/// Number of block sizes of the buddy allocator (order `n` -> 2^n page frames, largest block: 4 GiB)
+pub const NUM_ORDERS: usize = 21;
+
+/// Number of zones of physical memory (see `Zone`)
+pub const NUM_ZONES: usize = 2;
+
+/// Zones of physical memory, each managed by its own free lists
+#[repr(usize)]
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Zone {
+    Dma32 = 0,  // below 4 GiB (reachable by devices using 32 bit DMA addresses)
+    Normal = 1, // 4 GiB and above
+}
+
+impl Zone {
+    pub const ALL: [Zone; NUM_ZONES] = [Zone::Dma32, Zone::Normal];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Zone::Dma32 => "DMA32",
+            Zone::Normal => "Normal",
+        }
+    }
+}
+
+/// Page frame statistics of one zone (all sizes are given in page frames)
+#[repr(C)]
+#[derive(Clone, Copy, Default, Debug)]
+pub struct ZoneInfo {
+    pub total_frames: usize,              // usable frames (without reserved regions)
+    pub free_frames: usize,
+    pub largest_free_block: usize,        // limit for contiguous allocations
+    pub free_blocks: [usize; NUM_ORDERS], // number of free blocks per order
+}
+
+impl ZoneInfo {
+    pub fn used_frames(&self) -> usize {
+        self.total_frames - self.free_frames
+    }
+}
+
+/// Page frame statistics of all zones (indexed by `Zone`)
+#[repr(C)]
+#[derive(Clone, Copy, Default, Debug)]
+pub struct MemInfo {
+    pub zones: [ZoneInfo; NUM_ZONES],
+}
+
+impl MemInfo {
+    pub fn zone(&self, zone: Zone) -> &ZoneInfo {
+        &self.zones[zone as usize]
+    }
+}
@@ -0,0 +1,28 @@
+/* ╔═════════════════════════════════════════════════════════════════════════╗
+   ║ Module: mman                                                            ║
+   ╟─────────────────────────────────────────────────────────────────────────╢
+   ║ Descr.: Flags for the memory mapping system calls `Mmap`, `Munmap` and  ║
+   ║         `Mprotect`.                                                     ║
+   ╟─────────────────────────────────────────────────────────────────────────╢
+   ║ Author: agent, 18.10.2026                                               ║
+   ╚═════════════════════════════════════════════════════════════════════════╝
+*/
+use bitflags::bitflags;
+
+bitflags! {
+    /// Access rights for mapped memory (no flag set -> any access causes a page fault). \
+    /// `WRITE` and `EXEC` must not be combined (W^X).
+    pub struct Protection: usize {
+        const READ  = 1;
+        const WRITE = 2; // implies `READ`
+        const EXEC  = 4;
+    }
+}
+
+bitflags! {
+    /// Options for `Mmap`
+    pub struct MapFlags: usize {
+        const ANONYMOUS = 1; // zero filled memory, not backed by a file
+        const FIXED     = 2; // map exactly at the given address (otherwise it is only a hint)
+    }
+}
@@ -0,0 +1,21 @@
+/* ╔═════════════════════════════════════════════════════════════════════════╗
+   ║ Module: priority                                                        ║
+   ╟─────────────────────────────────────────────────────────────────────────╢
+   ║ Descr.: Thread priorities used by the scheduler (higher value means     ║
+   ║         higher priority).                                               ║
+   ╟─────────────────────────────────────────────────────────────────────────╢
+   ║ Author: agent, 18.10.2026                                               ║
+   ╚═════════════════════════════════════════════════════════════════════════╝
+*/
+
+/// Number of priorities (valid priorities are `MIN_PRIORITY..=MAX_PRIORITY`)
+pub const NUM_PRIORITIES: usize = 8;
+
+/// Lowest priority (e.g. for polling background threads)
+pub const MIN_PRIORITY: usize = 0;
+
+/// Highest priority
+pub const MAX_PRIORITY: usize = NUM_PRIORITIES - 1;
+
+/// Priority of new threads
+pub const DEFAULT_PRIORITY: usize = 4;
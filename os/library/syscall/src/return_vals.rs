@@ -25,6 +25,19 @@ pub enum Errno {
     EINVALH    = -9,  // Invalid handle
     ENOTEMPTY  = -10, // Directory not empty
     EBADSTR    = -11, // Bad string
+    EIO        = -12, // Input/output error
+    ENOSPC     = -13, // No space left on device
+    EBUSY      = -14, // Device or resource busy
+    ENODEV     = -15, // No such device
+    EROFS      = -16, // Read-only file system
+    ENOEXEC    = -17, // Exec format error
+    EPIPE      = -18, // Broken pipe
+    ECHILD     = -19, // No child process
+    ESRCH      = -20, // No such process
+    EAGAIN     = -21, // Resource temporarily unavailable
+    ENOMEM     = -22, // Not enough memory (or no free range in the address space)
+    EFAULT     = -23, // Bad address (memory passed to a system call is not accessible)
+    EINTR      = -24, // Interrupted system call
 }
 
 
@@ -0,0 +1,37 @@
+/* ╔═════════════════════════════════════════════════════════════════════════╗
+   ║ Module: signal                                                          ║
+   ╟─────────────────────────────────────────────────────────────────────────╢
+   ║ Descr.: Signals, which can be sent to processes (POSIX numbering).      ║
+   ╟─────────────────────────────────────────────────────────────────────────╢
+   ║ Author: agent, 18.10.2026                                               ║
+   ╚═════════════════════════════════════════════════════════════════════════╝
+*/
+
+use num_enum::{IntoPrimitive, TryFromPrimitive};
+
+/// Signal numbers are below this value (size of signal handler tables)
+pub const NUM_SIGNALS: usize = 16;
+
+/// Description: supported signals. \
+///    The default action of all signals is terminating the process. \
+///    `SIGKILL` cannot be handled by the process. \
+///    A process causing a CPU exception is terminated with `SIGILL`, `SIGTRAP`, `SIGFPE` or `SIGSEGV`
+///    by the kernel, without calling a handler (these signals are only handled, if sent by another process).
+#[derive(Debug, Copy, Clone, Eq, PartialEq, IntoPrimitive, TryFromPrimitive)]
+#[repr(usize)]
+pub enum Signal {
+    SIGINT  = 2,  // Interrupt (Ctrl-C)
+    SIGILL  = 4,  // Illegal instruction
+    SIGTRAP = 5,  // Breakpoint or debug exception
+    SIGFPE  = 8,  // Arithmetic error (e.g. division by zero)
+    SIGKILL = 9,  // Kill (cannot be handled)
+    SIGSEGV = 11, // Invalid memory access
+    SIGTERM = 15, // Termination request
+}
+
+impl Signal {
+    /// Exit status of a process terminated by this signal (like in Unix shells)
+    pub const fn exit_status(self) -> isize {
+        128 + self as isize
+    }
+}
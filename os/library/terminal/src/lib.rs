@@ -1,4 +1,9 @@
 #![no_std]
 
 pub mod write;
-pub mod read;
\ No newline at end of file
+pub mod read;
+
+/// Standard handles of each process (refer to the terminal, unless redirected)
+pub const STDIN: usize = 0;
+pub const STDOUT: usize = 1;
+pub const STDERR: usize = 2;
@@ -1,17 +1,21 @@
 /* ╔═════════════════════════════════════════════════════════════════════════╗
    ║ Module: read                                                            ║
    ╟─────────────────────────────────────────────────────────────────────────╢
-   ║ Descr.: Read a input char from terminal.                                ║
+   ║ Descr.: Read a input char from stdin (usually the terminal).            ║
    ╟─────────────────────────────────────────────────────────────────────────╢
    ║ Author: Fabian Ruhland, 31.8.2024, HHU                                  ║
    ╚═════════════════════════════════════════════════════════════════════════╝
 */
 use syscall::{syscall, SystemCall};
+use crate::STDIN;
 
+/// Read the next byte from stdin. \
+/// Returns `None` at the end of the input (e.g. redirected file) or on error.
 pub fn read() -> Option<char> {
-    let res = syscall(SystemCall::TerminalRead, &[]);
+    let mut buf = [0u8; 1];
+    let res = syscall(SystemCall::Read, &[STDIN, buf.as_mut_ptr() as usize, buf.len()]);
     match res {
-        Ok(ch) => Some(char::from_u32(ch as u32).unwrap()),
-        Err(_) => None,
-    }    
-}
\ No newline at end of file
+        Ok(1) => Some(buf[0] as char),
+        _ => None,
+    }
+}
@@ -1,7 +1,7 @@
 /* ╔═════════════════════════════════════════════════════════════════════════╗
    ║ Module: write                                                           ║
    ╟─────────────────────────────────────────────────────────────────────────╢
-   ║ Descr.: Write a char to the terminal.                                   ║
+   ║ Descr.: Write to stdout and stderr (usually the terminal).              ║
    ╟─────────────────────────────────────────────────────────────────────────╢
    ║ Author: Fabian Ruhland, 31.8.2024, HHU                                  ║
    ╚═════════════════════════════════════════════════════════════════════════╝
@@ -10,6 +10,7 @@ use core::fmt;
 use core::fmt::Write;
 use spin::Mutex;
 use syscall::{syscall, SystemCall};
+use crate::{STDERR, STDOUT};
 
 #[macro_export]
 macro_rules! print {
@@ -24,29 +25,55 @@ macro_rules! println {
     ($fmt:expr, $($arg:tt)*) => (print!(concat!($fmt, "\n"), $($arg)*));
 }
 
-static WRITER: Mutex<Writer> = Mutex::new(Writer::new());
+#[macro_export]
+macro_rules! eprint {
+    ($($arg:tt)*) => ({
+        $crate::write::eprint(format_args!($($arg)*));
+    });
+}
+
+#[macro_export]
+macro_rules! eprintln {
+    ($fmt:expr) => (eprint!(concat!($fmt, "\n")));
+    ($fmt:expr, $($arg:tt)*) => (eprint!(concat!($fmt, "\n"), $($arg)*));
+}
+
+static WRITER: Mutex<Writer> = Mutex::new(Writer::new(STDOUT));
+static ERROR_WRITER: Mutex<Writer> = Mutex::new(Writer::new(STDERR));
 
 pub fn print(args: fmt::Arguments) {
-    WRITER.lock().write_fmt(args).unwrap();
+    // Output may be redirected into a closed pipe, so errors are ignored (like in Unix without SIGPIPE)
+    let _ = WRITER.lock().write_fmt(args);
 }
 
-struct Writer {}
+pub fn eprint(args: fmt::Arguments) {
+    let _ = ERROR_WRITER.lock().write_fmt(args);
+}
+
+struct Writer {
+    fd: usize,
+}
 
 impl Writer {
-    const fn new() -> Self {
-        Self {}
+    const fn new(fd: usize) -> Self {
+        Self { fd }
     }
 }
 
 impl Write for Writer {
     fn write_str(&mut self, s: &str) -> fmt::Result {
-        let res = syscall(
-            SystemCall::TerminalWrite,
-            &[s.as_bytes().as_ptr() as usize, s.len()],
-        );
-        match res {
-            Ok(_) => Ok(()),
-            Err(_) => Err(fmt::Error),
+        // Files and pipes may accept only a part of the data
+        let mut bytes = s.as_bytes();
+        while !bytes.is_empty() {
+            let res = syscall(
+                SystemCall::Write,
+                &[self.fd, bytes.as_ptr() as usize, bytes.len()],
+            );
+            match res {
+                Ok(0) | Err(_) => return Err(fmt::Error),
+                Ok(written) => bytes = &bytes[written..],
+            }
         }
+        Ok(())
     }
 }